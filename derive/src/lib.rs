@@ -0,0 +1,303 @@
+//! Derive macros for Selective Disclosure JWTs
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, LitStr};
+
+/// Derives `SelectivelyDisclosable` for a struct with named fields, which must also derive
+/// `serde::Serialize`. Fields are visible unless annotated with:
+/// * `#[sd]`: the claim is selectively disclosable
+/// * `#[sd(each)]`: every element of the array is selectively disclosable, not the array itself
+/// * `#[sd(recursive)]`: the claims of the nested struct are selectively disclosable, then the claim itself
+///
+/// `#[serde(rename)]`, `#[serde(rename_all)]`, `#[serde(skip)]` & `#[serde(flatten)]` are honored.
+#[proc_macro_derive(SelectivelyDisclosable, attributes(sd))]
+pub fn derive_selectively_disclosable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "only structs can be selectively disclosable",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            input.span(),
+            "only structs with named fields can be selectively disclosable",
+        ));
+    };
+    let rename_all = container_rename_all(&input)?;
+
+    let krate = quote!(::selective_disclosure_jwt);
+    let mut bounds = vec![];
+    let mut body = vec![];
+    let mut checks = vec![];
+    for field in &fields.named {
+        let serde = SerdeField::try_parse(field)?;
+        if serde.skip {
+            continue;
+        }
+        let sd = SdField::try_parse(field)?;
+        let ty = &field.ty;
+
+        if serde.flatten {
+            if sd.is_annotated() {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "flattened fields can't be selectively disclosable",
+                ));
+            }
+            bounds.push(quote!(#ty: #krate::prelude::SelectivelyDisclosable));
+            body.push(quote! {
+                <#ty as #krate::prelude::SelectivelyDisclosable>::sd_decisions(value, pointer, decisions);
+            });
+            continue;
+        }
+
+        let ident = field.ident.as_ref().expect("named field");
+        let name = match serde.rename {
+            Some(name) => name,
+            None => rename_all.apply(ident.to_string().trim_start_matches("r#")),
+        };
+        // the claim names are checked against the lists of the library, by the compiler
+        let reserved = format!("'{name}' is a reserved claim name");
+        checks.push(quote_spanned! {field.span()=>
+            const _: () = ::std::assert!(!#krate::__private::is_reserved(#name), #reserved);
+        });
+        if sd.is_disclosable() {
+            let always_visible = format!("'{name}' can't be selectively disclosable");
+            checks.push(quote_spanned! {field.span()=>
+                const _: () = ::std::assert!(!#krate::__private::is_always_visible(#name), #always_visible);
+            });
+        }
+        if !sd.is_annotated() {
+            continue;
+        }
+
+        let token = name.replace('~', "~0").replace('/', "~1");
+        let mut decisions = vec![];
+        if sd.recursive {
+            bounds.push(quote!(#ty: #krate::prelude::SelectivelyDisclosable));
+            decisions.push(quote! {
+                <#ty as #krate::prelude::SelectivelyDisclosable>::sd_decisions(claim, &pointer, decisions);
+            });
+        }
+        if sd.each {
+            decisions.push(quote! {
+                if let Some(items) = claim.as_array() {
+                    decisions.extend((0..items.len()).map(|i| format!("{pointer}/{i}")));
+                }
+            });
+        }
+        if sd.is_disclosable() {
+            decisions.push(quote!(decisions.push(pointer);));
+        }
+        body.push(quote! {
+            if let Some(claim) = value.get(#name) {
+                let pointer = format!("{}/{}", pointer, #token);
+                #(#decisions)*
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut predicates = where_clause
+        .map(|w| w.predicates.iter().map(|p| quote!(#p)).collect())
+        .unwrap_or(vec![]);
+    predicates.extend(bounds);
+    Ok(quote! {
+        #(#checks)*
+
+        impl #impl_generics #krate::prelude::SelectivelyDisclosable for #ident #ty_generics
+        where #(#predicates),*
+        {
+            #[allow(unused_variables)]
+            fn sd_decisions(
+                value: &#krate::__private::serde_json::Value,
+                pointer: &str,
+                decisions: &mut ::std::vec::Vec<::std::string::String>,
+            ) {
+                #(#body)*
+            }
+        }
+    })
+}
+
+/// The `#[sd]` attribute of a field
+#[derive(Default)]
+struct SdField {
+    annotated: bool,
+    each: bool,
+    recursive: bool,
+}
+
+impl SdField {
+    fn try_parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut sd = Self::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("sd")) {
+            if sd.annotated {
+                return Err(syn::Error::new(attr.span(), "duplicate #[sd] attribute"));
+            }
+            sd.annotated = true;
+            if matches!(attr.meta, syn::Meta::Path(_)) {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("each") {
+                    sd.each = true;
+                    Ok(())
+                } else if meta.path.is_ident("recursive") {
+                    sd.recursive = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `each` or `recursive`"))
+                }
+            })?;
+        }
+        Ok(sd)
+    }
+
+    fn is_annotated(&self) -> bool {
+        self.annotated
+    }
+
+    /// Whether the claim itself, not only its elements or nested claims, is selectively disclosable
+    fn is_disclosable(&self) -> bool {
+        self.annotated && !self.each
+    }
+}
+
+/// What the `#[serde]` attributes of a field change to its claim
+#[derive(Default)]
+struct SerdeField {
+    rename: Option<String>,
+    skip: bool,
+    flatten: bool,
+}
+
+impl SerdeField {
+    fn try_parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut serde = Self::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if meta.input.peek(syn::Token![=]) {
+                        serde.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                    } else {
+                        meta.parse_nested_meta(|meta| {
+                            if meta.path.is_ident("serialize") {
+                                serde.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                            } else {
+                                meta.value()?.parse::<LitStr>()?;
+                            }
+                            Ok(())
+                        })?;
+                    }
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                    serde.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    serde.flatten = true;
+                } else {
+                    skip_meta(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(serde)
+    }
+}
+
+/// Consumes a serde attribute which doesn't change the claims
+fn skip_meta(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|meta| skip_meta(&meta))?;
+    }
+    Ok(())
+}
+
+/// `#[serde(rename_all)]` of the struct
+enum RenameAll {
+    None,
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameAll {
+    fn try_from_lit(lit: &LitStr) -> syn::Result<Self> {
+        Ok(match lit.value().as_str() {
+            "snake_case" => Self::None,
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            _ => return Err(syn::Error::new(lit.span(), "unsupported rename_all rule")),
+        })
+    }
+
+    /// Renames a snake_case field
+    fn apply(&self, field: &str) -> String {
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        };
+        match self {
+            Self::None => field.to_string(),
+            Self::Lower => field.to_lowercase(),
+            Self::Upper | Self::ScreamingSnake => field.to_uppercase(),
+            Self::Pascal => field.split('_').map(capitalize).collect(),
+            Self::Camel => {
+                let pascal = field.split('_').map(capitalize).collect::<String>();
+                let mut chars = pascal.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_lowercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => field.to_uppercase().replace('_', "-"),
+        }
+    }
+}
+
+fn container_rename_all(input: &DeriveInput) -> syn::Result<RenameAll> {
+    let mut rename_all = RenameAll::None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                if meta.input.peek(syn::Token![=]) {
+                    rename_all = RenameAll::try_from_lit(&meta.value()?.parse()?)?;
+                } else {
+                    meta.parse_nested_meta(|meta| {
+                        let lit = meta.value()?.parse()?;
+                        if meta.path.is_ident("serialize") {
+                            rename_all = RenameAll::try_from_lit(&lit)?;
+                        }
+                        Ok(())
+                    })?;
+                }
+            } else {
+                skip_meta(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(rename_all)
+}
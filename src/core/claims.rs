@@ -0,0 +1,46 @@
+//! Claim names with a special meaning in a SD-JWT payload. Shared with the derive macro through
+//! `__private` so that both check claim names the same way.
+
+/// Claim names of the SD-JWT payload which can't appear in the input claims, wherever they are
+pub const RESERVED_CLAIMS: [&str; 3] = ["_sd", "_sd_alg", "..."];
+
+/// Top-level claims which must always be visible to the Verifier. A disclosure frame only checks
+/// them at its root, whereas the derive macro checks them in every struct since a nested struct
+/// can't tell whether it is the root of the claims.
+///
+/// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-sd-jwt-vc-08.html#name-registered-jwt-claims
+pub const ALWAYS_VISIBLE_CLAIMS: [&str; 7] = ["iss", "nbf", "exp", "cnf", "vct", "vct#integrity", "status"];
+
+pub const fn is_reserved(name: &str) -> bool {
+    contains(&RESERVED_CLAIMS, name)
+}
+
+pub const fn is_always_visible(name: &str) -> bool {
+    contains(&ALWAYS_VISIBLE_CLAIMS, name)
+}
+
+/// Usable in const contexts, unlike `<[&str]>::contains`
+const fn contains(names: &[&str], name: &str) -> bool {
+    let mut i = 0;
+    while i < names.len() {
+        if eq(names[i].as_bytes(), name.as_bytes()) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
@@ -0,0 +1,190 @@
+use crate::{
+    core::json_pointer::{
+        escape,
+        path::{JsonPointerPath, Step},
+    },
+    error::{SdjError, SdjResult},
+};
+use serde_json::Value;
+
+/// A claims path pointer, addressing claims with an array of path components. It resolves on the
+/// disclosed claims, going through the values of nested disclosures.
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-claims-path-pointer
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct ClaimsPath(pub Vec<PathComponent>);
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum PathComponent {
+    /// An object member
+    Name(String),
+    /// An array item
+    Index(usize),
+    /// All the array items, serialized as `null`
+    All(AllItems),
+}
+
+/// Serialized as `null`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct AllItems;
+
+impl serde::Serialize for AllItems {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_none()
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AllItems {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <()>::deserialize(deserializer).map(|_| Self)
+    }
+}
+
+impl ClaimsPath {
+    /// Selects all the claims this points to, along with their Json pointer. A component which does
+    /// not apply to the selected value (e.g. an index on an object) selects nothing.
+    pub fn find<'a>(&self, value: &'a Value) -> Vec<(String, &'a Value)> {
+        self.0.iter().fold(vec![(String::new(), value)], |nodes, component| {
+            nodes
+                .into_iter()
+                .flat_map(|(path, node)| component.apply(path, node))
+                .collect()
+        })
+    }
+
+    /// The Json pointer of the claim this points to, unless it selects all the items of an array
+    pub fn to_json_pointer(&self) -> Option<String> {
+        self.0
+            .iter()
+            .try_fold(String::new(), |pointer, component| match component {
+                PathComponent::Name(name) => Some(format!("{pointer}/{}", escape(name))),
+                PathComponent::Index(index) => Some(format!("{pointer}/{index}")),
+                PathComponent::All(_) => None,
+            })
+    }
+
+    /// From the Json pointer of a claim of `claims`, which tells object members from array items
+    pub fn try_from_json_pointer(pointer: &str, claims: &Value) -> SdjResult<Self> {
+        let not_found = || SdjError::InvalidJsonPointerPath(pointer.to_string());
+        let path = JsonPointerPath::try_from(pointer)?;
+        let mut value = claims;
+        let mut components = vec![];
+        for token in path.tokens() {
+            let (component, next) = match JsonPointerPath::step(value, token) {
+                Some(Step::Key(key)) => (PathComponent::Name(key.to_string()), value.get(key)),
+                Some(Step::Index(i)) => (PathComponent::Index(i), value.get(i)),
+                Some(Step::End) | None => return Err(not_found()),
+            };
+            components.push(component);
+            value = next.ok_or_else(not_found)?;
+        }
+        Ok(Self(components))
+    }
+}
+
+impl PathComponent {
+    fn apply<'a>(&self, path: String, value: &'a Value) -> Vec<(String, &'a Value)> {
+        match (self, value) {
+            (Self::Name(name), Value::Object(object)) => object
+                .get(name)
+                .map(|v| vec![(format!("{path}/{}", escape(name)), v)])
+                .unwrap_or_default(),
+            (Self::Index(index), Value::Array(items)) => items
+                .get(*index)
+                .map(|v| vec![(format!("{path}/{index}"), v)])
+                .unwrap_or_default(),
+            (Self::All(_), Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("{path}/{i}"), v))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+impl std::fmt::Display for ClaimsPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", serde_json::to_string(self).map_err(|_| std::fmt::Error)?)
+    }
+}
+
+/// From its JSON serialization e.g. `["degrees", null, "type"]`
+impl std::str::FromStr for ClaimsPath {
+    type Err = SdjError;
+
+    fn from_str(s: &str) -> SdjResult<Self> {
+        serde_json::from_str(s).map_err(|_| SdjError::InvalidClaimsPath(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn find(path: Value, value: &Value) -> Vec<String> {
+        serde_json::from_value::<ClaimsPath>(path)
+            .unwrap()
+            .find(value)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    #[test]
+    fn should_find_claims() {
+        let value = json!({
+            "address": {"street_address": "123 Main St"},
+            "nationalities": ["US", "DE"],
+            "degrees": [{"type": "BSc"}, {"type": "MSc"}]
+        });
+        assert_eq!(
+            find(json!(["address", "street_address"]), &value),
+            vec!["/address/street_address"]
+        );
+        assert_eq!(find(json!(["nationalities", 1]), &value), vec!["/nationalities/1"]);
+        assert_eq!(
+            find(json!(["degrees", null, "type"]), &value),
+            vec!["/degrees/0/type", "/degrees/1/type"]
+        );
+        assert!(find(json!(["address", 0]), &value).is_empty());
+        assert!(find(json!(["nationalities", "0"]), &value).is_empty());
+        assert!(find(json!(["address", null]), &value).is_empty());
+    }
+
+    #[test]
+    fn should_round_trip() {
+        let raw = json!(["degrees", null, "type", 1]);
+        let path = serde_json::from_value::<ClaimsPath>(raw.clone()).unwrap();
+        assert_eq!(path.0[1], PathComponent::All(AllItems));
+        assert_eq!(serde_json::to_value(&path).unwrap(), raw);
+        assert_eq!(path.to_string(), r#"["degrees",null,"type",1]"#);
+    }
+
+    #[test]
+    fn should_convert_json_pointers() {
+        let value = json!({"a/b": {"0": ["x", "y"]}, "degrees": [{"type": "BSc"}]});
+        let path = ClaimsPath::try_from_json_pointer("/a~1b/0/1", &value).unwrap();
+        assert_eq!(path, r#"["a/b", "0", 1]"#.parse().unwrap());
+        assert_eq!(path.to_json_pointer().unwrap(), "/a~1b/0/1");
+        assert_eq!(path.find(&value)[0].1, "y");
+
+        let all = r#"["degrees", null, "type"]"#.parse::<ClaimsPath>().unwrap();
+        assert!(all.to_json_pointer().is_none());
+        assert_eq!(ClaimsPath(vec![]).to_json_pointer().unwrap(), "");
+
+        for pointer in ["/unknown", "/degrees/1", "/degrees/-", "/degrees/00"] {
+            assert!(matches!(
+                ClaimsPath::try_from_json_pointer(pointer, &value).unwrap_err(),
+                SdjError::InvalidJsonPointerPath(p) if p == pointer
+            ));
+        }
+        assert!(matches!(
+            "[1.5]".parse::<ClaimsPath>().unwrap_err(),
+            SdjError::InvalidClaimsPath(p) if p == "[1.5]"
+        ));
+    }
+}
@@ -0,0 +1,33 @@
+use crate::core::dcql::claims_path::ClaimsPath;
+
+pub type DcqlResult<T> = Result<T, DcqlError>;
+
+/// Why a credential does not satisfy a DCQL query
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum DcqlError {
+    #[error("Credential query '{query_id}' only accepts the format '{format}'")]
+    UnsupportedFormat { query_id: String, format: String },
+    #[error("Credential query '{query_id}' only accepts the types {accepted:?}")]
+    UnexpectedVct { query_id: String, accepted: Vec<String> },
+    #[error("Credential query '{query_id}': no claim at {path}")]
+    MissingClaim { query_id: String, path: ClaimsPath },
+    #[error("Credential query '{query_id}': no claim at {path} has one of the values {values:?}")]
+    ValueNotSatisfied {
+        query_id: String,
+        path: ClaimsPath,
+        values: Vec<serde_json::Value>,
+    },
+    #[error("Credential query '{query_id}': no claim set can be satisfied")]
+    NoClaimSet { query_id: String },
+    #[error("Credential query '{query_id}' refers to the unknown claim '{claim_id}'")]
+    UnknownClaim { query_id: String, claim_id: String },
+    #[error("Unknown credential query '{0}'")]
+    UnknownCredentialQuery(String),
+    #[error("Credential query '{0}' does not allow multiple credentials")]
+    MultipleNotAllowed(String),
+    #[error("The credentials do not satisfy the query, missing {missing:?}")]
+    NotSatisfied {
+        /// Ids of the credential queries left unanswered
+        missing: Vec<String>,
+    },
+}
@@ -0,0 +1,355 @@
+use claims_path::ClaimsPath;
+use error::{DcqlError, DcqlResult};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+pub mod claims_path;
+pub mod error;
+
+/// What a Verifier requests, with the Digital Credentials Query Language
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-digital-credentials-query-l
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DcqlQuery {
+    pub credentials: Vec<CredentialQuery>,
+    /// Which combinations of credentials satisfy the query. When absent, all of them are required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_sets: Option<Vec<CredentialSetQuery>>,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-credential-query
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CredentialQuery {
+    pub id: String,
+    /// Claim Format Designation e.g. `dc+sd-jwt`
+    pub format: String,
+    /// Whether more than one credential can answer this query
+    #[serde(default)]
+    pub multiple: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<CredentialQueryMeta>,
+    /// When absent, no selectively disclosable claim is requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Vec<ClaimsQuery>>,
+    /// Combinations of claim ids, by order of preference. When absent, all the claims are required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_sets: Option<Vec<Vec<String>>>,
+    #[serde(default = "default_true")]
+    pub require_cryptographic_holder_binding: bool,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct CredentialQueryMeta {
+    /// Accepted SD-JWT VC types
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vct_values: Option<Vec<String>>,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-claims-query
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ClaimsQuery {
+    /// Required when [CredentialQuery::claim_sets] is present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub path: ClaimsPath,
+    /// The claim has to be equal to one of those
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<Value>>,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-credential-set-query
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CredentialSetQuery {
+    /// Combinations of credential query ids, any of which satisfies this set
+    pub options: Vec<Vec<String>>,
+    #[serde(default = "default_true")]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<Value>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl DcqlQuery {
+    /// Ids of the credential queries to answer for satisfying the query, given those which are
+    /// `answered`. Empty when the query is satisfied.
+    pub fn missing(&self, answered: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut missing = match &self.credential_sets {
+            None => self
+                .credentials
+                .iter()
+                .map(|c| c.id.clone())
+                .filter(|id| !answered(id))
+                .collect(),
+            Some(sets) => sets
+                .iter()
+                .filter(|set| set.required)
+                .filter(|set| !set.options.iter().any(|o| o.iter().all(|id| answered(id))))
+                .flat_map(|set| set.options.iter().flatten())
+                .filter(|id| !answered(id))
+                .cloned()
+                .collect::<Vec<_>>(),
+        };
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    pub fn credential(&self, id: &str) -> Option<&CredentialQuery> {
+        self.credentials.iter().find(|c| c.id == id)
+    }
+
+    /// Checks that the disclosed claims of the presented credentials, by credential query id,
+    /// satisfy the query
+    pub fn try_check(&self, presented: &BTreeMap<String, Vec<Value>>) -> DcqlResult<()> {
+        for (id, credentials) in presented {
+            let query = self
+                .credential(id)
+                .ok_or_else(|| DcqlError::UnknownCredentialQuery(id.clone()))?;
+            if !query.multiple && credentials.len() > 1 {
+                return Err(DcqlError::MultipleNotAllowed(id.clone()));
+            }
+            for claims in credentials {
+                query.try_match(claims)?;
+            }
+        }
+
+        let missing = self.missing(|id| presented.get(id).map(|c| !c.is_empty()).unwrap_or_default());
+        if !missing.is_empty() {
+            return Err(DcqlError::NotSatisfied { missing });
+        }
+        Ok(())
+    }
+}
+
+impl CredentialQuery {
+    /// The Claim Format Designations of a SD-JWT VC
+    pub const SD_JWT_FORMATS: [&'static str; 2] = ["dc+sd-jwt", "vc+sd-jwt"];
+
+    /// Checks that the claims satisfy this query then returns the Json pointers of the requested
+    /// claims. Uses the first satisfiable claim set, if any.
+    pub fn try_match(&self, claims: &Value) -> DcqlResult<Vec<String>> {
+        if !Self::SD_JWT_FORMATS.contains(&self.format.as_str()) {
+            return Err(DcqlError::UnsupportedFormat {
+                query_id: self.id.clone(),
+                format: self.format.clone(),
+            });
+        }
+
+        if let Some(accepted) = self.meta.as_ref().and_then(|m| m.vct_values.as_ref()) {
+            let vct = claims.get("vct").and_then(Value::as_str);
+            if !vct.map(|vct| accepted.iter().any(|a| a == vct)).unwrap_or_default() {
+                return Err(DcqlError::UnexpectedVct {
+                    query_id: self.id.clone(),
+                    accepted: accepted.clone(),
+                });
+            }
+        }
+
+        let Some(requested) = &self.claims else {
+            return Ok(vec![]);
+        };
+        let Some(claim_sets) = &self.claim_sets else {
+            return requested.iter().try_fold(vec![], |mut pointers, claim| {
+                pointers.extend(claim.try_match(&self.id, claims)?);
+                Ok(pointers)
+            });
+        };
+
+        for claim_set in claim_sets {
+            let mut pointers = vec![];
+            let mut satisfied = true;
+            for claim_id in claim_set {
+                let claim = requested
+                    .iter()
+                    .find(|c| c.id.as_ref() == Some(claim_id))
+                    .ok_or_else(|| DcqlError::UnknownClaim {
+                        query_id: self.id.clone(),
+                        claim_id: claim_id.clone(),
+                    })?;
+                match claim.try_match(&self.id, claims) {
+                    Ok(found) => pointers.extend(found),
+                    Err(_) => {
+                        satisfied = false;
+                        break;
+                    }
+                }
+            }
+            if satisfied {
+                return Ok(pointers);
+            }
+        }
+        Err(DcqlError::NoClaimSet {
+            query_id: self.id.clone(),
+        })
+    }
+}
+
+impl ClaimsQuery {
+    /// Json pointers of the claims selected by the path and, if any, equal to one of the values
+    fn try_match(&self, query_id: &str, claims: &Value) -> DcqlResult<Vec<String>> {
+        let found = self.path.find(claims);
+        if found.is_empty() {
+            return Err(DcqlError::MissingClaim {
+                query_id: query_id.to_string(),
+                path: self.path.clone(),
+            });
+        }
+        let Some(values) = &self.values else {
+            return Ok(found.into_iter().map(|(p, _)| p).collect());
+        };
+        let found = found
+            .into_iter()
+            .filter(|(_, v)| values.contains(v))
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        if found.is_empty() {
+            return Err(DcqlError::ValueNotSatisfied {
+                query_id: query_id.to_string(),
+                path: self.path.clone(),
+                values: values.clone(),
+            });
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claims() -> Value {
+        json!({
+            "vct": "https://credentials.example.com/identity_credential",
+            "given_name": "John",
+            "address": {"street_address": "123 Main St", "country": "US"},
+            "nationalities": ["US", "DE"]
+        })
+    }
+
+    fn query(credential: Value) -> DcqlQuery {
+        serde_json::from_value(json!({"credentials": [credential]})).unwrap()
+    }
+
+    #[test]
+    fn should_parse_query() {
+        let query: DcqlQuery = serde_json::from_value(json!({
+            "credentials": [
+                {
+                    "id": "pid",
+                    "format": "dc+sd-jwt",
+                    "meta": {"vct_values": ["https://credentials.example.com/identity_credential"]},
+                    "claims": [
+                        {"id": "a", "path": ["address", "street_address"]},
+                        {"id": "b", "path": ["nationalities", null], "values": ["DE"]}
+                    ],
+                    "claim_sets": [["a", "b"], ["a"]]
+                },
+                {"id": "mdl", "format": "dc+sd-jwt", "multiple": true, "require_cryptographic_holder_binding": false}
+            ],
+            "credential_sets": [{"options": [["pid"], ["mdl"]], "purpose": "Identification"}]
+        }))
+        .unwrap();
+        let pid = query.credential("pid").unwrap();
+        assert!(!pid.multiple && pid.require_cryptographic_holder_binding);
+        assert_eq!(pid.claim_sets.as_ref().unwrap().len(), 2);
+        let mdl = query.credential("mdl").unwrap();
+        assert!(mdl.multiple && !mdl.require_cryptographic_holder_binding);
+        assert!(query.credential_sets.as_ref().unwrap()[0].required);
+    }
+
+    #[test]
+    fn should_match_claims() {
+        let query = query(json!({
+            "id": "pid",
+            "format": "dc+sd-jwt",
+            "meta": {"vct_values": ["https://credentials.example.com/identity_credential"]},
+            "claims": [
+                {"path": ["given_name"]},
+                {"path": ["nationalities", null], "values": ["DE", "FR"]}
+            ]
+        }));
+        let pid = query.credential("pid").unwrap();
+        assert_eq!(
+            pid.try_match(&claims()).unwrap(),
+            vec!["/given_name", "/nationalities/1"]
+        );
+
+        let mut other = claims();
+        other["vct"] = json!("https://credentials.example.com/mdl");
+        assert!(matches!(pid.try_match(&other), Err(DcqlError::UnexpectedVct { .. })));
+
+        other = claims();
+        other["nationalities"] = json!(["US"]);
+        assert!(matches!(
+            pid.try_match(&other),
+            Err(DcqlError::ValueNotSatisfied { .. })
+        ));
+
+        other = claims();
+        other.as_object_mut().unwrap().remove("given_name");
+        assert!(matches!(pid.try_match(&other), Err(DcqlError::MissingClaim { .. })));
+    }
+
+    #[test]
+    fn should_pick_first_satisfiable_claim_set() {
+        let query = query(json!({
+            "id": "pid",
+            "format": "dc+sd-jwt",
+            "claims": [
+                {"id": "birthdate", "path": ["birthdate"]},
+                {"id": "country", "path": ["address", "country"]},
+                {"id": "name", "path": ["given_name"]}
+            ],
+            "claim_sets": [["birthdate"], ["name", "country"]]
+        }));
+        let pid = query.credential("pid").unwrap();
+        assert_eq!(
+            pid.try_match(&claims()).unwrap(),
+            vec!["/given_name", "/address/country"]
+        );
+
+        let mut other = claims();
+        other.as_object_mut().unwrap().remove("given_name");
+        assert!(matches!(pid.try_match(&other), Err(DcqlError::NoClaimSet { .. })));
+    }
+
+    #[test]
+    fn should_check_credential_sets() {
+        let query: DcqlQuery = serde_json::from_value(json!({
+            "credentials": [
+                {"id": "pid", "format": "dc+sd-jwt"},
+                {"id": "other_pid", "format": "dc+sd-jwt"},
+                {"id": "address", "format": "dc+sd-jwt"}
+            ],
+            "credential_sets": [
+                {"options": [["pid"], ["other_pid"]]},
+                {"options": [["address"]], "required": false}
+            ]
+        }))
+        .unwrap();
+        assert!(query.missing(|id| id == "other_pid").is_empty());
+        assert_eq!(query.missing(|id| id == "address"), vec!["other_pid", "pid"]);
+
+        let presented = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| (id.to_string(), vec![claims()]))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert!(query.try_check(&presented(&["pid", "address"])).is_ok());
+        assert!(matches!(
+            query.try_check(&presented(&["address"])),
+            Err(DcqlError::NotSatisfied { .. })
+        ));
+        assert!(matches!(
+            query.try_check(&presented(&["unknown"])),
+            Err(DcqlError::UnknownCredentialQuery(_))
+        ));
+        let mut twice = presented(&["pid"]);
+        twice.get_mut("pid").unwrap().push(claims());
+        assert!(matches!(query.try_check(&twice), Err(DcqlError::MultipleNotAllowed(_))));
+    }
+}
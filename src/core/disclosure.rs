@@ -1,6 +1,6 @@
 use crate::{
-    crypto::{self, salt::Salt},
-    error::SdjError,
+    crypto::salt::Salt,
+    error::{SdjError, SdjResult},
 };
 
 /// A combination of a salt, a cleartext claim name (present when the claim is a key-value pair and
@@ -11,85 +11,81 @@ use crate::{
 ///
 /// [RFC]: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-5.2
 #[derive(Debug, Clone)]
-pub enum Disclosure<Hash: digest::Digest = sha2::Sha256, const SALT_SIZE: usize = { crypto::DEFAULT_SALT_SIZE }> {
-    Object {
-        /// A salt value. MUST be a string. See Section
-        /// [9.3](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.3)
-        /// and Section [9.4](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.4)
-        /// for security considerations. It is RECOMMENDED to base64url-encode minimum 128 bits of
-        /// cryptographically secure pseudorandom data, producing a string. The salt value MUST be
-        /// unique for each claim that is to be selectively disclosed. The Issuer MUST NOT disclose the
-        /// salt value to any party other than the Holder.
-        salt: Salt<SALT_SIZE>,
-        /// The claim name, or key, as it would be used in a regular JWT body. The value MUST be a string.
-        name: String,
-        /// The claim value, as it would be used in a regular JWT body. The value MAY be of any type
-        /// that is allowed in JSON, including numbers, strings, booleans, arrays, and objects.
-        value: serde_json::Value,
-        /// Marker
-        hasher: core::marker::PhantomData<Hash>,
-    },
-    Array {
-        /// A salt value. MUST be a string. See Section
-        /// [9.3](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.3)
-        /// and Section [9.4](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.4)
-        /// for security considerations. It is RECOMMENDED to base64url-encode minimum 128 bits of
-        /// cryptographically secure pseudorandom data, producing a string. The salt value MUST be
-        /// unique for each claim that is to be selectively disclosed. The Issuer MUST NOT disclose the
-        /// salt value to any party other than the Holder.
-        salt: Salt<SALT_SIZE>,
-        /// The claim value, as it would be used in a regular JWT body. The value MAY be of any type
-        /// that is allowed in JSON, including numbers, strings, booleans, arrays, and objects.
-        value: serde_json::Value,
-        /// Marker
-        hasher: core::marker::PhantomData<Hash>,
-    },
+pub struct Disclosure<Hash: digest::Digest = sha2::Sha256> {
+    /// A salt value. MUST be a string. See Section
+    /// [9.3](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.3)
+    /// and Section [9.4](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.4)
+    /// for security considerations. It is RECOMMENDED to base64url-encode minimum 128 bits of
+    /// cryptographically secure pseudorandom data, producing a string. The salt value MUST be
+    /// unique for each claim that is to be selectively disclosed. The Issuer MUST NOT disclose the
+    /// salt value to any party other than the Holder.
+    salt: Salt,
+    /// The claim name, or key, as it would be used in a regular JWT body. The value MUST be a string.
+    /// Absent when the claim is an array element.
+    name: Option<String>,
+    /// The claim value, as it would be used in a regular JWT body. The value MAY be of any type
+    /// that is allowed in JSON, including numbers, strings, booleans, arrays, and objects.
+    value: serde_json::Value,
+    /// The base64url encoded disclosure, always hashed & serialized as is: as received when parsed,
+    /// as built otherwise
+    encoded: String,
+    /// Marker
+    hasher: core::marker::PhantomData<Hash>,
+}
+
+#[cfg(feature = "issuer")]
+impl Disclosure {
+    /// Encodes a new disclosure like the reference implementation does
+    pub(crate) fn try_new(salt: Salt, name: Option<String>, value: serde_json::Value) -> SdjResult<Self> {
+        let encoded = super::disclosure_hash::encode(&salt, name.as_deref(), &value)?;
+        Ok(Self {
+            salt,
+            name,
+            value,
+            encoded,
+            hasher: core::marker::PhantomData,
+        })
+    }
+}
+
+impl<Hash: digest::Digest> Disclosure<Hash> {
+    /// The base64url encoded disclosure: as received when parsed, otherwise its JSON array encoded
+    /// like the reference implementation does
+    pub fn build(&self) -> SdjResult<String> {
+        Ok(self.encoded.clone())
+    }
+
+    pub fn salt(&self) -> &Salt {
+        &self.salt
+    }
+
+    /// The claim name, `None` for an array element
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn value(&self) -> &serde_json::Value {
+        &self.value
+    }
 }
 
 impl Eq for Disclosure {}
 
 impl PartialEq for Disclosure {
+    /// Disclosures are equal when they are encoded the same, hence have the same digest. A received
+    /// disclosure differs from the one this crate would build with the same content when it was
+    /// encoded otherwise.
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (
-                Self::Object {
-                    salt: s1,
-                    name: n1,
-                    value: v1,
-                    ..
-                },
-                Self::Object {
-                    salt: s2,
-                    name: n2,
-                    value: v2,
-                    ..
-                },
-            ) => s1 == s2 && n1 == n2 && v1 == v2,
-            (
-                Self::Array {
-                    salt: s1, value: v1, ..
-                },
-                Self::Array {
-                    salt: s2, value: v2, ..
-                },
-            ) => s1 == s2 && v1 == v2,
-            _ => false,
-        }
+        self.encoded == other.encoded
     }
 }
 
 impl std::fmt::Display for Disclosure {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Object { salt, name, value, .. } => {
-                let salt = salt.to_string();
-                write!(f, "[\"{salt}\", \"{name}\", {value}]")
-            }
-            Self::Array { salt, value, .. } => {
-                let salt = salt.to_string();
-                write!(f, "[\"{salt}\", {value}]")
-            }
-        }
+        let json = base64_simd::URL_SAFE_NO_PAD
+            .decode_to_vec(&self.encoded)
+            .map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", String::from_utf8_lossy(&json))
     }
 }
 
@@ -100,29 +96,21 @@ impl std::str::FromStr for Disclosure {
         let decoded = base64_simd::URL_SAFE_NO_PAD.decode_to_vec(s.as_bytes())?;
         let json = serde_json::from_slice::<serde_json::Value>(&decoded)?;
         let array = json.as_array().ok_or(SdjError::InvalidDisclosure)?;
-        match array.as_slice() {
+        let (salt, name, value) = match array.as_slice() {
             [salt, name, value] => {
-                let salt = salt.as_str().ok_or(SdjError::InvalidDisclosure)?.parse()?;
                 let name = name.as_str().ok_or(SdjError::InvalidDisclosure)?.to_string();
-
-                Ok(Disclosure::Object {
-                    salt,
-                    name,
-                    value: value.clone(),
-                    hasher: core::marker::PhantomData,
-                })
+                (salt, Some(name), value)
             }
-            [salt, value] => {
-                let salt = salt.as_str().ok_or(SdjError::InvalidDisclosure)?.parse()?;
-
-                Ok(Disclosure::Array {
-                    salt,
-                    value: value.clone(),
-                    hasher: core::marker::PhantomData,
-                })
-            }
-            _ => Err(SdjError::InvalidDisclosure),
-        }
+            [salt, value] => (salt, None, value),
+            _ => return Err(SdjError::InvalidDisclosure),
+        };
+        Ok(Self {
+            salt: salt.as_str().ok_or(SdjError::InvalidDisclosure)?.parse()?,
+            name,
+            value: value.clone(),
+            encoded: s.to_string(),
+            hasher: core::marker::PhantomData,
+        })
     }
 }
 
@@ -133,19 +121,14 @@ pub mod tests {
     /// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-5.2.1
     #[test]
     fn should_pass_rfc_example_for_object() {
-        // let (salt, name, value) = ("_26bc4LT-ac6q2KI6cBW5es", "family_name", "Möbius");
-
-        // TODO:
-        /*let disclosure = Disclosure {
-            salt: salt.parse::<Salt<17>>().unwrap(),
-            name: Some(name),
-            value: &serde_json::json!(value),
-            hasher: core::marker::PhantomData::<sha2::Sha256>::default(),
-        };
+        let (salt, name, value) = ("_26bc4LT-ac6q2KI6cBW5es", "family_name", "Möbius");
+
+        let disclosure =
+            Disclosure::try_new(salt.parse().unwrap(), Some(name.to_string()), serde_json::json!(value)).unwrap();
         assert_eq!(
             disclosure.build().unwrap(),
             "WyJfMjZiYzRMVC1hYzZxMktJNmNCVzVlcyIsICJmYW1pbHlfbmFtZSIsICJNw7ZiaXVzIl0".to_string()
-        );*/
+        );
     }
 
     /// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-5.2.2
@@ -153,11 +136,7 @@ pub mod tests {
     fn should_pass_rfc_example_for_array() {
         let (salt, value) = ("lklxF5jMYlGTPUovMNIvCA", "FR");
 
-        let disclosure = Disclosure::Array {
-            salt: salt.parse::<Salt<16>>().unwrap(),
-            value: serde_json::json!(value),
-            hasher: core::marker::PhantomData::<sha2::Sha256>,
-        };
+        let disclosure = Disclosure::try_new(salt.parse().unwrap(), None, serde_json::json!(value)).unwrap();
         assert_eq!(
             disclosure.build().unwrap(),
             "WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgIkZSIl0".to_string()
@@ -169,12 +148,20 @@ pub mod tests {
     fn should_pass_rfc_example_for_hash() {
         let (salt, value) = ("lklxF5jMYlGTPUovMNIvCA", "FR");
 
-        let disclosure = Disclosure::Array {
-            salt: salt.parse::<Salt<16>>().unwrap(),
-            value: serde_json::json!(value),
-            hasher: core::marker::PhantomData::<sha2::Sha256>,
-        };
+        let disclosure = Disclosure::try_new(salt.parse().unwrap(), None, serde_json::json!(value)).unwrap();
         let hash: String = disclosure.hash().unwrap().into();
         assert_eq!(hash, "w0I8EKcdCtUPkGCNUrfwVp2xEgNjtoIDlOxc9-PlOhs".to_string());
     }
+
+    #[test]
+    fn should_compare_encodings() {
+        let disclosure =
+            Disclosure::try_new("lklxF5jMYlGTPUovMNIvCA".parse().unwrap(), None, serde_json::json!("FR")).unwrap();
+        let received = disclosure.build().unwrap().parse::<Disclosure>().unwrap();
+        assert_eq!(disclosure, received);
+
+        // same salt & value, without whitespace
+        let compact = base64_simd::URL_SAFE_NO_PAD.encode_to_string(r#"["lklxF5jMYlGTPUovMNIvCA","FR"]"#);
+        assert_ne!(disclosure, compact.parse::<Disclosure>().unwrap());
+    }
 }
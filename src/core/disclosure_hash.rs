@@ -13,7 +13,7 @@ use crate::{core::disclosure::Disclosure, error::SdjResult};
 )]
 pub struct DisclosureHash(String);
 
-impl Disclosure<sha2::Sha256, 16> {
+impl Disclosure<sha2::Sha256> {
     pub fn hash(&self) -> SdjResult<DisclosureHash> {
         // let mut hasher = Hash::new();
         use sha2::Digest;
@@ -24,25 +24,122 @@ impl Disclosure<sha2::Sha256, 16> {
         let b64_encoded = base64_simd::URL_SAFE_NO_PAD.encode_to_string(hashed.as_slice());
         Ok(b64_encoded.into())
     }
+}
+
+/// Encodes the JSON array of a new disclosure like the reference implementation does
+#[cfg(feature = "issuer")]
+pub(super) fn encode(
+    salt: &crate::crypto::salt::Salt,
+    name: Option<&str>,
+    value: &serde_json::Value,
+) -> SdjResult<String> {
+    use serde::Serialize as _;
+
+    let mut utf8_encoded = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut utf8_encoded, DisclosureFormatter);
+    match name {
+        Some(name) => (salt.as_str(), name, value).serialize(&mut serializer)?,
+        None => (salt.as_str(), value).serialize(&mut serializer)?,
+    }
+
+    // this encoding might be vulnerable to side-channel attacks revealing the content being
+    // encoded which should not be leaked
+    use base64ct::Encoding as _;
+    Ok(base64ct::Base64UrlUnpadded::encode_string(&utf8_encoded))
+}
+
+/// Separates array items & object members with `", "` and keys from values with `": "`, the way
+/// Python's `json.dumps` does
+#[cfg(feature = "issuer")]
+struct DisclosureFormatter;
+
+#[cfg(feature = "issuer")]
+impl serde_json::ser::Formatter for DisclosureFormatter {
+    fn begin_array_value<W: ?Sized + std::io::Write>(&mut self, writer: &mut W, first: bool) -> std::io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b", ")
+        }
+    }
+
+    fn begin_object_key<W: ?Sized + std::io::Write>(&mut self, writer: &mut W, first: bool) -> std::io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b", ")
+        }
+    }
+
+    fn begin_object_value<W: ?Sized + std::io::Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(b": ")
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn disclosure(name: &str, value: serde_json::Value) -> Disclosure {
+        Disclosure::try_new("2GLC42sKQveCfGfryNRN9w".parse().unwrap(), Some(name.to_string()), value).unwrap()
+    }
+
+    fn decode(encoded: &str) -> String {
+        String::from_utf8(base64_simd::URL_SAFE_NO_PAD.decode_to_vec(encoded).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn should_escape_names_and_values() {
+        let built = disclosure("a \"quoted\" \\ name", json!({"k": ["v\n", 1.5]}))
+            .build()
+            .unwrap();
+        assert_eq!(
+            decode(&built),
+            r#"["2GLC42sKQveCfGfryNRN9w", "a \"quoted\" \\ name", {"k": ["v\n", 1.5]}]"#
+        );
+        let parsed = built.parse::<Disclosure>().unwrap();
+        assert_eq!(parsed.name(), Some("a \"quoted\" \\ name"));
+    }
+
+    #[test]
+    fn should_round_trip_numbers() {
+        let values = json!([
+            0.30000000000000004,
+            1e-7,
+            5e-324,
+            1.7976931348623157e308,
+            u64::MAX,
+            i64::MIN
+        ]);
+        let built = disclosure("numbers", values.clone()).build().unwrap();
+        assert_eq!(built.parse::<Disclosure>().unwrap().value(), &values);
+    }
+
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn should_keep_numbers_exact() {
+        let numbers = "[12345678901234567890123, -98765432109876543210, 0.1000000000000000000001, 123.4500]";
+        let values = serde_json::from_str::<serde_json::Value>(numbers).unwrap();
+        let built = disclosure("numbers", values.clone()).build().unwrap();
+        assert_eq!(
+            decode(&built),
+            format!(r#"["2GLC42sKQveCfGfryNRN9w", "numbers", {numbers}]"#)
+        );
+        assert_eq!(built.parse::<Disclosure>().unwrap().value(), &values);
+    }
+
+    #[test]
+    fn should_keep_encoding_as_received() {
+        // compact separators, exponent & more digits than a f64 holds
+        let json = r#"["2GLC42sKQveCfGfryNRN9w","amount",1.000000000000000000001e3]"#;
+        let received = base64_simd::URL_SAFE_NO_PAD.encode_to_string(json);
+        let disclosure = received.parse::<Disclosure>().unwrap();
+        assert_eq!(disclosure.build().unwrap(), received);
+        assert_eq!(disclosure.to_string(), json);
 
-    pub fn build(&self) -> SdjResult<String> {
-        let utf8_encoded = match self {
-            Disclosure::Object { salt, name, value, .. } => {
-                let salt = salt.to_string();
-                let value = serde_json::to_string(&value)?;
-                format!("[\"{salt}\", \"{name}\", {value}]")
-            }
-            Disclosure::Array { salt, value, .. } => {
-                let salt = salt.to_string();
-                let value = serde_json::to_string(&value)?;
-                format!("[\"{salt}\", {value}]")
-            }
-        };
-
-        // this encoding might be vulnerable to side-channel attacks revealing the content being
-        // encoded which should not be leaked
-        use base64ct::Encoding as _;
-        let b64_encoded = base64ct::Base64UrlUnpadded::encode_string(utf8_encoded.as_bytes());
-        Ok(b64_encoded)
+        use sha2::Digest as _;
+        let digest = base64_simd::URL_SAFE_NO_PAD.encode_to_string(sha2::Sha256::digest(received.as_bytes()));
+        assert_eq!(String::from(disclosure.hash().unwrap()), digest);
     }
 }
@@ -0,0 +1,193 @@
+use crate::{
+    core::json_pointer::escape,
+    error::{SdjError, SdjResult},
+};
+use serde_json::Value;
+
+/// Home baked implementation of the subset of [JSONPath](https://www.rfc-editor.org/rfc/rfc9535)
+/// used in practice for addressing claims:
+/// * `$` the root
+/// * `.name`, `['name']` or `["name"]` an object member
+/// * `[0]` an array item
+/// * `.*` or `[*]` all the members or items
+/// * `..` recursive descent e.g. `$..email`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Member(String),
+    Index(usize),
+    Wildcard,
+    /// Applies the segment to the node and all its descendants
+    Descendants(Box<Segment>),
+}
+
+impl JsonPath {
+    /// Finds all the values matching this path, along with their Json pointer
+    pub fn find<'a>(&self, value: &'a Value) -> Vec<(String, &'a Value)> {
+        self.segments
+            .iter()
+            .fold(vec![(String::new(), value)], |nodes, segment| {
+                nodes
+                    .into_iter()
+                    .flat_map(|(path, node)| segment.apply(path, node))
+                    .collect()
+            })
+    }
+}
+
+impl Segment {
+    fn apply<'a>(&self, path: String, value: &'a Value) -> Vec<(String, &'a Value)> {
+        match (self, value) {
+            (Self::Member(name), Value::Object(object)) => object
+                .get(name)
+                .map(|v| vec![(format!("{path}/{}", escape(name)), v)])
+                .unwrap_or_default(),
+            (Self::Index(index), Value::Array(items)) => items
+                .get(*index)
+                .map(|v| vec![(format!("{path}/{index}"), v)])
+                .unwrap_or_default(),
+            (Self::Wildcard, Value::Object(object)) => object
+                .iter()
+                .map(|(k, v)| (format!("{path}/{}", escape(k)), v))
+                .collect(),
+            (Self::Wildcard, Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("{path}/{i}"), v))
+                .collect(),
+            (Self::Descendants(segment), _) => {
+                let mut found = segment.apply(path.clone(), value);
+                for (child_path, child) in Self::Wildcard.apply(path, value) {
+                    found.extend(self.apply(child_path, child));
+                }
+                found
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl std::str::FromStr for JsonPath {
+    type Err = SdjError;
+
+    fn from_str(raw: &str) -> SdjResult<Self> {
+        let invalid = || SdjError::InvalidJsonPath(raw.to_string());
+        let mut rest = raw.strip_prefix('$').ok_or_else(invalid)?;
+        let mut segments = vec![];
+
+        while !rest.is_empty() {
+            let (descendants, after) = match rest.strip_prefix("..") {
+                Some(after) => (true, after),
+                None => (false, rest),
+            };
+            let (segment, after) = if let Some(bracket) = after.strip_prefix('[') {
+                let (inner, after) = bracket.split_once(']').ok_or_else(invalid)?;
+                (parse_bracket(inner).ok_or_else(invalid)?, after)
+            } else {
+                let after = if descendants {
+                    after
+                } else {
+                    after.strip_prefix('.').ok_or_else(invalid)?
+                };
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let (name, after) = after.split_at(end);
+                let segment = match name {
+                    "" => return Err(invalid()),
+                    "*" => Segment::Wildcard,
+                    name => Segment::Member(name.to_string()),
+                };
+                (segment, after)
+            };
+            segments.push(if descendants {
+                Segment::Descendants(Box::new(segment))
+            } else {
+                segment
+            });
+            rest = after;
+        }
+
+        Ok(Self {
+            raw: raw.to_string(),
+            segments,
+        })
+    }
+}
+
+fn parse_bracket(inner: &str) -> Option<Segment> {
+    let inner = inner.trim();
+    if inner == "*" {
+        return Some(Segment::Wildcard);
+    }
+    let quoted = ['\'', '"'].iter().find_map(|&q| inner.strip_prefix(q)?.strip_suffix(q));
+    match quoted {
+        Some(name) => Some(Segment::Member(name.to_string())),
+        None => inner.parse().ok().map(Segment::Index),
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn find(path: &str, value: &Value) -> Vec<String> {
+        path.parse::<JsonPath>()
+            .unwrap()
+            .find(value)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    #[test]
+    fn should_find_members_and_items() {
+        let value = json!({
+            "email": "a@b.c",
+            "address": {"city": "Paris", "a/b": 1},
+            "degrees": [{"type": "BSc"}, {"type": "MSc"}]
+        });
+        assert_eq!(find("$", &value), vec![""]);
+        assert_eq!(find("$.email", &value), vec!["/email"]);
+        assert_eq!(find("$['address']['city']", &value), vec!["/address/city"]);
+        assert_eq!(find("$.address[\"a/b\"]", &value), vec!["/address/a~1b"]);
+        assert_eq!(find("$.degrees[1].type", &value), vec!["/degrees/1/type"]);
+        assert_eq!(
+            find("$.degrees[*].type", &value),
+            vec!["/degrees/0/type", "/degrees/1/type"]
+        );
+        assert_eq!(find("$.address.*", &value).len(), 2);
+        assert!(find("$.unknown", &value).is_empty());
+        assert!(find("$.degrees[2]", &value).is_empty());
+    }
+
+    #[test]
+    fn should_find_descendants() {
+        let value = json!({
+            "email": "a@b.c",
+            "contacts": [{"email": "d@e.f"}, {"phone": "123"}]
+        });
+        assert_eq!(find("$..email", &value), vec!["/email", "/contacts/0/email"]);
+        assert_eq!(find("$..[1]", &value), vec!["/contacts/1"]);
+    }
+
+    #[test]
+    fn should_fail_when_invalid() {
+        for path in ["", "email", "$.", "$[", "$[abc]", "$.a..", "$email"] {
+            assert!(
+                matches!(path.parse::<JsonPath>(), Err(SdjError::InvalidJsonPath(p)) if p == path),
+                "{path}"
+            );
+        }
+    }
+}
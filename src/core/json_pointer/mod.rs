@@ -1,17 +1,49 @@
 #[cfg(feature = "issuer")]
 use crate::core::disclosure::Disclosure;
+#[cfg(any(feature = "issuer", feature = "holder"))]
 use crate::error::{SdjError, SdjResult};
+#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
 use serde_json::Value;
 
 pub(crate) mod path;
 
+/// Escapes a Json pointer reference token
+pub(crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Calls `f` with the Json pointer and value of every nested claim and array item
+#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
+pub(crate) fn visit(value: &Value, path: &str, f: &mut impl FnMut(&str, &Value)) {
+    let children: Box<dyn Iterator<Item = (String, &Value)>> = match value {
+        Value::Object(object) => Box::new(object.iter().map(|(k, v)| (escape(k), v))),
+        Value::Array(items) => Box::new(items.iter().enumerate().map(|(i, v)| (i.to_string(), v))),
+        _ => return,
+    };
+    for (token, child) in children {
+        let child_path = format!("{path}/{token}");
+        f(&child_path, child);
+        visit(child, &child_path, f);
+    }
+}
+
+/// Where the digest of a selectively disclosable claim is in a SD-JWT payload
+#[cfg(feature = "holder")]
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum DigestRef<'v> {
+    /// The `_sd` digests of the object containing the claim
+    Object(&'v [Value]),
+    /// The `...` digest of the array item
+    ArrayItem(&'v str),
+}
+
 /// Home baked JSON Pointer implementation that also drops the selected value
 ///
 /// For more information read [RFC6901](https://tools.ietf.org/html/rfc6901)
+#[cfg(any(feature = "issuer", feature = "holder"))]
 pub(crate) trait JsonPointer {
     /// Finds the value by JSON Pointer then removes the key from the JSON Value
     /// and returns the key name and value in order to build a [crate::core::disclosure::Disclosure]
-    // TODO: have this return a Result
     #[cfg(feature = "issuer")]
     fn try_find_drop(
         &mut self,
@@ -19,9 +51,11 @@ pub(crate) trait JsonPointer {
         path: &path::JsonPointerPath,
     ) -> SdjResult<Disclosure>;
 
-    fn try_find_disclosure(&self, path: &path::JsonPointerPath) -> SdjResult<&Value>;
+    #[cfg(feature = "holder")]
+    fn try_find_disclosure(&self, path: &path::JsonPointerPath) -> SdjResult<DigestRef<'_>>;
 }
 
+#[cfg(any(feature = "issuer", feature = "holder"))]
 impl JsonPointer for Value {
     #[cfg(feature = "issuer")]
     fn try_find_drop(
@@ -30,73 +64,54 @@ impl JsonPointer for Value {
         path: &path::JsonPointerPath,
     ) -> SdjResult<Disclosure> {
         use serde_json::json;
-        let tokens = path
-            .split('/')
-            .skip(1)
-            .map(|x| x.replace("~1", "/").replace("~0", "~"))
-            .collect::<Vec<_>>();
-        let tokens_len = tokens.len();
-
-        let mut disclosure = None;
-
-        // TODO: better error handling
-        tokens
-            .into_iter()
-            .enumerate()
-            .try_fold(
-                (String::new(), Some(self)),
-                |(_, previous), (index, token)| match previous {
-                    Some(Value::Object(map)) => {
-                        let is_last_token = index == tokens_len - 1;
-                        if is_last_token {
-                            let value = map.remove(&token)?;
-                            let _disclosure = Disclosure::try_new_object(backend, token.clone(), value).ok()?;
-                            let hash = _disclosure.hash().ok()?;
-                            disclosure = Some(_disclosure);
-
-                            map.entry("_sd")
-                                .and_modify(|e| e.as_array_mut().unwrap().push(json!(hash)))
-                                .or_insert_with(|| json!([hash]));
-
-                            None
-                        } else {
-                            let next = map.get_mut(&token);
-                            next.map(|value| (token, Some(value)))
-                        }
-                    }
-                    Some(Value::Array(list)) => {
-                        use std::str::FromStr as _;
-                        let item_index = usize::from_str(&token).ok()?;
-                        let item = list.get_mut(item_index)?;
-
-                        let _disclosure = Disclosure::try_new_array(backend, item.clone()).ok()?;
-                        let hash = _disclosure.hash().ok()?;
-                        disclosure = Some(_disclosure);
-                        *item = json!({"...": hash});
-
-                        None
-                    }
-                    _ => None,
-                },
-            );
-
-        disclosure.ok_or_else(|| SdjError::InvalidJsonPointerPath(path.to_string()))
+        let not_found = || SdjError::InvalidJsonPointerPath(path.to_string());
+        let (parent, token) = path.parent().zip(path.last()).ok_or_else(not_found)?;
+        let parent = parent.resolve_mut(self).ok_or_else(not_found)?;
+
+        match path::JsonPointerPath::step(parent, token) {
+            Some(path::Step::Key(key)) => {
+                let map = parent.as_object_mut().ok_or_else(not_found)?;
+                let value = map.remove(key).ok_or_else(not_found)?;
+                let disclosure = Disclosure::try_new_object(backend, key.to_string(), value)?;
+                let hash = disclosure.hash()?;
+                let digests = map
+                    .entry("_sd")
+                    .or_insert_with(|| json!([]))
+                    .as_array_mut()
+                    .ok_or(SdjError::InvalidJwt)?;
+                digests.push(json!(hash));
+                // hides the order of the claims, and which digests are decoys
+                digests.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                Ok(disclosure)
+            }
+            Some(path::Step::Index(i)) => {
+                let item = parent.get_mut(i).ok_or_else(not_found)?;
+                let disclosure = Disclosure::try_new_array(backend, item.take())?;
+                *item = json!({"...": disclosure.hash()?});
+                Ok(disclosure)
+            }
+            Some(path::Step::End) | None => Err(not_found()),
+        }
     }
 
-    fn try_find_disclosure(&self, path: &path::JsonPointerPath) -> SdjResult<&Value> {
-        if path.is_key() {
-            let mut path = path
-                .parent()
-                .ok_or(SdjError::InvalidJsonPointerPath(path.to_string()))?;
-            path.append("_sd");
-            self.pointer(&path)
-                .ok_or(SdjError::InvalidJsonPointerPath(path.to_string()))
-        } else {
-            let value = self
-                .pointer(path)
-                .ok_or(SdjError::InvalidJsonPointerPath(path.to_string()))?;
-            let obj = value.as_object().ok_or(SdjError::InvalidJwt)?;
-            obj.get("...").ok_or(SdjError::InvalidJwt)
+    #[cfg(feature = "holder")]
+    fn try_find_disclosure(&self, path: &path::JsonPointerPath) -> SdjResult<DigestRef<'_>> {
+        let not_found = || SdjError::InvalidJsonPointerPath(path.to_string());
+        let (parent, token) = path.parent().zip(path.last()).ok_or_else(not_found)?;
+        let parent = parent.resolve(self).ok_or_else(not_found)?;
+
+        match path::JsonPointerPath::step(parent, token) {
+            Some(path::Step::Key(_)) => {
+                let digests = parent.get("_sd").ok_or_else(not_found)?;
+                let digests = digests.as_array().ok_or(SdjError::InvalidJwt)?;
+                Ok(DigestRef::Object(digests))
+            }
+            Some(path::Step::Index(i)) => {
+                let item = parent.get(i).ok_or_else(not_found)?;
+                let digest = item.get("...").ok_or(SdjError::InvalidJwt)?;
+                Ok(DigestRef::ArrayItem(digest.as_str().ok_or(SdjError::InvalidJwt)?))
+            }
+            Some(path::Step::End) | None => Err(not_found()),
         }
     }
 }
@@ -124,61 +139,49 @@ pub mod tests {
 
         // string
         let path = "/string".try_into().unwrap();
-        let Ok(Disclosure::Object { name, value, .. }) = input.try_find_drop(&mut backend, &path) else {
-            unimplemented!()
-        };
-        assert_eq!(name, "string");
-        assert_eq!(value, "s");
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("string"));
+        assert_eq!(disclosure.value(), "s");
         assert!(input.get("string").is_none());
         assert_eq!(input.get("_sd").unwrap().as_array().unwrap().len(), 1);
 
         // int
         let path = "/int".try_into().unwrap();
-        let Ok(Disclosure::Object { name, value, .. }) = input.try_find_drop(&mut backend, &path) else {
-            unimplemented!()
-        };
-        assert_eq!(name, "int");
-        assert_eq!(value, 42);
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("int"));
+        assert_eq!(disclosure.value(), &42);
         assert!(input.get("int").is_none());
         assert_eq!(input.get("_sd").unwrap().as_array().unwrap().len(), 2);
 
         // float
         let path = "/float".try_into().unwrap();
-        let Ok(Disclosure::Object { name, value, .. }) = input.try_find_drop(&mut backend, &path) else {
-            unimplemented!()
-        };
-        assert_eq!(name, "float");
-        assert_eq!(value, 4.13);
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("float"));
+        assert_eq!(disclosure.value(), &4.13);
         assert!(input.get("float").is_none());
         assert_eq!(input.get("_sd").unwrap().as_array().unwrap().len(), 3);
 
         // bool
         let path = "/bool".try_into().unwrap();
-        let Ok(Disclosure::Object { name, value, .. }) = input.try_find_drop(&mut backend, &path) else {
-            unimplemented!()
-        };
-        assert_eq!(name, "bool");
-        assert_eq!(value, false);
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("bool"));
+        assert_eq!(disclosure.value(), &false);
         assert!(input.get("bool").is_none());
         assert_eq!(input.get("_sd").unwrap().as_array().unwrap().len(), 4);
 
         // object
         let path = "/obj".try_into().unwrap();
-        let Ok(Disclosure::Object { name, value, .. }) = input.try_find_drop(&mut backend, &path) else {
-            unimplemented!()
-        };
-        assert_eq!(name, "obj");
-        assert_eq!(value, json!({"a": 1, "b": 2}));
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("obj"));
+        assert_eq!(disclosure.value(), &json!({"a": 1, "b": 2}));
         assert!(input.get("obj").is_none());
         assert_eq!(input.get("_sd").unwrap().as_array().unwrap().len(), 5);
 
         // array
         let path = "/array".try_into().unwrap();
-        let Ok(Disclosure::Object { name, value, .. }) = input.try_find_drop(&mut backend, &path) else {
-            unimplemented!()
-        };
-        assert_eq!(name, "array");
-        assert_eq!(value, json!([0, 1, 2]));
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("array"));
+        assert_eq!(disclosure.value(), &json!([0, 1, 2]));
         assert!(input.get("array").is_none());
         assert_eq!(input.get("_sd").unwrap().as_array().unwrap().len(), 6);
     }
@@ -190,13 +193,12 @@ pub mod tests {
         let mut input = json!({"array": [0, 1, 2]});
 
         let path = "/array/2".try_into().unwrap();
-        let Ok(Disclosure::Array { value, .. }) = input.try_find_drop(&mut backend, &path) else {
-            unimplemented!()
-        };
-        assert_eq!(value, json!(2));
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert!(disclosure.name().is_none());
+        assert_eq!(disclosure.value(), &json!(2));
 
         let array = input.get("array").unwrap().as_array().unwrap();
-        assert_eq!(array.get(0).unwrap(), &json!(0));
+        assert_eq!(array.first().unwrap(), &json!(0));
         assert_eq!(array.get(1).unwrap(), &json!(1));
 
         let item_as_disclosure = array.get(2).unwrap().as_object().unwrap();
@@ -215,4 +217,45 @@ pub mod tests {
                 SdjError::InvalidJsonPointerPath(p) if p == "/b"
         ));
     }
+
+    #[test]
+    fn should_decode_keys_by_value_type() {
+        let mut backend = CryptoBackend::new();
+        let mut input = json!({"a/b": {"m~n": 1, "0": 2}, "array": [0, 1]});
+
+        let path = "/a~1b/m~0n".try_into().unwrap();
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("m~n"));
+
+        // an object key, even though it looks like an index
+        let path = "/a~1b/0".try_into().unwrap();
+        let disclosure = input.try_find_drop(&mut backend, &path).unwrap();
+        assert_eq!(disclosure.name(), Some("0"));
+        assert_eq!(disclosure.value(), &2);
+        assert_eq!(input["a/b"]["_sd"].as_array().unwrap().len(), 2);
+
+        let digests = input.try_find_disclosure(&path).unwrap();
+        assert!(matches!(digests, DigestRef::Object(digests) if digests.len() == 2));
+        let path = "/array/1".try_into().unwrap();
+        input.try_find_drop(&mut backend, &path).unwrap();
+        assert!(matches!(
+            input.try_find_disclosure(&path).unwrap(),
+            DigestRef::ArrayItem(_)
+        ));
+    }
+
+    #[test]
+    fn should_fail_when_not_a_claim() {
+        let mut backend = CryptoBackend::new();
+        let mut input = json!({"array": [0, 1], "a": 1});
+
+        for path in ["", "/array/-", "/array/01", "/array/2", "/a/b"] {
+            let path = path.try_into().unwrap();
+            assert!(matches!(
+                input.try_find_drop(&mut backend, &path).unwrap_err(),
+                SdjError::InvalidJsonPointerPath(p) if p == *path
+            ));
+        }
+        assert_eq!(input, json!({"array": [0, 1], "a": 1}));
+    }
 }
@@ -1,43 +1,127 @@
-use crate::error::SdjError;
+use crate::error::{SdjError, SdjResult};
+use serde_json::Value;
 use std::borrow::Cow;
 
-#[derive(Debug, Clone, derive_more::AsRef, derive_more::Deref)]
-pub(crate) struct JsonPointerPath<'a>(Cow<'a, str>);
+/// Json pointer as per [RFC6901](https://tools.ietf.org/html/rfc6901), along with its decoded
+/// reference tokens. Whether a token is an object key or an array index depends on the value it
+/// is resolved against, e.g. `/a/0` is the key `"0"` when `a` is an object.
+#[derive(Debug, Clone)]
+pub(crate) struct JsonPointerPath<'a> {
+    pointer: Cow<'a, str>,
+    tokens: Vec<String>,
+}
+
+/// A reference token resolved against the object or array it applies to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Step<'t> {
+    Key(&'t str),
+    Index(usize),
+    /// `-`, the nonexistent item after the last one of an array
+    End,
+}
 
 impl<'a> JsonPointerPath<'a> {
     const DELIMITER: char = '/';
 
-    pub fn object_key(&self) -> Option<&str> {
-        let (_, last) = self.rsplit_once(Self::DELIMITER)?;
+    fn try_new(pointer: Cow<'a, str>) -> SdjResult<Self> {
+        let invalid = || SdjError::InvalidJsonPointerPath(pointer.to_string());
+        let tokens = match pointer.strip_prefix(Self::DELIMITER) {
+            None if pointer.is_empty() => vec![],
+            None => return Err(invalid()),
+            Some(tokens) => tokens
+                .split(Self::DELIMITER)
+                .map(|token| Self::unescape(token).ok_or_else(invalid))
+                .collect::<SdjResult<_>>()?,
+        };
+        Ok(Self { pointer, tokens })
+    }
 
-        use std::str::FromStr as _;
-        // meaning it's an array index
-        if usize::from_str(last).is_ok() {
-            None
-        } else {
-            Some(last)
+    /// Decodes `~1` then `~0`, any other `~` is invalid
+    fn unescape(token: &str) -> Option<String> {
+        let mut unescaped = String::with_capacity(token.len());
+        let mut chars = token.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '~' => match chars.next()? {
+                    '0' => unescaped.push('~'),
+                    '1' => unescaped.push('/'),
+                    _ => return None,
+                },
+                c => unescaped.push(c),
+            }
         }
+        Some(unescaped)
+    }
+
+    /// Decoded reference tokens, none for the whole document
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Decoded last reference token
+    #[cfg(any(feature = "issuer", feature = "holder"))]
+    pub fn last(&self) -> Option<&str> {
+        self.tokens.last().map(String::as_str)
     }
 
-    pub fn is_key(&self) -> bool {
-        self.object_key().is_some()
+    #[cfg(any(feature = "issuer", feature = "holder"))]
+    pub fn parent(&self) -> Option<JsonPointerPath<'_>> {
+        let (parent, _) = self.pointer.rsplit_once(Self::DELIMITER)?;
+        Some(JsonPointerPath {
+            pointer: Cow::Borrowed(parent),
+            tokens: self.tokens[..self.tokens.len() - 1].to_vec(),
+        })
     }
 
-    pub fn parent(&'a self) -> Option<Self> {
-        if let Some((parent, _)) = self.rsplit_once(Self::DELIMITER) {
-            Some(Self(Cow::Borrowed(parent)))
-        } else {
-            None
+    /// Resolves a reference token against the value it applies to. Array indices are either `0`
+    /// or digits without leading zeros.
+    pub fn step<'t>(value: &Value, token: &'t str) -> Option<Step<'t>> {
+        match value {
+            Value::Object(_) => Some(Step::Key(token)),
+            Value::Array(_) if token == "-" => Some(Step::End),
+            Value::Array(_) => {
+                let is_index = token == "0"
+                    || (!token.starts_with('0') && !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit()));
+                is_index.then(|| token.parse().ok()).flatten().map(Step::Index)
+            }
+            _ => None,
         }
     }
 
-    pub fn append(&mut self, path: &str) {
-        self.0 = Cow::Owned(format!("{}{}{path}", self.0, Self::DELIMITER))
+    #[cfg(any(feature = "issuer", feature = "holder"))]
+    pub fn resolve<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.tokens
+            .iter()
+            .try_fold(value, |value, token| match Self::step(value, token)? {
+                Step::Key(key) => value.get(key),
+                Step::Index(i) => value.get(i),
+                Step::End => None,
+            })
+    }
+
+    #[cfg(feature = "issuer")]
+    pub fn resolve_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        self.tokens
+            .iter()
+            .try_fold(value, |value, token| match Self::step(value, token)? {
+                Step::Key(key) => value.get_mut(key),
+                Step::Index(i) => value.get_mut(i),
+                Step::End => None,
+            })
     }
+}
+
+impl std::ops::Deref for JsonPointerPath<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pointer
+    }
+}
 
-    // FIXME: both validations are wrong according to the RFC
-    fn is_valid(&self) -> bool {
-        !self.is_empty() && self.starts_with(Self::DELIMITER)
+impl AsRef<str> for JsonPointerPath<'_> {
+    fn as_ref(&self) -> &str {
+        &self.pointer
     }
 }
 
@@ -45,11 +129,15 @@ impl<'a> TryFrom<&'a str> for JsonPointerPath<'a> {
     type Error = SdjError;
 
     fn try_from(path: &'a str) -> Result<Self, Self::Error> {
-        let jpp = Self(Cow::Borrowed(path));
-        if !jpp.is_valid() {
-            return Err(SdjError::InvalidJsonPointerPath(path.to_string()));
-        }
-        Ok(jpp)
+        Self::try_new(Cow::Borrowed(path))
+    }
+}
+
+impl TryFrom<String> for JsonPointerPath<'static> {
+    type Error = SdjError;
+
+    fn try_from(path: String) -> Result<Self, Self::Error> {
+        Self::try_new(Cow::Owned(path))
     }
 }
 
@@ -57,20 +145,36 @@ impl<'a> TryFrom<&'a str> for JsonPointerPath<'a> {
 pub mod tests {
 
     use super::*;
+    use serde_json::json;
+
+    fn tokens(path: &str) -> Vec<String> {
+        JsonPointerPath::try_from(path).unwrap().tokens
+    }
 
-    mod object_key {
+    mod tokens {
         use super::*;
 
         #[test]
-        fn should_find_object_key() {
-            assert_eq!(JsonPointerPath::try_from("/a/b/c").unwrap().object_key(), Some("c"));
-            assert_eq!(JsonPointerPath::try_from("/a").unwrap().object_key(), Some("a"));
-            assert_eq!(JsonPointerPath::try_from("/a/0/b").unwrap().object_key(), Some("b"));
+        fn should_decode_tokens() {
+            assert_eq!(tokens("/a/b/c"), ["a", "b", "c"]);
+            assert_eq!(tokens("/a~1b/m~0n"), ["a/b", "m~n"]);
+            // `~01` is `~1` once decoded, not `/`
+            assert_eq!(tokens("/~01"), ["~1"]);
+            assert_eq!(tokens("/"), [""]);
+            assert_eq!(tokens("//a/"), ["", "a", ""]);
+            assert!(tokens("").is_empty());
         }
 
         #[test]
-        fn should_not_find_object_key_for_array_items() {
-            assert!(JsonPointerPath::try_from("/a/0").unwrap().object_key().is_none());
+        fn should_find_parent_and_last() {
+            let path = JsonPointerPath::try_from("/a/b~1c").unwrap();
+            assert_eq!(path.last(), Some("b/c"));
+            let parent = path.parent().unwrap();
+            assert_eq!(&*parent, "/a");
+            assert_eq!(parent.tokens, ["a"]);
+            let root = parent.parent().unwrap();
+            assert!(root.tokens.is_empty());
+            assert!(root.parent().is_none());
         }
     }
 
@@ -78,11 +182,8 @@ pub mod tests {
         use super::*;
 
         #[test]
-        fn should_be_invalid_when_empty() {
-            assert!(matches!(
-                JsonPointerPath::try_from("").unwrap_err(),
-                SdjError::InvalidJsonPointerPath(p) if p.is_empty()
-            ));
+        fn should_point_to_whole_document_when_empty() {
+            assert!(JsonPointerPath::try_from("").unwrap().tokens.is_empty());
         }
 
         #[test]
@@ -97,5 +198,67 @@ pub mod tests {
                 SdjError::InvalidJsonPointerPath(p) if p == "!a"
             ));
         }
+
+        #[test]
+        fn should_be_invalid_when_bad_escape() {
+            for path in ["/a~", "/a~2", "/~a"] {
+                assert!(matches!(
+                    JsonPointerPath::try_from(path).unwrap_err(),
+                    SdjError::InvalidJsonPointerPath(p) if p == path
+                ));
+            }
+        }
+    }
+
+    mod resolve {
+        use super::*;
+
+        fn resolve(value: &Value, path: &str) -> Option<Value> {
+            JsonPointerPath::try_from(path).unwrap().resolve(value).cloned()
+        }
+
+        #[test]
+        fn should_resolve_rfc_examples() {
+            let doc = json!({
+                "foo": ["bar", "baz"],
+                "": 0,
+                "a/b": 1,
+                "c%d": 2,
+                "e^f": 3,
+                "g|h": 4,
+                "i\\j": 5,
+                "k\"l": 6,
+                " ": 7,
+                "m~n": 8
+            });
+            assert_eq!(resolve(&doc, ""), Some(doc.clone()));
+            assert_eq!(resolve(&doc, "/foo"), Some(json!(["bar", "baz"])));
+            assert_eq!(resolve(&doc, "/foo/0"), Some(json!("bar")));
+            for (path, expected) in [
+                ("/", 0),
+                ("/a~1b", 1),
+                ("/c%d", 2),
+                ("/e^f", 3),
+                ("/g|h", 4),
+                ("/i\\j", 5),
+                ("/k\"l", 6),
+                ("/ ", 7),
+                ("/m~0n", 8),
+            ] {
+                assert_eq!(resolve(&doc, path), Some(json!(expected)), "{path}");
+            }
+        }
+
+        #[test]
+        fn should_tell_keys_from_indices() {
+            let doc = json!({"obj": {"0": "key"}, "array": ["item"]});
+            assert_eq!(resolve(&doc, "/obj/0"), Some(json!("key")));
+            assert_eq!(resolve(&doc, "/array/0"), Some(json!("item")));
+            for path in ["/array/00", "/array/-", "/array/+0", "/array/a", "/array/1", "/obj/0/a"] {
+                assert_eq!(resolve(&doc, path), None, "{path}");
+            }
+            assert_eq!(JsonPointerPath::step(&doc["array"], "-"), Some(Step::End));
+            assert_eq!(JsonPointerPath::step(&doc["obj"], "-"), Some(Step::Key("-")));
+        }
     }
 }
@@ -0,0 +1,344 @@
+use crate::{
+    core::{jwk::Jwk, sd_jwt::SDJwtJson},
+    error::{SdjError, SdjResult},
+    prelude::{JwsAlgorithm, SDJwt},
+};
+use aes_gcm::{aead::AeadInPlace, Aes128Gcm, Aes256Gcm, KeyInit};
+use ed25519_compact::x25519;
+use jwt_simple::prelude::{
+    ECDSAP256KeyPairLike, ECDSAP256PublicKeyLike, ECDSAP384KeyPairLike, ECDSAP384PublicKeyLike, ES256KeyPair,
+    ES384KeyPair, Ed25519KeyPair,
+};
+use sha2::Digest;
+
+/// Key agreement of the JWEs, directly deriving the content encryption key
+pub const ECDH_ES: &str = "ECDH-ES";
+
+/// Content encryption algorithm
+///
+/// See also: https://www.rfc-editor.org/rfc/rfc7518#section-5.3
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ContentEncryption {
+    #[default]
+    A128GCM,
+    A256GCM,
+}
+
+impl ContentEncryption {
+    fn key_len(&self) -> usize {
+        match self {
+            Self::A128GCM => 16,
+            Self::A256GCM => 32,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::A128GCM => "A128GCM",
+            Self::A256GCM => "A256GCM",
+        }
+    }
+}
+
+/// How a [SDJwt] is serialized before being encrypted
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SDJwtSerialization {
+    /// `<Issuer-signed JWT>~<Disclosure 1>~...~<optional KB-JWT>`
+    #[default]
+    Compact,
+    /// See [SDJwtJson]
+    Json,
+}
+
+impl SDJwt {
+    /// Encrypts the serialized SD-JWT to the public key of the recipient, i.e. the key it signs
+    /// with, in the JWE compact serialization
+    pub fn try_encrypt(
+        &self,
+        recipient: &Jwk,
+        enc: ContentEncryption,
+        serialization: SDJwtSerialization,
+    ) -> SdjResult<String> {
+        let plaintext = match serialization {
+            SDJwtSerialization::Compact => self.try_serialize()?.into_bytes(),
+            SDJwtSerialization::Json => serde_json::to_vec(&self.try_to_json()?)?,
+        };
+        try_encrypt(&plaintext, recipient, enc)
+    }
+
+    /// Decrypts with the PEM encoded key pair of the recipient, whichever the SD-JWT serialization
+    pub fn try_decrypt(jwe: &str, alg: JwsAlgorithm, key_pair: &str) -> SdjResult<Self> {
+        let plaintext = try_decrypt(jwe, alg, key_pair)?;
+        if plaintext.first() == Some(&b'{') {
+            let json: SDJwtJson = serde_json::from_slice(&plaintext)?;
+            return json.try_into();
+        }
+        String::from_utf8(plaintext)
+            .map_err(|_| SdjError::InvalidSerializedSdJwt)?
+            .parse()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct JweHeader {
+    alg: String,
+    enc: ContentEncryption,
+    /// Ephemeral public key of the sender
+    epk: Jwk,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    apu: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    apv: Option<String>,
+}
+
+/// Encrypts to a P-256, P-384 or Ed25519 (converted to X25519) recipient key, in the JWE compact
+/// serialization
+///
+/// See also: https://www.rfc-editor.org/rfc/rfc7518#section-4.6
+pub(crate) fn try_encrypt(plaintext: &[u8], recipient: &Jwk, enc: ContentEncryption) -> SdjResult<String> {
+    let (epk, z) = match recipient.try_alg()? {
+        JwsAlgorithm::P256 => {
+            let ephemeral = ES256KeyPair::generate();
+            let secret = ephemeral.key_pair().as_ref().as_nonzero_scalar();
+            let z = p256::ecdh::diffie_hellman(secret, try_p256_public_key(recipient)?.as_affine());
+            let epk = Jwk::try_from_public_pem(JwsAlgorithm::P256, &ephemeral.public_key().to_pem()?)?;
+            (epk, z.raw_secret_bytes().to_vec())
+        }
+        JwsAlgorithm::P384 => {
+            let ephemeral = ES384KeyPair::generate();
+            let secret = ephemeral.key_pair().as_ref().as_nonzero_scalar();
+            let z = p384::ecdh::diffie_hellman(secret, try_p384_public_key(recipient)?.as_affine());
+            let epk = Jwk::try_from_public_pem(JwsAlgorithm::P384, &ephemeral.public_key().to_pem()?)?;
+            (epk, z.raw_secret_bytes().to_vec())
+        }
+        JwsAlgorithm::Ed25519 => {
+            let recipient_key = x25519::PublicKey::from_ed25519(&try_ed25519_public_key(recipient)?)
+                .map_err(|_| SdjError::InvalidJwk)?;
+            let ephemeral = x25519::KeyPair::generate();
+            let z = recipient_key.dh(&ephemeral.sk).map_err(|_| SdjError::InvalidJwe)?;
+            let epk = Jwk {
+                kty: "OKP".to_string(),
+                crv: X25519.to_string(),
+                x: base64_simd::URL_SAFE_NO_PAD.encode_to_string(*ephemeral.pk),
+                y: None,
+                kid: None,
+            };
+            (epk, z.to_vec())
+        }
+    };
+
+    let header = JweHeader {
+        alg: ECDH_ES.to_string(),
+        enc,
+        epk,
+        kid: recipient.kid.clone(),
+        apu: None,
+        apv: None,
+    };
+    let header = base64_simd::URL_SAFE_NO_PAD.encode_to_string(serde_json::to_vec(&header)?);
+    let cek = concat_kdf(&z, enc, &[], &[]);
+
+    let mut iv = [0u8; 12];
+    getrandom::getrandom(&mut iv).map_err(|_| crate::crypto::error::CryptoError::RandomError)?;
+    let mut ciphertext = plaintext.to_vec();
+    let tag = match enc {
+        ContentEncryption::A128GCM => Aes128Gcm::new_from_slice(&cek)
+            .map_err(|_| SdjError::InvalidJwe)?
+            .encrypt_in_place_detached(&iv.into(), header.as_bytes(), &mut ciphertext),
+        ContentEncryption::A256GCM => Aes256Gcm::new_from_slice(&cek)
+            .map_err(|_| SdjError::InvalidJwe)?
+            .encrypt_in_place_detached(&iv.into(), header.as_bytes(), &mut ciphertext),
+    }
+    .map_err(|_| SdjError::InvalidJwe)?;
+
+    let encode = |v: &[u8]| base64_simd::URL_SAFE_NO_PAD.encode_to_string(v);
+    Ok(format!(
+        "{header}..{}.{}.{}",
+        encode(&iv),
+        encode(&ciphertext),
+        encode(&tag)
+    ))
+}
+
+/// Decrypts with the PEM encoded key pair of the recipient, the one it signs with using `alg`
+pub(crate) fn try_decrypt(jwe: &str, alg: JwsAlgorithm, key_pair: &str) -> SdjResult<Vec<u8>> {
+    let decode = |part: &str| {
+        base64_simd::URL_SAFE_NO_PAD
+            .decode_to_vec(part)
+            .map_err(|_| SdjError::InvalidJwe)
+    };
+    let parts = jwe.split('.').collect::<Vec<_>>();
+    let [protected, encrypted_key, iv, ciphertext, tag] = parts.as_slice() else {
+        return Err(SdjError::InvalidJwe);
+    };
+    let header: JweHeader = serde_json::from_slice(&decode(protected)?).map_err(|_| SdjError::InvalidJwe)?;
+    if header.alg != ECDH_ES || !encrypted_key.is_empty() {
+        return Err(SdjError::UnsupportedAlgorithm(header.alg));
+    }
+    let iv: [u8; 12] = decode(iv)?.try_into().map_err(|_| SdjError::InvalidJwe)?;
+    let tag: [u8; 16] = decode(tag)?.try_into().map_err(|_| SdjError::InvalidJwe)?;
+    let apu = header.apu.as_deref().map(decode).transpose()?.unwrap_or_default();
+    let apv = header.apv.as_deref().map(decode).transpose()?.unwrap_or_default();
+
+    let z = match alg {
+        JwsAlgorithm::P256 => {
+            let key_pair = ES256KeyPair::from_pem(key_pair)?;
+            let secret = key_pair.key_pair().as_ref().as_nonzero_scalar();
+            let epk = try_p256_public_key(&header.epk).map_err(|_| SdjError::InvalidJwe)?;
+            p256::ecdh::diffie_hellman(secret, epk.as_affine())
+                .raw_secret_bytes()
+                .to_vec()
+        }
+        JwsAlgorithm::P384 => {
+            let key_pair = ES384KeyPair::from_pem(key_pair)?;
+            let secret = key_pair.key_pair().as_ref().as_nonzero_scalar();
+            let epk = try_p384_public_key(&header.epk).map_err(|_| SdjError::InvalidJwe)?;
+            p384::ecdh::diffie_hellman(secret, epk.as_affine())
+                .raw_secret_bytes()
+                .to_vec()
+        }
+        JwsAlgorithm::Ed25519 => {
+            let key_pair = Ed25519KeyPair::from_pem(key_pair)?;
+            let secret = ed25519_compact::SecretKey::from_slice(&key_pair.to_bytes())
+                .and_then(|sk| x25519::SecretKey::from_ed25519(&sk))
+                .map_err(|_| SdjError::InvalidJwe)?;
+            if header.epk.crv != X25519 {
+                return Err(SdjError::InvalidJwe);
+            }
+            let epk = x25519::PublicKey::from_slice(&decode(&header.epk.x)?).map_err(|_| SdjError::InvalidJwe)?;
+            epk.dh(&secret).map_err(|_| SdjError::InvalidJwe)?.to_vec()
+        }
+    };
+    let cek = concat_kdf(&z, header.enc, &apu, &apv);
+
+    let mut plaintext = decode(ciphertext)?;
+    match header.enc {
+        ContentEncryption::A128GCM => Aes128Gcm::new_from_slice(&cek)
+            .map_err(|_| SdjError::InvalidJwe)?
+            .decrypt_in_place_detached(&iv.into(), protected.as_bytes(), &mut plaintext, &tag.into()),
+        ContentEncryption::A256GCM => Aes256Gcm::new_from_slice(&cek)
+            .map_err(|_| SdjError::InvalidJwe)?
+            .decrypt_in_place_detached(&iv.into(), protected.as_bytes(), &mut plaintext, &tag.into()),
+    }
+    .map_err(|_| SdjError::InvalidJwe)?;
+    Ok(plaintext)
+}
+
+const X25519: &str = "X25519";
+
+fn try_p256_public_key(jwk: &Jwk) -> SdjResult<p256::PublicKey> {
+    if jwk.try_alg()? != JwsAlgorithm::P256 {
+        return Err(SdjError::UnsupportedAlgorithm(jwk.crv.clone()));
+    }
+    let point = jwt_simple::prelude::ES256PublicKey::from_pem(&jwk.try_to_public_pem()?)?
+        .public_key()
+        .to_bytes_uncompressed();
+    p256::PublicKey::from_sec1_bytes(&point).map_err(|_| SdjError::InvalidJwk)
+}
+
+fn try_p384_public_key(jwk: &Jwk) -> SdjResult<p384::PublicKey> {
+    if jwk.try_alg()? != JwsAlgorithm::P384 {
+        return Err(SdjError::UnsupportedAlgorithm(jwk.crv.clone()));
+    }
+    let point = jwt_simple::prelude::ES384PublicKey::from_pem(&jwk.try_to_public_pem()?)?
+        .public_key()
+        .to_bytes_uncompressed();
+    p384::PublicKey::from_sec1_bytes(&point).map_err(|_| SdjError::InvalidJwk)
+}
+
+fn try_ed25519_public_key(jwk: &Jwk) -> SdjResult<ed25519_compact::PublicKey> {
+    let x = base64_simd::URL_SAFE_NO_PAD.decode_to_vec(&jwk.x)?;
+    ed25519_compact::PublicKey::from_slice(&x).map_err(|_| SdjError::InvalidJwk)
+}
+
+/// Single round of the Concat KDF, enough for the content encryption keys of AES-GCM
+///
+/// See also: https://www.rfc-editor.org/rfc/rfc7518#section-4.6.2
+fn concat_kdf(z: &[u8], enc: ContentEncryption, apu: &[u8], apv: &[u8]) -> Vec<u8> {
+    let key_len = enc.key_len();
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(z);
+    for info in [enc.as_str().as_bytes(), apu, apv] {
+        hasher.update((info.len() as u32).to_be_bytes());
+        hasher.update(info);
+    }
+    hasher.update(((key_len * 8) as u32).to_be_bytes());
+    hasher.finalize()[..key_len].to_vec()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions};
+    use serde_json::json;
+
+    fn key_pairs() -> [(JwsAlgorithm, String); 3] {
+        [
+            (JwsAlgorithm::Ed25519, Ed25519KeyPair::generate().to_pem()),
+            (JwsAlgorithm::P256, ES256KeyPair::generate().to_pem().unwrap()),
+            (JwsAlgorithm::P384, ES384KeyPair::generate().to_pem().unwrap()),
+        ]
+    }
+
+    #[test]
+    fn should_derive_key_like_rfc7518() {
+        // https://www.rfc-editor.org/rfc/rfc7518#appendix-C
+        let decode = |v: &str| base64_simd::URL_SAFE_NO_PAD.decode_to_vec(v).unwrap();
+        let alice = p256::SecretKey::from_slice(&decode("0_NxaRPUMQoAJt50Gz8YiTr8gRTwyEaCumd-MToTmIo")).unwrap();
+        let bob = try_p256_public_key(&Jwk {
+            kty: "EC".to_string(),
+            crv: "P-256".to_string(),
+            x: "weNJy2HscCSM6AEDTDg04biOvhFhyyWvOHQfeF_PxMQ".to_string(),
+            y: Some("e8lnCO-AlStT-NJVX-crhB7QRYhiix03illJOVAOyck".to_string()),
+            kid: None,
+        })
+        .unwrap();
+        let z = p256::ecdh::diffie_hellman(alice.to_nonzero_scalar(), bob.as_affine());
+        let cek = concat_kdf(z.raw_secret_bytes(), ContentEncryption::A128GCM, b"Alice", b"Bob");
+        assert_eq!(
+            base64_simd::URL_SAFE_NO_PAD.encode_to_string(cek),
+            "VqqN6vgjbSBcIijNcacQGg"
+        );
+    }
+
+    #[test]
+    fn should_encrypt_and_decrypt() {
+        for (alg, key_pair) in key_pairs() {
+            let recipient = Jwk::try_from_key_pair_pem(alg, &key_pair).unwrap();
+            for enc in [ContentEncryption::A128GCM, ContentEncryption::A256GCM] {
+                let jwe = try_encrypt(b"{\"vp_token\":{}}", &recipient, enc).unwrap();
+                assert_eq!(try_decrypt(&jwe, alg, &key_pair).unwrap(), b"{\"vp_token\":{}}");
+
+                let (_, other) = key_pairs().into_iter().find(|(a, _)| *a == alg).unwrap();
+                assert!(matches!(try_decrypt(&jwe, alg, &other), Err(SdjError::InvalidJwe)));
+            }
+        }
+    }
+
+    #[test]
+    fn should_wrap_sd_jwt() {
+        let input = json!({"given_name": "John", "family_name": "Doe"});
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt(input, &["/given_name", "/family_name"], IssuerOptions::default())
+            .unwrap();
+        let expected = sd_jwt.try_serialize().unwrap();
+        for (alg, key_pair) in key_pairs() {
+            let recipient = Jwk::try_from_key_pair_pem(alg, &key_pair).unwrap();
+            for serialization in [SDJwtSerialization::Compact, SDJwtSerialization::Json] {
+                let jwe = sd_jwt
+                    .try_encrypt(&recipient, ContentEncryption::A256GCM, serialization)
+                    .unwrap();
+                let decrypted = SDJwt::try_decrypt(&jwe, alg, &key_pair).unwrap();
+                assert_eq!(decrypted.try_serialize().unwrap(), expected);
+            }
+        }
+
+        let json = sd_jwt.try_to_json().unwrap();
+        assert_eq!(json.header.disclosures.len(), 2);
+        assert_eq!(SDJwt::try_from(json).unwrap().try_serialize().unwrap(), expected);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    error::{SdjError, SdjResult},
+    prelude::JwsAlgorithm,
+};
+use jwt_simple::prelude::{
+    ECDSAP256PublicKeyLike, ECDSAP384PublicKeyLike, ES256KeyPair, ES256PublicKey, ES384KeyPair, ES384PublicKey,
+    Ed25519KeyPair, Ed25519PublicKey,
+};
+
+/// A public key as a [JSON Web Key](https://www.rfc-editor.org/rfc/rfc7517)
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Jwk {
+    /// `OKP` for Ed25519, `EC` for P-256 & P-384
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+}
+
+/// See also: https://www.rfc-editor.org/rfc/rfc7517#section-5
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl Jwk {
+    /// From a PEM encoded public key
+    pub fn try_from_public_pem(alg: JwsAlgorithm, pem: &str) -> SdjResult<Self> {
+        Ok(match alg {
+            JwsAlgorithm::Ed25519 => Self::okp(&Ed25519PublicKey::from_pem(pem)?.to_bytes()),
+            JwsAlgorithm::P256 => Self::ec(
+                "P-256",
+                &ES256PublicKey::from_pem(pem)?.public_key().to_bytes_uncompressed(),
+            )?,
+            JwsAlgorithm::P384 => Self::ec(
+                "P-384",
+                &ES384PublicKey::from_pem(pem)?.public_key().to_bytes_uncompressed(),
+            )?,
+        })
+    }
+
+    /// The public part of a PEM encoded key pair
+    pub fn try_from_key_pair_pem(alg: JwsAlgorithm, pem: &str) -> SdjResult<Self> {
+        let public_pem = match alg {
+            JwsAlgorithm::Ed25519 => Ed25519KeyPair::from_pem(pem)?.public_key().to_pem(),
+            JwsAlgorithm::P256 => ES256KeyPair::from_pem(pem)?.public_key().to_pem()?,
+            JwsAlgorithm::P384 => ES384KeyPair::from_pem(pem)?.public_key().to_pem()?,
+        };
+        Self::try_from_public_pem(alg, &public_pem)
+    }
+
+    /// The signature algorithm this key is meant for
+    pub fn try_alg(&self) -> SdjResult<JwsAlgorithm> {
+        match (self.kty.as_str(), self.crv.as_str()) {
+            ("OKP", "Ed25519") => Ok(JwsAlgorithm::Ed25519),
+            ("EC", "P-256") => Ok(JwsAlgorithm::P256),
+            ("EC", "P-384") => Ok(JwsAlgorithm::P384),
+            _ => Err(SdjError::InvalidJwk),
+        }
+    }
+
+    /// To a PEM encoded public key
+    pub fn try_to_public_pem(&self) -> SdjResult<String> {
+        let x = base64_simd::URL_SAFE_NO_PAD.decode_to_vec(&self.x)?;
+        let point = || -> SdjResult<Vec<u8>> {
+            let y = self.y.as_ref().ok_or(SdjError::InvalidJwk)?;
+            let y = base64_simd::URL_SAFE_NO_PAD.decode_to_vec(y)?;
+            Ok([&[0x04], x.as_slice(), y.as_slice()].concat())
+        };
+        Ok(match self.try_alg()? {
+            JwsAlgorithm::Ed25519 => Ed25519PublicKey::from_bytes(&x)?.to_pem(),
+            JwsAlgorithm::P256 => ES256PublicKey::from_bytes(&point()?)?.to_pem()?,
+            JwsAlgorithm::P384 => ES384PublicKey::from_bytes(&point()?)?.to_pem()?,
+        })
+    }
+
+    fn okp(x: &[u8]) -> Self {
+        Self {
+            kty: "OKP".to_string(),
+            crv: "Ed25519".to_string(),
+            x: base64_simd::URL_SAFE_NO_PAD.encode_to_string(x),
+            y: None,
+            kid: None,
+        }
+    }
+
+    /// From an uncompressed SEC1 point
+    fn ec(crv: &str, point: &[u8]) -> SdjResult<Self> {
+        let coordinates = point.strip_prefix(&[0x04]).ok_or(SdjError::InvalidJwk)?;
+        let (x, y) = coordinates.split_at(coordinates.len() / 2);
+        Ok(Self {
+            kty: "EC".to_string(),
+            crv: crv.to_string(),
+            x: base64_simd::URL_SAFE_NO_PAD.encode_to_string(x),
+            y: Some(base64_simd::URL_SAFE_NO_PAD.encode_to_string(y)),
+            kid: None,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_round_trip_pem() {
+        let key_pairs = [
+            (JwsAlgorithm::Ed25519, Ed25519KeyPair::generate().to_pem()),
+            (JwsAlgorithm::P256, ES256KeyPair::generate().to_pem().unwrap()),
+            (JwsAlgorithm::P384, ES384KeyPair::generate().to_pem().unwrap()),
+        ];
+        for (alg, key_pair) in key_pairs {
+            let jwk = Jwk::try_from_key_pair_pem(alg, &key_pair).unwrap();
+            assert_eq!(jwk.try_alg().unwrap(), alg);
+            let pem = jwk.try_to_public_pem().unwrap();
+            assert_eq!(Jwk::try_from_public_pem(alg, &pem).unwrap(), jwk);
+        }
+    }
+
+    #[test]
+    fn should_fail_when_unsupported() {
+        let jwk = Jwk {
+            kty: "RSA".to_string(),
+            crv: "".to_string(),
+            x: "".to_string(),
+            y: None,
+            kid: None,
+        };
+        assert!(matches!(jwk.try_alg(), Err(SdjError::InvalidJwk)));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{
+    core::jwk::Jwk,
+    error::{SdjError, SdjResult},
+    prelude::JwsAlgorithm,
+};
+use jwt_simple::prelude::{
+    ECDSAP256KeyPairLike, ECDSAP384KeyPairLike, ES256KeyPair, ES384KeyPair, Ed25519KeyPair, EdDSAKeyPairLike,
+};
+use serde_json::{Map, Value};
+
+/// JOSE header of the JWTs exchanged with the protocols built on top of SD-JWT, which need a
+/// specific `typ` and sometimes the key in the header
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct JwtHeader {
+    pub alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwk: Option<Jwk>,
+    /// Any other header parameter e.g. `x5c`
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl JwtHeader {
+    pub fn new(alg: JwsAlgorithm, typ: &str) -> Self {
+        Self {
+            alg: alg.to_jws_alg().to_string(),
+            typ: Some(typ.to_string()),
+            kid: None,
+            jwk: None,
+            other: Map::new(),
+        }
+    }
+
+    pub fn with_jwk(mut self, jwk: Jwk) -> Self {
+        self.jwk = Some(jwk);
+        self
+    }
+
+    pub fn try_alg(&self) -> SdjResult<JwsAlgorithm> {
+        JwsAlgorithm::from_jws_alg(&self.alg).ok_or_else(|| SdjError::UnsupportedAlgorithm(self.alg.clone()))
+    }
+
+    /// Signs the claims with a PEM encoded key pair
+    pub fn try_sign(&self, claims: &impl serde::Serialize, key_pair: &str) -> SdjResult<String> {
+        use p256::ecdsa::signature::Signer as _;
+
+        let encode = |v: Vec<u8>| base64_simd::URL_SAFE_NO_PAD.encode_to_string(v);
+        let signing_input = format!(
+            "{}.{}",
+            encode(serde_json::to_vec(self)?),
+            encode(serde_json::to_vec(claims)?)
+        );
+        let message = signing_input.as_bytes();
+        let signature = match self.try_alg()? {
+            JwsAlgorithm::Ed25519 => {
+                let key_pair = Ed25519KeyPair::from_pem(key_pair)?;
+                key_pair.key_pair().as_ref().sk.sign(message, None).to_vec()
+            }
+            JwsAlgorithm::P256 => {
+                let key_pair = ES256KeyPair::from_pem(key_pair)?;
+                let signature: p256::ecdsa::Signature = key_pair.key_pair().as_ref().sign(message);
+                signature.to_vec()
+            }
+            JwsAlgorithm::P384 => {
+                let key_pair = ES384KeyPair::from_pem(key_pair)?;
+                let signature: p384::ecdsa::Signature = key_pair.key_pair().as_ref().sign(message);
+                signature.to_vec()
+            }
+        };
+        Ok(format!("{signing_input}.{}", encode(signature)))
+    }
+}
+
+/// Verifies the signature with a PEM encoded public key then returns the header & claims. Time
+/// related checks are up to the caller since they differ between protocols.
+#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
+pub(crate) fn try_verify(token: &str, verifying_key: &str) -> SdjResult<(JwtHeader, Value)> {
+    use jwt_simple::prelude::{
+        ECDSAP256PublicKeyLike, ECDSAP384PublicKeyLike, ES256PublicKey, ES384PublicKey, Ed25519PublicKey,
+        EdDSAPublicKeyLike,
+    };
+    use p256::ecdsa::signature::Verifier as _;
+
+    let (header, claims) = try_decode_unverified(token)?;
+    let (signing_input, signature) = token.rsplit_once('.').ok_or(SdjError::InvalidJwt)?;
+    let signature = base64_simd::URL_SAFE_NO_PAD.decode_to_vec(signature)?;
+    let message = signing_input.as_bytes();
+    let verified = match header.try_alg()? {
+        JwsAlgorithm::Ed25519 => {
+            let signature = ed25519_compact::Signature::from_slice(&signature).map_err(|_| SdjError::InvalidJwt)?;
+            let public_key = Ed25519PublicKey::from_pem(verifying_key)?;
+            public_key.public_key().as_ref().verify(message, &signature).is_ok()
+        }
+        JwsAlgorithm::P256 => {
+            let signature = p256::ecdsa::Signature::from_slice(&signature).map_err(|_| SdjError::InvalidJwt)?;
+            let public_key = ES256PublicKey::from_pem(verifying_key)?;
+            public_key.public_key().as_ref().verify(message, &signature).is_ok()
+        }
+        JwsAlgorithm::P384 => {
+            let signature = p384::ecdsa::Signature::from_slice(&signature).map_err(|_| SdjError::InvalidJwt)?;
+            let public_key = ES384PublicKey::from_pem(verifying_key)?;
+            public_key.public_key().as_ref().verify(message, &signature).is_ok()
+        }
+    };
+    if !verified {
+        return Err(SdjError::InvalidJwt);
+    }
+    Ok((header, claims))
+}
+
+/// Decodes the header & claims without verifying the signature
+pub(crate) fn try_decode_unverified(token: &str) -> SdjResult<(JwtHeader, Value)> {
+    let mut parts = token.split('.');
+    let (header, claims) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(claims), Some(_), None) => (header, claims),
+        _ => return Err(SdjError::InvalidJwt),
+    };
+    let decode = |part: &str| base64_simd::URL_SAFE_NO_PAD.decode_to_vec(part);
+    let header = serde_json::from_slice(&decode(header)?).map_err(|_| SdjError::InvalidJwt)?;
+    let claims = serde_json::from_slice(&decode(claims)?).map_err(|_| SdjError::InvalidJwt)?;
+    Ok((header, claims))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_sign_and_verify() {
+        let key_pairs = [
+            (JwsAlgorithm::Ed25519, Ed25519KeyPair::generate().to_pem()),
+            (JwsAlgorithm::P256, ES256KeyPair::generate().to_pem().unwrap()),
+            (JwsAlgorithm::P384, ES384KeyPair::generate().to_pem().unwrap()),
+        ];
+        for (alg, key_pair) in key_pairs {
+            let jwk = Jwk::try_from_key_pair_pem(alg, &key_pair).unwrap();
+            let header = JwtHeader::new(alg, "openid4vci-proof+jwt").with_jwk(jwk.clone());
+            let claims = json!({"aud": "https://issuer.example.com", "iat": 1700000000, "nonce": "n-0S6_WzA2Mj"});
+            let token = header.try_sign(&claims, &key_pair).unwrap();
+
+            let (read_header, read_claims) = try_verify(&token, &jwk.try_to_public_pem().unwrap()).unwrap();
+            assert_eq!(read_header, header);
+            assert_eq!(read_claims, claims);
+
+            let other = Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &ES256KeyPair::generate().to_pem().unwrap())
+                .unwrap()
+                .try_to_public_pem()
+                .unwrap();
+            assert!(try_verify(&token, &other).is_err());
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{
+    core::jwt::{try_decode_unverified, JwtHeader},
+    error::{SdjError, SdjResult},
+    prelude::SDJwt,
+};
+use serde_json::{Map, Value};
+
+/// `typ` of the Key Binding JWT
+#[cfg(any(feature = "holder", feature = "verifier"))]
+pub const KB_JWT_TYP: &str = "kb+jwt";
+
+/// Claims of the Key Binding JWT, binding a presentation to a Verifier and a transaction
+///
+/// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-08.html#name-key-binding-jwt
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct KeyBindingClaims {
+    pub iat: u64,
+    /// The Verifier, in OpenID4VP its `client_id`
+    pub aud: String,
+    pub nonce: String,
+    /// Digest of the presentation the KB-JWT is appended to
+    pub sd_hash: String,
+    /// Digests of the `transaction_data` of the OpenID4VP request, as passed i.e. base64url encoded
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-transaction-data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_data_hashes: Option<Vec<String>>,
+    /// Defaults to `sha-256`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_data_hashes_alg: Option<String>,
+    /// Any other claim e.g. required by a profile
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl KeyBindingClaims {
+    /// Claims which can't be overridden by [KeyBindingClaims::other]
+    pub const RESERVED: [&'static str; 6] = [
+        "iat",
+        "aud",
+        "nonce",
+        "sd_hash",
+        "transaction_data_hashes",
+        "transaction_data_hashes_alg",
+    ];
+}
+
+impl SDJwt {
+    /// Decodes the KB-JWT, if any, without verifying it
+    pub fn try_read_key_binding(&self) -> SdjResult<Option<(JwtHeader, KeyBindingClaims)>> {
+        let Some(kb_jwt) = self.key_binding.as_deref() else {
+            return Ok(None);
+        };
+        let (header, claims) = try_decode_unverified(kb_jwt)?;
+        let claims = serde_json::from_value(claims).map_err(|e| SdjError::InvalidKeyBinding(e.to_string()))?;
+        Ok(Some((header, claims)))
+    }
+}
+
+/// Computes the `sd_hash` of a serialized presentation, without its KB-JWT i.e. ending with `~`
+#[cfg(any(feature = "holder", feature = "verifier"))]
+pub(crate) fn try_sd_hash(presentation: &str, payload: &Value) -> SdjResult<String> {
+    use sha2::Digest as _;
+
+    match payload.get(crate::core::processing::SD_ALG).and_then(|a| a.as_str()) {
+        None | Some("sha-256") => {}
+        Some(alg) => return Err(SdjError::UnsupportedHashAlgorithm(alg.to_string())),
+    }
+    let digest = sha2::Sha256::digest(presentation.as_bytes());
+    Ok(base64_simd::URL_SAFE_NO_PAD.encode_to_string(digest))
+}
+
+/// Computes the `transaction_data_hashes` of base64url encoded transaction data, with the
+/// `transaction_data_hashes_alg` i.e. `sha-256` when `None`
+#[cfg(any(feature = "holder", feature = "verifier"))]
+pub(crate) fn try_transaction_data_hashes(transaction_data: &[String], alg: Option<&str>) -> SdjResult<Vec<String>> {
+    use crate::crypto::hash::HashAlgorithm;
+    use sha2::Digest as _;
+
+    let sha_256 = HashAlgorithm::SHA256.to_jwt_claim();
+    match alg {
+        None => {}
+        Some(alg) if alg == sha_256 => {}
+        Some(alg) => return Err(SdjError::UnsupportedHashAlgorithm(alg.to_string())),
+    }
+    Ok(transaction_data
+        .iter()
+        .map(|data| base64_simd::URL_SAFE_NO_PAD.encode_to_string(sha2::Sha256::digest(data.as_bytes())))
+        .collect())
+}
@@ -2,6 +2,7 @@ use jwt_simple::prelude::{ES256KeyPair, ES384KeyPair, Ed25519KeyPair};
 
 use crate::prelude::SdjError;
 
+#[allow(dead_code)]
 #[derive(derive_more::AsRef, derive_more::Deref)]
 pub struct JwsSignatureKeyPair<KP>(KP);
 
@@ -1,6 +1,24 @@
+#[cfg(feature = "issuer")]
+pub mod claims;
+pub mod dcql;
 pub mod disclosure;
 pub mod disclosure_hash;
+pub mod json_path;
 pub mod json_pointer;
+#[cfg(feature = "jwe")]
+pub mod jwe;
+pub mod jwk;
 pub mod jws;
+pub mod jwt;
+pub mod key_binding;
 pub mod keys;
+pub mod oid4vci;
+pub mod oid4vp;
+pub mod presentation_exchange;
+#[cfg(any(feature = "holder", feature = "verifier"))]
+pub mod processing;
 pub mod sd_jwt;
+#[cfg(any(feature = "holder", feature = "verifier"))]
+pub mod url;
+#[cfg(feature = "holder")]
+pub mod x509;
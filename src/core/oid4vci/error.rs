@@ -0,0 +1,52 @@
+use crate::core::oid4vci::CredentialErrorResponse;
+
+/// Errors of the credential endpoint
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-error-response
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Oid4vciError {
+    #[error("Invalid credential request: {0}")]
+    InvalidCredentialRequest(String),
+    #[error("Unknown credential configuration '{0}'")]
+    UnknownCredentialConfiguration(String),
+    #[error("Invalid proof: {0}")]
+    InvalidProof(String),
+    #[error("Invalid or expired c_nonce")]
+    InvalidNonce,
+    /// The Credential Issuer answered with an error
+    #[error("Credential Issuer error '{}'", .0.error)]
+    ErrorResponse(CredentialErrorResponse),
+    #[error("Invalid credential offer: {0}")]
+    InvalidCredentialOffer(String),
+    #[error("Invalid Credential Issuer metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("Issued credential does not match the Credential Issuer metadata: {0}")]
+    InvalidCredential(String),
+}
+
+impl Oid4vciError {
+    /// Value of the `error` parameter of the error response
+    pub fn error_code(&self) -> &str {
+        match self {
+            Self::InvalidCredentialRequest(_)
+            | Self::InvalidCredentialOffer(_)
+            | Self::InvalidMetadata(_)
+            | Self::InvalidCredential(_) => "invalid_credential_request",
+            Self::UnknownCredentialConfiguration(_) => "unknown_credential_configuration",
+            Self::InvalidProof(_) => "invalid_proof",
+            Self::InvalidNonce => "invalid_nonce",
+            Self::ErrorResponse(response) => &response.error,
+        }
+    }
+
+    /// The body of the error response to send back to the Wallet
+    pub fn to_error_response(&self) -> CredentialErrorResponse {
+        match self {
+            Self::ErrorResponse(response) => response.clone(),
+            _ => CredentialErrorResponse {
+                error: self.error_code().to_string(),
+                error_description: Some(self.to_string()),
+            },
+        }
+    }
+}
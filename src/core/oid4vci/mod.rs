@@ -0,0 +1,226 @@
+//! Protocol objects of [OpenID for Verifiable Credential Issuance](https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html)
+
+use crate::core::jwk::Jwk;
+use std::collections::BTreeMap;
+
+pub mod error;
+
+/// `typ` of the key proof JWT
+#[cfg(any(feature = "issuer", feature = "holder"))]
+pub const PROOF_JWT_TYP: &str = "openid4vci-proof+jwt";
+
+/// Scheme of the URIs a Credential Issuer offers credentials with
+#[cfg(feature = "holder")]
+pub const CREDENTIAL_OFFER_SCHEME: &str = "openid-credential-offer://";
+
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-offer-parameters
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CredentialOffer {
+    pub credential_issuer: String,
+    pub credential_configuration_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grants: Option<Grants>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Grants {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_code: Option<AuthorizationCodeGrant>,
+    #[serde(
+        rename = "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pre_authorized_code: Option<PreAuthorizedCodeGrant>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AuthorizationCodeGrant {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer_state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_server: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PreAuthorizedCodeGrant {
+    #[serde(rename = "pre-authorized_code")]
+    pub pre_authorized_code: String,
+    /// Describes the transaction code the End-User will have to provide, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_code: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_server: Option<String>,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-issuer-metadata-p
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CredentialIssuerMetadata {
+    pub credential_issuer: String,
+    pub credential_endpoint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce_endpoint: Option<String>,
+    pub credential_configurations_supported: BTreeMap<String, CredentialConfigurationSupported>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CredentialConfigurationSupported {
+    /// e.g. `dc+sd-jwt`
+    pub format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vct: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// e.g. `jwk`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cryptographic_binding_methods_supported: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_signing_alg_values_supported: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof_types_supported: Option<BTreeMap<String, ProofTypeSupported>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProofTypeSupported {
+    pub proof_signing_alg_values_supported: Vec<String>,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-request
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CredentialRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_configuration_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_identifier: Option<String>,
+    /// A single proof, as in the earlier drafts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<Proof>,
+    /// One proof per credential to issue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proofs: Option<Proofs>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Proof {
+    /// Only `jwt` is supported
+    pub proof_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Proofs {
+    #[serde(default)]
+    pub jwt: Vec<String>,
+}
+
+impl CredentialRequest {
+    /// All the key proof JWTs, whichever way they are sent
+    pub fn proof_jwts(&self) -> Vec<&str> {
+        let single = self
+            .proof
+            .iter()
+            .filter(|p| p.proof_type == "jwt")
+            .filter_map(|p| p.jwt.as_deref());
+        let batch = self.proofs.iter().flat_map(|p| p.jwt.iter().map(String::as_str));
+        single.chain(batch).collect()
+    }
+}
+
+/// Claims of the key proof JWT
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-jwt-proof-type
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProofClaims {
+    /// Client id of the Wallet, absent for anonymous access
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    /// The Credential Issuer identifier
+    pub aud: String,
+    pub iat: u64,
+    /// The `c_nonce` provided by the Credential Issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// A key the credential has been bound to, as found in a key proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenKey {
+    pub jwk: Jwk,
+    pub claims: ProofClaims,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-response
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CredentialResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<Vec<IssuedCredential>>,
+    /// A single credential, as in the earlier drafts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+    /// For deferred issuance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IssuedCredential {
+    /// The serialized SD-JWT
+    pub credential: String,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-nonce-response
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct NonceResponse {
+    pub c_nonce: String,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-error-response
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CredentialErrorResponse {
+    pub error: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_read_credential_offer() {
+        let offer: CredentialOffer = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential"],
+            "grants": {
+                "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                    "pre-authorized_code": "oaKazRN8I0IbtZ0C7JuMn5",
+                    "tx_code": {"length": 4, "input_mode": "numeric"}
+                }
+            }
+        }))
+        .unwrap();
+        let grant = offer.grants.unwrap().pre_authorized_code.unwrap();
+        assert_eq!(grant.pre_authorized_code, "oaKazRN8I0IbtZ0C7JuMn5");
+    }
+
+    #[test]
+    fn should_read_single_and_batch_proofs() {
+        let single: CredentialRequest = serde_json::from_value(json!({
+            "credential_configuration_id": "pid",
+            "proof": {"proof_type": "jwt", "jwt": "a.b.c"}
+        }))
+        .unwrap();
+        assert_eq!(single.proof_jwts(), vec!["a.b.c"]);
+
+        let batch: CredentialRequest = serde_json::from_value(json!({
+            "credential_configuration_id": "pid",
+            "proofs": {"jwt": ["a.b.c", "d.e.f"]}
+        }))
+        .unwrap();
+        assert_eq!(batch.proof_jwts(), vec!["a.b.c", "d.e.f"]);
+    }
+}
@@ -0,0 +1,42 @@
+/// Errors of an authorization request, or of the Verifier answering a presentation
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-error-response
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Oid4vpError {
+    #[error("Invalid authorization request: {0}")]
+    InvalidRequest(String),
+    #[error("Invalid request object: {0}")]
+    InvalidRequestObject(String),
+    #[error("Verifier could not be authenticated: {0}")]
+    InvalidClient(String),
+    #[error("Unsupported client identifier prefix in '{0}'")]
+    UnsupportedClientIdPrefix(String),
+    #[error("Unsupported response mode '{0}'")]
+    UnsupportedResponseMode(String),
+    #[error("Invalid authorization response: {0}")]
+    InvalidResponse(String),
+    #[error("No pending request with state '{0}'")]
+    UnknownState(String),
+    #[error("The request with state '{0}' has expired")]
+    ExpiredSession(String),
+    #[error("Credentials from '{0}' are not accepted")]
+    UntrustedIssuer(String),
+    #[error("'{id}' discloses {path} which was not requested")]
+    ExcessiveDisclosure { id: String, path: String },
+}
+
+impl Oid4vpError {
+    /// Value of the `error` parameter of the error response
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest(_) | Self::UnsupportedResponseMode(_) => "invalid_request",
+            Self::InvalidRequestObject(_) => "invalid_request_object",
+            Self::InvalidClient(_) | Self::UnsupportedClientIdPrefix(_) => "invalid_client",
+            Self::InvalidResponse(_)
+            | Self::UnknownState(_)
+            | Self::ExpiredSession(_)
+            | Self::UntrustedIssuer(_)
+            | Self::ExcessiveDisclosure { .. } => "invalid_request",
+        }
+    }
+}
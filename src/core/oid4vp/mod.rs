@@ -0,0 +1,316 @@
+//! Protocol objects of [OpenID for Verifiable Presentations](https://openid.net/specs/openid-4-verifiable-presentations-1_0.html)
+
+use crate::core::{
+    dcql::DcqlQuery,
+    jwk::{Jwk, JwkSet},
+    presentation_exchange::{PresentationDefinition, PresentationSubmission},
+};
+use error::Oid4vpError;
+use serde_json::{Map, Value};
+
+pub mod error;
+
+/// `typ` of a signed authorization request i.e. a request object
+#[cfg(any(feature = "holder", feature = "verifier"))]
+pub const REQUEST_OBJECT_TYP: &str = "oauth-authz-req+jwt";
+
+/// `typ` of a Verifier Attestation JWT
+#[cfg(feature = "holder")]
+pub const VERIFIER_ATTESTATION_TYP: &str = "verifier-attestation+jwt";
+
+/// How the Wallet authenticates the Verifier, given as a prefix of its `client_id`
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-defined-client-identifier-p
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClientIdPrefix {
+    /// The `client_id` is the URI the response is sent to, requests cannot be signed
+    RedirectUri,
+    /// Requests are signed with the key of a certificate with this DNS name
+    X509SanDns,
+    /// Requests are signed with the key of a Verifier Attestation JWT with this subject
+    VerifierAttestation,
+}
+
+impl ClientIdPrefix {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::RedirectUri => "redirect_uri",
+            Self::X509SanDns => "x509_san_dns",
+            Self::VerifierAttestation => "verifier_attestation",
+        }
+    }
+
+    /// Splits a `client_id` into its prefix and the identifier it is followed by
+    pub fn try_split(client_id: &str) -> Result<(Self, &str), Oid4vpError> {
+        let (prefix, identifier) = client_id
+            .split_once(':')
+            .ok_or_else(|| Oid4vpError::UnsupportedClientIdPrefix(client_id.to_string()))?;
+        let prefix = [Self::RedirectUri, Self::X509SanDns, Self::VerifierAttestation]
+            .into_iter()
+            .find(|p| p.as_str() == prefix)
+            .ok_or_else(|| Oid4vpError::UnsupportedClientIdPrefix(client_id.to_string()))?;
+        Ok((prefix, identifier))
+    }
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-response-mode-direct_post
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ResponseMode {
+    #[serde(rename = "direct_post")]
+    DirectPost,
+    /// The response is encrypted to a key of the `client_metadata`
+    #[serde(rename = "direct_post.jwt")]
+    DirectPostJwt,
+    #[serde(rename = "fragment")]
+    Fragment,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-authorization-request
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuthorizationRequest {
+    /// e.g. `x509_san_dns:verifier.example.com`
+    pub client_id: String,
+    /// Only `vp_token` is supported
+    pub response_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_mode: Option<ResponseMode>,
+    /// Where the response is posted with the `direct_post` response modes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+    pub nonce: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dcql_query: Option<DcqlQuery>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presentation_definition: Option<PresentationDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_metadata: Option<ClientMetadata>,
+    /// Base64url encoded [TransactionData] the presentations have to be bound to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_data: Option<Vec<String>>,
+}
+
+impl AuthorizationRequest {
+    pub const VP_TOKEN: &'static str = "vp_token";
+
+    /// Parameters which are Json encoded when the request is passed in a URI
+    #[cfg(feature = "holder")]
+    pub(crate) const JSON_PARAMETERS: [&'static str; 4] = [
+        "dcql_query",
+        "presentation_definition",
+        "client_metadata",
+        "transaction_data",
+    ];
+
+    /// Checks what does not depend on how the request was passed
+    pub fn try_validate(&self) -> Result<(), Oid4vpError> {
+        let invalid = |reason: &str| Err(Oid4vpError::InvalidRequest(reason.to_string()));
+        if self.response_type != Self::VP_TOKEN {
+            return invalid("response_type has to be vp_token");
+        }
+        if self.dcql_query.is_some() == self.presentation_definition.is_some() {
+            return invalid("expected either a dcql_query or a presentation_definition");
+        }
+        for encoded in self.transaction_data.iter().flatten() {
+            let transaction_data = TransactionData::try_decode(encoded)?;
+            let known = |id: &String| match (&self.dcql_query, &self.presentation_definition) {
+                (Some(query), _) => query.credential(id).is_some(),
+                (None, Some(definition)) => definition.input_descriptor(id).is_some(),
+                (None, None) => false,
+            };
+            if transaction_data.credential_ids.is_empty() || !transaction_data.credential_ids.iter().all(known) {
+                return invalid("transaction_data has to refer to requested credentials");
+            }
+        }
+        match self.response_mode {
+            Some(ResponseMode::DirectPost | ResponseMode::DirectPostJwt) if self.response_uri.is_none() => {
+                invalid("response_uri is required with direct_post")
+            }
+            Some(ResponseMode::DirectPostJwt) if self.encryption_key().is_none() => {
+                invalid("no key to encrypt the response to")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The transaction data, as passed, a presentation answering the credential query or input
+    /// descriptor `credential_id` has to be bound to
+    pub fn try_transaction_data(&self, credential_id: &str) -> Result<Vec<String>, Oid4vpError> {
+        let mut bound = vec![];
+        for encoded in self.transaction_data.iter().flatten() {
+            if TransactionData::try_decode(encoded)?
+                .credential_ids
+                .iter()
+                .any(|id| id == credential_id)
+            {
+                bound.push(encoded.clone());
+            }
+        }
+        Ok(bound)
+    }
+
+    /// The key to encrypt the response to with `direct_post.jwt`
+    pub fn encryption_key(&self) -> Option<&Jwk> {
+        let jwks = self.client_metadata.as_ref()?.jwks.as_ref()?;
+        jwks.keys.iter().find(|k| k.kty == "EC" && k.crv == "P-256")
+    }
+}
+
+/// Details of a transaction, e.g. a payment, the End-User authorizes by presenting credentials
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-transaction-data
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TransactionData {
+    pub r#type: String,
+    /// Ids of the credential queries or input descriptors whose presentations are bound to it
+    pub credential_ids: Vec<String>,
+    /// Accepted algorithms for hashing it in the KB-JWT, `sha-256` when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_data_hashes_alg: Option<Vec<String>>,
+    /// Parameters specific to the `type`
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl TransactionData {
+    /// Encodes it as passed in the request i.e. base64url encoded Json
+    pub fn try_encode(&self) -> Result<String, Oid4vpError> {
+        let json = serde_json::to_vec(self).map_err(|e| Oid4vpError::InvalidRequest(e.to_string()))?;
+        Ok(base64_simd::URL_SAFE_NO_PAD.encode_to_string(json))
+    }
+
+    pub fn try_decode(encoded: &str) -> Result<Self, Oid4vpError> {
+        let invalid = || Oid4vpError::InvalidRequest("invalid transaction_data".to_string());
+        let json = base64_simd::URL_SAFE_NO_PAD
+            .decode_to_vec(encoded)
+            .map_err(|_| invalid())?;
+        serde_json::from_slice(&json).map_err(|_| invalid())
+    }
+
+    /// Whether it can be hashed with `alg` in the KB-JWT
+    pub fn accepts_hash_alg(&self, alg: &str) -> bool {
+        match &self.transaction_data_hashes_alg {
+            Some(accepted) => accepted.iter().any(|a| a == alg),
+            None => alg == "sha-256",
+        }
+    }
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-verifier-metadata-client-me
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ClientMetadata {
+    /// Keys to encrypt the response to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwks: Option<JwkSet>,
+    /// e.g. `A128GCM`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_response_enc_values_supported: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vp_formats_supported: Option<Value>,
+}
+
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-response
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuthorizationResponse {
+    /// With DCQL, presentations by credential query id. With Presentation Exchange, a
+    /// presentation or an array of them.
+    pub vp_token: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presentation_submission: Option<PresentationSubmission>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+/// Claims of a Verifier Attestation JWT, vouching for the key the Verifier signs requests with
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-verifier-attestation-jwt
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VerifierAttestationClaims {
+    pub iss: String,
+    /// The `client_id` without its prefix
+    pub sub: String,
+    pub exp: u64,
+    pub cnf: Confirmation,
+    /// Where the Verifier is allowed to receive responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_uris: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Confirmation {
+    pub jwk: Jwk,
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_split_client_id() {
+        assert_eq!(
+            ClientIdPrefix::try_split("x509_san_dns:verifier.example.com").unwrap(),
+            (ClientIdPrefix::X509SanDns, "verifier.example.com")
+        );
+        assert_eq!(
+            ClientIdPrefix::try_split("redirect_uri:https://verifier.example.com/cb").unwrap(),
+            (ClientIdPrefix::RedirectUri, "https://verifier.example.com/cb")
+        );
+        for client_id in ["verifier.example.com", "decentralized_identifier:did:example:123"] {
+            assert!(matches!(
+                ClientIdPrefix::try_split(client_id),
+                Err(Oid4vpError::UnsupportedClientIdPrefix(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn should_validate_request() {
+        let request = |extra: Value| -> AuthorizationRequest {
+            let mut request = json!({
+                "client_id": "x509_san_dns:verifier.example.com",
+                "response_type": "vp_token",
+                "nonce": "n-0S6_WzA2Mj",
+                "dcql_query": {"credentials": [{"id": "pid", "format": "dc+sd-jwt"}]}
+            });
+            request
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            serde_json::from_value(request).unwrap()
+        };
+        assert!(request(json!({})).try_validate().is_ok());
+        assert!(request(json!({"response_type": "code"})).try_validate().is_err());
+        assert!(
+            request(json!({"presentation_definition": {"id": "pd", "input_descriptors": []}}))
+                .try_validate()
+                .is_err()
+        );
+        assert!(request(json!({"response_mode": "direct_post"})).try_validate().is_err());
+        let transaction_data = |credential_ids: Value| {
+            let transaction_data: TransactionData = serde_json::from_value(json!({
+                "type": "payment",
+                "credential_ids": credential_ids,
+                "amount": "42.00 EUR"
+            }))
+            .unwrap();
+            json!({"transaction_data": [transaction_data.try_encode().unwrap()]})
+        };
+        let bound = request(transaction_data(json!(["pid"])));
+        assert!(bound.try_validate().is_ok());
+        assert_eq!(bound.try_transaction_data("pid").unwrap().len(), 1);
+        assert!(bound.try_transaction_data("mdl").unwrap().is_empty());
+        assert!(request(transaction_data(json!(["mdl"]))).try_validate().is_err());
+        assert!(request(json!({"transaction_data": ["not base64url Json"]}))
+            .try_validate()
+            .is_err());
+        assert!(request(
+            json!({"response_mode": "direct_post.jwt", "response_uri": "https://verifier.example.com/post"})
+        )
+        .try_validate()
+        .is_err());
+    }
+}
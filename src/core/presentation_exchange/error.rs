@@ -0,0 +1,33 @@
+pub type PresentationExchangeResult<T> = Result<T, PresentationExchangeError>;
+
+/// Why a credential does not satisfy an input descriptor
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum PresentationExchangeError {
+    #[error("Input descriptor '{descriptor_id}' only accepts formats {accepted:?}")]
+    UnsupportedFormat {
+        descriptor_id: String,
+        accepted: Vec<String>,
+    },
+    #[error("Input descriptor '{descriptor_id}': no claim at {paths:?}")]
+    MissingField { descriptor_id: String, paths: Vec<String> },
+    #[error("Input descriptor '{descriptor_id}': no claim at {paths:?} satisfies the filter {filter}")]
+    FilterNotSatisfied {
+        descriptor_id: String,
+        paths: Vec<String>,
+        filter: serde_json::Value,
+    },
+    #[error("Invalid JSONPath '{0}'")]
+    InvalidJsonPath(String),
+    #[error("Unsupported filter keyword '{0}'")]
+    UnsupportedFilter(String),
+    #[error("Invalid filter {0}")]
+    InvalidFilter(serde_json::Value),
+    #[error("Unknown input descriptor '{0}'")]
+    UnknownInputDescriptor(String),
+    #[error("No credential satisfies the input descriptor '{descriptor_id}'")]
+    NoCandidate {
+        descriptor_id: String,
+        /// Why each credential was discarded
+        unmet: Vec<PresentationExchangeError>,
+    },
+}
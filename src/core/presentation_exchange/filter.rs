@@ -0,0 +1,118 @@
+use crate::core::presentation_exchange::error::{PresentationExchangeError, PresentationExchangeResult};
+use serde_json::Value;
+
+/// Evaluates the subset of [JSON Schema](https://json-schema.org/) used in field filters:
+/// `type`, `const`, `enum`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+/// `minLength`, `maxLength`, `pattern`, `contains`, `minItems`, `maxItems` and `format` (ignored).
+/// Any other keyword is rejected rather than silently ignored.
+pub(crate) fn try_matches(filter: &Value, value: &Value) -> PresentationExchangeResult<bool> {
+    let invalid = || PresentationExchangeError::InvalidFilter(filter.clone());
+    let keywords = filter.as_object().ok_or_else(invalid)?;
+
+    for (keyword, expected) in keywords {
+        let number = || expected.as_f64().ok_or_else(invalid);
+        let length = || expected.as_u64().ok_or_else(invalid);
+        let matches = match keyword.as_str() {
+            "type" => match expected {
+                Value::String(t) => is_type(t, value),
+                Value::Array(types) => types.iter().filter_map(Value::as_str).any(|t| is_type(t, value)),
+                _ => return Err(invalid()),
+            },
+            "const" => value == expected,
+            "enum" => expected.as_array().ok_or_else(invalid)?.contains(value),
+            "minimum" => value.as_f64().map_or(Ok(false), |v| Ok(v >= number()?))?,
+            "maximum" => value.as_f64().map_or(Ok(false), |v| Ok(v <= number()?))?,
+            "exclusiveMinimum" => value.as_f64().map_or(Ok(false), |v| Ok(v > number()?))?,
+            "exclusiveMaximum" => value.as_f64().map_or(Ok(false), |v| Ok(v < number()?))?,
+            "minLength" => string_length(value).map_or(Ok(false), |l| Ok(l >= length()?))?,
+            "maxLength" => string_length(value).map_or(Ok(false), |l| Ok(l <= length()?))?,
+            // not anchored, as per JSON Schema
+            "pattern" => {
+                let pattern = expected.as_str().ok_or_else(invalid)?;
+                let pattern = regex::Regex::new(pattern).map_err(|_| invalid())?;
+                value.as_str().is_some_and(|v| pattern.is_match(v))
+            }
+            "minItems" => value
+                .as_array()
+                .map_or(Ok(false), |a| Ok(a.len() as u64 >= length()?))?,
+            "maxItems" => value
+                .as_array()
+                .map_or(Ok(false), |a| Ok(a.len() as u64 <= length()?))?,
+            "contains" => {
+                let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+                let mut contains = false;
+                for item in items {
+                    contains |= try_matches(expected, item)?;
+                }
+                contains
+            }
+            // only an annotation
+            "format" | "$schema" | "title" | "description" => true,
+            unsupported => return Err(PresentationExchangeError::UnsupportedFilter(unsupported.to_string())),
+        };
+        if !matches {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn is_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn string_length(value: &Value) -> Option<u64> {
+    value.as_str().map(|s| s.chars().count() as u64)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_evaluate_keywords() {
+        assert!(try_matches(&json!({"type": "string"}), &json!("a")).unwrap());
+        assert!(!try_matches(&json!({"type": "string"}), &json!(1)).unwrap());
+        assert!(try_matches(&json!({"type": ["string", "null"]}), &json!(null)).unwrap());
+        assert!(try_matches(&json!({"const": "DE"}), &json!("DE")).unwrap());
+        assert!(try_matches(&json!({"enum": ["DE", "FR"]}), &json!("FR")).unwrap());
+        assert!(!try_matches(&json!({"enum": ["DE", "FR"]}), &json!("US")).unwrap());
+        assert!(try_matches(&json!({"type": "number", "minimum": 18}), &json!(18)).unwrap());
+        assert!(!try_matches(&json!({"exclusiveMinimum": 18}), &json!(18)).unwrap());
+        assert!(!try_matches(&json!({"maximum": 18}), &json!("17")).unwrap());
+        assert!(try_matches(&json!({"minLength": 2, "maxLength": 2}), &json!("DE")).unwrap());
+        assert!(try_matches(&json!({"contains": {"const": "DE"}}), &json!(["US", "DE"])).unwrap());
+        assert!(!try_matches(&json!({"contains": {"const": "DE"}}), &json!(["US"])).unwrap());
+        assert!(try_matches(&json!({"type": "string", "format": "date"}), &json!("2000-01-01")).unwrap());
+        assert!(try_matches(&json!({"pattern": "^D[EK]$"}), &json!("DE")).unwrap());
+        assert!(try_matches(&json!({"pattern": "[0-9]{4}"}), &json!("born 1990")).unwrap());
+        assert!(!try_matches(&json!({"pattern": "^D[EK]$"}), &json!("FR")).unwrap());
+        assert!(!try_matches(&json!({"pattern": "^1"}), &json!(1)).unwrap());
+    }
+
+    #[test]
+    fn should_reject_unsupported_keywords() {
+        assert_eq!(
+            try_matches(&json!({"patternProperties": {}}), &json!({})).unwrap_err(),
+            PresentationExchangeError::UnsupportedFilter("patternProperties".to_string())
+        );
+        assert!(matches!(
+            try_matches(&json!({"pattern": "(DE"}), &json!("DE")).unwrap_err(),
+            PresentationExchangeError::InvalidFilter(_)
+        ));
+        assert!(matches!(
+            try_matches(&json!({"minimum": "a"}), &json!(1)).unwrap_err(),
+            PresentationExchangeError::InvalidFilter(_)
+        ));
+    }
+}
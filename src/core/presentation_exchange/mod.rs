@@ -0,0 +1,174 @@
+//! Models of [DIF Presentation Exchange v2](https://identity.foundation/presentation-exchange/spec/v2.0.0/),
+//! the Holder evaluates them against its credentials
+
+use crate::core::json_path::JsonPath;
+use error::{PresentationExchangeError, PresentationExchangeResult};
+use serde_json::Value;
+
+pub mod error;
+mod filter;
+
+/// What a Verifier requests, as defined in
+/// [DIF Presentation Exchange v2](https://identity.foundation/presentation-exchange/spec/v2.0.0/#presentation-definition)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PresentationDefinition {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Value>,
+    pub input_descriptors: Vec<InputDescriptor>,
+}
+
+/// See also: https://identity.foundation/presentation-exchange/spec/v2.0.0/#input-descriptor-object
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct InputDescriptor {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    /// Accepted formats by their Claim Format Designation e.g. `vc+sd-jwt`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Map<String, Value>>,
+    #[serde(default)]
+    pub constraints: Constraints,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Constraints {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<Field>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_disclosure: Option<LimitDisclosure>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Field {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// JSONPath expressions, evaluated in order until one matches
+    pub path: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// JSON Schema the value has to be valid against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Value>,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent_to_retain: Option<bool>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitDisclosure {
+    Required,
+    Preferred,
+}
+
+/// Describes how the presented credentials map to the input descriptors
+///
+/// See also: https://identity.foundation/presentation-exchange/spec/v2.0.0/#presentation-submission
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PresentationSubmission {
+    pub id: String,
+    pub definition_id: String,
+    pub descriptor_map: Vec<DescriptorMap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DescriptorMap {
+    /// Id of the input descriptor
+    pub id: String,
+    pub format: String,
+    /// JSONPath to the presentation in the `vp_token`
+    pub path: String,
+}
+
+impl PresentationDefinition {
+    /// The Claim Format Designations of a SD-JWT (VC)
+    pub const SD_JWT_FORMATS: [&'static str; 2] = ["vc+sd-jwt", "dc+sd-jwt"];
+
+    pub fn input_descriptor(&self, id: &str) -> Option<&InputDescriptor> {
+        self.input_descriptors.iter().find(|d| d.id == id)
+    }
+
+    /// The SD-JWT Claim Format Designation accepted by the input descriptor or else by the
+    /// definition, `vc+sd-jwt` when neither restricts formats
+    pub fn sd_jwt_format(&self, descriptor: &InputDescriptor) -> Option<&'static str> {
+        let accepted = match (&descriptor.format, self.format.as_ref().and_then(Value::as_object)) {
+            (Some(format), _) | (None, Some(format)) => format,
+            (None, None) => return Some(Self::SD_JWT_FORMATS[0]),
+        };
+        Self::SD_JWT_FORMATS.into_iter().find(|f| accepted.contains_key(*f))
+    }
+}
+
+impl InputDescriptor {
+    /// Whether only the claims matched by the fields, and their ancestors, may be disclosed
+    pub fn limits_disclosure(&self) -> bool {
+        self.constraints.limit_disclosure == Some(LimitDisclosure::Required)
+    }
+
+    /// Checks that the claims satisfy every constraint then returns the Json pointers of the
+    /// claims matched by the fields
+    pub fn try_match_claims(&self, claims: &Value) -> PresentationExchangeResult<Vec<String>> {
+        if let Some(format) = &self.format {
+            let supported = PresentationDefinition::SD_JWT_FORMATS
+                .iter()
+                .any(|f| format.contains_key(*f));
+            if !supported {
+                return Err(PresentationExchangeError::UnsupportedFormat {
+                    descriptor_id: self.id.clone(),
+                    accepted: format.keys().cloned().collect(),
+                });
+            }
+        }
+
+        let mut pointers = vec![];
+        for field in &self.constraints.fields {
+            match self.try_match_field(field, claims)? {
+                Some(pointer) => pointers.push(pointer),
+                None if field.optional => {}
+                None if field.filter.is_some() => {
+                    return Err(PresentationExchangeError::FilterNotSatisfied {
+                        descriptor_id: self.id.clone(),
+                        paths: field.path.clone(),
+                        filter: field.filter.clone().unwrap_or_default(),
+                    })
+                }
+                None => {
+                    return Err(PresentationExchangeError::MissingField {
+                        descriptor_id: self.id.clone(),
+                        paths: field.path.clone(),
+                    })
+                }
+            }
+        }
+        Ok(pointers)
+    }
+
+    /// Returns the Json pointer of the first claim matching the field
+    fn try_match_field(&self, field: &Field, claims: &Value) -> PresentationExchangeResult<Option<String>> {
+        for path in &field.path {
+            let path = path
+                .parse::<JsonPath>()
+                .map_err(|_| PresentationExchangeError::InvalidJsonPath(path.clone()))?;
+            for (pointer, value) in path.find(claims) {
+                let matches = match &field.filter {
+                    Some(filter) => filter::try_matches(filter, value)?,
+                    None => true,
+                };
+                if matches {
+                    return Ok(Some(pointer));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
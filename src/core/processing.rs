@@ -0,0 +1,337 @@
+use crate::{
+    core::{disclosure::Disclosure, json_pointer::escape},
+    crypto::hash::HashAlgorithm,
+    error::{SdjError, SdjResult},
+};
+use serde_json::{Map, Value};
+use std::{borrow::Borrow, collections::HashMap};
+
+/// A [Disclosure] matched with the digest referencing it in the Issuer-signed JWT
+#[derive(Debug, Clone)]
+pub struct ProcessedDisclosure {
+    /// Position of the disclosure in [crate::prelude::SDJwt::disclosures]
+    #[cfg_attr(not(feature = "holder"), allow(dead_code))]
+    pub index: usize,
+    /// Digest of the disclosure as found in the payload
+    pub digest: String,
+    /// Json pointer to the disclosed claim in the processed claim set
+    pub path: String,
+    /// Position of the disclosure this one is nested in, if any
+    #[cfg_attr(not(feature = "holder"), allow(dead_code))]
+    pub parent: Option<usize>,
+}
+
+/// The claim set obtained once every digest of the payload has been replaced by the
+/// corresponding disclosed claim.
+///
+/// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-6.1
+#[derive(Debug, Clone)]
+pub struct ProcessedClaims {
+    /// Claims as seen by a Verifier i.e. without `_sd`, `_sd_alg` nor undisclosed array items
+    pub claims: Value,
+    /// All the disclosures referenced by a digest in the payload, in the order they were found
+    pub disclosures: Vec<ProcessedDisclosure>,
+    /// Positions of the disclosures which are not referenced by any digest
+    pub unreferenced: Vec<usize>,
+    /// Number of digests without a matching disclosure, either decoys or undisclosed claims
+    #[cfg_attr(not(feature = "holder"), allow(dead_code))]
+    pub undisclosed_digests: usize,
+    /// Json pointers of all the arrays in the processed claim set, along with their length in the
+    /// payload i.e. including undisclosed items
+    #[cfg_attr(not(feature = "holder"), allow(dead_code))]
+    pub array_lengths: Vec<(String, usize)>,
+    /// Json pointers of the objects & arrays with digests lacking a matching disclosure i.e. where
+    /// claims might have been withheld by the Holder, or be decoys
+    #[cfg_attr(not(feature = "verifier"), allow(dead_code))]
+    pub withheld_in: Vec<String>,
+}
+
+impl ProcessedClaims {
+    /// Replaces all the digests in the payload with the matching disclosures
+    pub fn try_new<D: Borrow<Disclosure>>(payload: &Value, disclosures: &[D]) -> SdjResult<Self> {
+        let disclosures = disclosures.iter().map(Borrow::borrow).collect::<Vec<_>>();
+        let mut digests = HashMap::with_capacity(disclosures.len());
+        for (index, disclosure) in disclosures.iter().enumerate() {
+            let digest: String = disclosure.hash()?.into();
+            if digests.insert(digest, index).is_some() {
+                return Err(SdjError::DuplicateDisclosure);
+            }
+        }
+
+        let mut processor = Processor {
+            disclosures: &disclosures,
+            digests,
+            processed: vec![],
+            undisclosed_digests: 0,
+            array_lengths: vec![],
+            withheld_in: vec![],
+        };
+        let mut claims = processor.process(payload, "", None)?;
+        if let Some(claims) = claims.as_object_mut() {
+            claims.remove(SD_ALG);
+        }
+
+        let mut unreferenced = processor.digests.into_values().collect::<Vec<_>>();
+        unreferenced.sort_unstable();
+
+        Ok(Self {
+            claims,
+            disclosures: processor.processed,
+            unreferenced,
+            undisclosed_digests: processor.undisclosed_digests,
+            array_lengths: processor.array_lengths,
+            withheld_in: processor.withheld_in,
+        })
+    }
+
+    /// Processes the SD-JWT the way a Verifier does, rejecting it when:
+    /// * the hash algorithm is not supported
+    /// * a disclosure is not referenced by any digest
+    ///
+    /// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-6.1
+    pub fn try_verify<D: Borrow<Disclosure>>(payload: &Value, disclosures: &[D]) -> SdjResult<Self> {
+        let sd_alg = payload.get(SD_ALG).map(|alg| alg.as_str().ok_or(SdjError::InvalidJwt));
+        let sd_alg = sd_alg.transpose()?.unwrap_or(HashAlgorithm::SHA256.to_jwt_claim());
+        if sd_alg != HashAlgorithm::SHA256.to_jwt_claim() {
+            return Err(SdjError::UnsupportedHashAlgorithm(sd_alg.to_string()));
+        }
+        let processed = Self::try_new(payload, disclosures)?;
+        if !processed.unreferenced.is_empty() {
+            return Err(SdjError::UnreferencedDisclosure);
+        }
+        Ok(processed)
+    }
+}
+
+pub(crate) const SD: &str = "_sd";
+pub(crate) const SD_ALG: &str = "_sd_alg";
+pub(crate) const ELLIPSIS: &str = "...";
+
+struct Processor<'a> {
+    disclosures: &'a [&'a Disclosure],
+    /// Disclosures not yet referenced, by digest
+    digests: HashMap<String, usize>,
+    processed: Vec<ProcessedDisclosure>,
+    undisclosed_digests: usize,
+    array_lengths: Vec<(String, usize)>,
+    withheld_in: Vec<String>,
+}
+
+impl<'a> Processor<'a> {
+    fn process(&mut self, value: &Value, path: &str, parent: Option<usize>) -> SdjResult<Value> {
+        match value {
+            Value::Object(object) => self.process_object(object, path, parent),
+            Value::Array(items) => self.process_array(items, path, parent),
+            v => Ok(v.clone()),
+        }
+    }
+
+    fn process_object(&mut self, object: &Map<String, Value>, path: &str, parent: Option<usize>) -> SdjResult<Value> {
+        let mut processed = Map::with_capacity(object.len());
+        for (key, value) in object.iter().filter(|(k, _)| k.as_str() != SD) {
+            let value = self.process(value, &format!("{path}/{}", escape(key)), parent)?;
+            processed.insert(key.clone(), value);
+        }
+
+        let digests = match object.get(SD) {
+            Some(Value::Array(digests)) => digests.as_slice(),
+            Some(_) => return Err(SdjError::InvalidJwt),
+            None => &[],
+        };
+        for digest in digests {
+            let digest = digest.as_str().ok_or(SdjError::InvalidJwt)?;
+            let Some(index) = self.take(digest, path)? else {
+                // decoy or not disclosed
+                continue;
+            };
+            let disclosure = self.disclosures[index];
+            let (Some(name), value) = (disclosure.name(), disclosure.value()) else {
+                return Err(SdjError::InvalidDisclosure);
+            };
+            if processed.contains_key(name) || [SD, ELLIPSIS].contains(&name) {
+                return Err(SdjError::InvalidDisclosure);
+            }
+            let claim_path = format!("{path}/{}", escape(name));
+            self.processed.push(ProcessedDisclosure {
+                index,
+                digest: digest.to_string(),
+                path: claim_path.clone(),
+                parent,
+            });
+            let value = self.process(value, &claim_path, Some(index))?;
+            processed.insert(name.to_string(), value);
+        }
+        Ok(Value::Object(processed))
+    }
+
+    fn process_array(&mut self, items: &[Value], path: &str, parent: Option<usize>) -> SdjResult<Value> {
+        self.array_lengths.push((path.to_string(), items.len()));
+        let mut processed = Vec::with_capacity(items.len());
+        for item in items {
+            let item_path = format!("{path}/{}", processed.len());
+            match item.as_object().and_then(|o| o.get(ELLIPSIS).filter(|_| o.len() == 1)) {
+                Some(digest) => {
+                    let digest = digest.as_str().ok_or(SdjError::InvalidJwt)?;
+                    let Some(index) = self.take(digest, path)? else {
+                        // decoy or not disclosed
+                        continue;
+                    };
+                    let disclosure = self.disclosures[index];
+                    if disclosure.name().is_some() {
+                        return Err(SdjError::InvalidDisclosure);
+                    }
+                    self.processed.push(ProcessedDisclosure {
+                        index,
+                        digest: digest.to_string(),
+                        path: item_path.clone(),
+                        parent,
+                    });
+                    processed.push(self.process(disclosure.value(), &item_path, Some(index))?);
+                }
+                None => processed.push(self.process(item, &item_path, parent)?),
+            }
+        }
+        Ok(Value::Array(processed))
+    }
+
+    /// Finds the disclosure matching this digest found in the object or array at `path`, if any.
+    /// A digest referenced twice is an error
+    fn take(&mut self, digest: &str, path: &str) -> SdjResult<Option<usize>> {
+        if let Some(index) = self.digests.remove(digest) {
+            return Ok(Some(index));
+        }
+        let already_processed = self.processed.iter().any(|p| p.digest == digest);
+        if already_processed {
+            return Err(SdjError::DuplicateDisclosure);
+        }
+        self.undisclosed_digests += 1;
+        if self.withheld_in.last().map(String::as_str) != Some(path) {
+            self.withheld_in.push(path.to_string());
+        }
+        Ok(None)
+    }
+}
+
+/// Whether the Json pointer `ancestor` points to `path` or to one of its ancestors
+pub(crate) fn is_ancestor_or_self(ancestor: &str, path: &str) -> bool {
+    path == ancestor || (path.starts_with(ancestor) && path[ancestor.len()..].starts_with('/'))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions};
+    use serde_json::json;
+
+    fn issue(input: Value, decisions: &'static [&'static str]) -> (Value, Vec<Disclosure>) {
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt(input, decisions, IssuerOptions::default())
+            .unwrap();
+        (sd_jwt.jws.try_read_unverified_payload().unwrap(), sd_jwt.disclosures)
+    }
+
+    #[test]
+    fn should_restore_input_claims() {
+        let input = json!({
+            "iss": "https://example.com/issuer",
+            "given_name": "John",
+            "address": {"street_address": "123 Main St", "country": "US"},
+            "nationalities": ["US", "DE", "FR"]
+        });
+        let (payload, disclosures) = issue(
+            input.clone(),
+            &["/given_name", "/address/country", "/address", "/nationalities/1"],
+        );
+
+        let processed = ProcessedClaims::try_new(&payload, &disclosures).unwrap();
+        assert_eq!(processed.claims, input);
+        assert!(processed.unreferenced.is_empty());
+
+        // in the order of the digests, which the Issuer sorts
+        let mut paths = processed
+            .disclosures
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect::<Vec<_>>();
+        paths.sort_unstable();
+        assert_eq!(
+            paths,
+            vec!["/address", "/address/country", "/given_name", "/nationalities/1"]
+        );
+        let find = |path| processed.disclosures.iter().find(|d| d.path == path).unwrap();
+        assert_eq!(find("/address/country").parent, Some(find("/address").index));
+        assert_eq!(find("/address").parent, None);
+    }
+
+    #[test]
+    fn should_drop_undisclosed_claims() {
+        let input = json!({"given_name": "John", "nationalities": ["US", "DE"]});
+        let (payload, mut disclosures) = issue(input, &["/given_name", "/nationalities/0"]);
+        disclosures.clear();
+
+        let processed = ProcessedClaims::try_new(&payload, &disclosures).unwrap();
+        assert_eq!(processed.claims, json!({"nationalities": ["DE"]}));
+        assert!(processed.disclosures.is_empty());
+        assert_eq!(processed.undisclosed_digests, 2);
+        assert_eq!(processed.array_lengths, vec![("/nationalities".to_string(), 2)]);
+        assert_eq!(processed.withheld_in, vec!["/nationalities", ""]);
+    }
+
+    #[test]
+    fn should_report_unreferenced_disclosures() {
+        let (payload, _) = issue(json!({"a": 1}), &["/a"]);
+        let (_, disclosures) = issue(json!({"a": 1}), &["/a"]);
+
+        let processed = ProcessedClaims::try_new(&payload, &disclosures).unwrap();
+        assert_eq!(processed.claims, json!({}));
+        assert_eq!(processed.unreferenced, vec![0]);
+        assert!(matches!(
+            ProcessedClaims::try_verify(&payload, &disclosures).unwrap_err(),
+            SdjError::UnreferencedDisclosure
+        ));
+    }
+
+    #[test]
+    fn should_fail_verifying_when_hash_algorithm_unsupported() {
+        let (mut payload, disclosures) = issue(json!({"a": 1}), &["/a"]);
+        assert!(ProcessedClaims::try_verify(&payload, &disclosures).is_ok());
+        payload
+            .as_object_mut()
+            .unwrap()
+            .insert(SD_ALG.to_string(), json!("md5"));
+        assert!(matches!(
+            ProcessedClaims::try_verify(&payload, &disclosures).unwrap_err(),
+            SdjError::UnsupportedHashAlgorithm(alg) if alg == "md5"
+        ));
+    }
+
+    #[test]
+    fn should_fail_when_disclosure_repeated() {
+        let (payload, mut disclosures) = issue(json!({"a": 1}), &["/a"]);
+        disclosures.push(disclosures[0].clone());
+        assert!(matches!(
+            ProcessedClaims::try_new(&payload, &disclosures).unwrap_err(),
+            SdjError::DuplicateDisclosure
+        ));
+    }
+
+    #[test]
+    fn should_fail_when_disclosed_claim_already_exists() {
+        let (mut payload, disclosures) = issue(json!({"a": 1}), &["/a"]);
+        payload.as_object_mut().unwrap().insert("a".to_string(), json!(2));
+        assert!(matches!(
+            ProcessedClaims::try_new(&payload, &disclosures).unwrap_err(),
+            SdjError::InvalidDisclosure
+        ));
+    }
+
+    #[test]
+    fn should_compare_pointers_by_token() {
+        assert!(is_ancestor_or_self("/address", "/address"));
+        assert!(is_ancestor_or_self("/address", "/address/country"));
+        assert!(is_ancestor_or_self("", "/address"));
+        assert!(!is_ancestor_or_self("/address", "/addresses"));
+        assert!(!is_ancestor_or_self("/address/country", "/address"));
+    }
+}
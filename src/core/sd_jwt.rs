@@ -11,7 +11,7 @@ use std::str::FromStr;
 /// * optionally a Key Binding JWT
 ///
 /// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-5.11
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SDJwt {
     pub jws: Jws,
     pub disclosures: Vec<Disclosure>,
@@ -20,6 +20,83 @@ pub struct SDJwt {
 
 impl SDJwt {
     pub const DELIMITER: &str = "~";
+
+    pub fn try_serialize(&self) -> SdjResult<String> {
+        Self::try_serialize_parts(&self.jws, &self.disclosures, self.key_binding.as_deref())
+    }
+
+    /// Serializes to `<Issuer-signed JWT>~<Disclosure 1>~...~<Disclosure N>~<optional KB-JWT>`
+    pub(crate) fn try_serialize_parts<'a>(
+        jws: &Jws,
+        disclosures: impl IntoIterator<Item = &'a Disclosure>,
+        key_binding: Option<&str>,
+    ) -> SdjResult<String> {
+        let delimiter = Self::DELIMITER;
+        let mut serialized = format!("{}{delimiter}", jws.as_str());
+        for disclosure in disclosures {
+            serialized.push_str(&disclosure.build()?);
+            serialized.push_str(delimiter);
+        }
+        serialized.push_str(key_binding.unwrap_or_default());
+        Ok(serialized)
+    }
+}
+
+/// The flattened JWS JSON serialization of a [SDJwt], with the disclosures & KB-JWT in the
+/// unprotected header
+///
+/// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-08.html#name-json-serialization
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SDJwtJson {
+    pub protected: String,
+    pub payload: String,
+    pub signature: String,
+    pub header: SDJwtJsonHeader,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SDJwtJsonHeader {
+    pub disclosures: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kb_jwt: Option<String>,
+}
+
+impl SDJwt {
+    pub fn try_to_json(&self) -> SdjResult<SDJwtJson> {
+        let mut parts = self.jws.split('.');
+        let (Some(protected), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(SdjError::InvalidJwt);
+        };
+        Ok(SDJwtJson {
+            protected: protected.to_string(),
+            payload: payload.to_string(),
+            signature: signature.to_string(),
+            header: SDJwtJsonHeader {
+                disclosures: self.disclosures.iter().map(|d| d.build()).collect::<SdjResult<_>>()?,
+                kb_jwt: self.key_binding.clone(),
+            },
+        })
+    }
+}
+
+impl TryFrom<SDJwtJson> for SDJwt {
+    type Error = SdjError;
+
+    fn try_from(json: SDJwtJson) -> Result<Self, Self::Error> {
+        let disclosures = json
+            .header
+            .disclosures
+            .iter()
+            .map(|d| d.parse())
+            .collect::<SdjResult<Vec<_>>>()?;
+        Ok(Self {
+            jws: format!("{}.{}.{}", json.protected, json.payload, json.signature).into(),
+            disclosures,
+            key_binding: json.header.kb_jwt,
+        })
+    }
 }
 
 impl FromStr for SDJwt {
@@ -27,7 +104,7 @@ impl FromStr for SDJwt {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s.split(Self::DELIMITER).collect::<Vec<_>>();
-        if parts.len() < 3 {
+        if parts.len() < 2 {
             return Err(SdjError::InvalidSerializedSdJwt);
         }
         let size = parts.len();
@@ -45,3 +122,23 @@ impl FromStr for SDJwt {
         })
     }
 }
+
+impl serde::Serialize for SDJwt {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let serialized = self.try_serialize().map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&serialized)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SDJwt {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let serialized = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        serialized.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::error::{SdjError, SdjResult};
+
+/// Parameters of the query component of a URI e.g. `openid-credential-offer://?credential_offer=...`,
+/// percent-decoded
+pub(crate) fn try_parse_query(uri: &str) -> SdjResult<Vec<(String, String)>> {
+    let query = uri.split_once('?').map(|(_, q)| q).unwrap_or_default();
+    let query = query.split_once('#').map(|(q, _)| q).unwrap_or(query);
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|parameter| {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            Ok((try_decode(name)?, try_decode(value)?))
+        })
+        .collect()
+}
+
+/// Encodes parameters as `application/x-www-form-urlencoded` e.g. for a query or a POST body
+pub(crate) fn encode_form<'a>(parameters: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    parameters
+        .into_iter()
+        .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// The host of a `https` URI
+#[cfg(feature = "holder")]
+pub(crate) fn host(uri: &str) -> Option<&str> {
+    let authority = uri.strip_prefix("https://")?;
+    let authority = authority.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit_once('@').map(|(_, h)| h).unwrap_or(authority);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => (b as char).to_string(),
+            b' ' => "+".to_string(),
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Decodes `application/x-www-form-urlencoded` values
+fn try_decode(value: &str) -> SdjResult<String> {
+    let invalid = || SdjError::InvalidUri(value.to_string());
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut chars = value.bytes();
+    while let Some(c) = chars.next() {
+        match c {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [chars.next().ok_or_else(invalid)?, chars.next().ok_or_else(invalid)?];
+                let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+                bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            }
+            c => bytes.push(c),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_decode_query() {
+        let uri = "openid-credential-offer://?credential_offer=%7B%22a%22%3A%22b+c%22%7D&empty=&flag#fragment";
+        assert_eq!(
+            try_parse_query(uri).unwrap(),
+            vec![
+                ("credential_offer".to_string(), r#"{"a":"b c"}"#.to_string()),
+                ("empty".to_string(), "".to_string()),
+                ("flag".to_string(), "".to_string()),
+            ]
+        );
+        assert!(try_parse_query("openid-credential-offer://").unwrap().is_empty());
+        assert!(matches!(try_parse_query("x://?a=%2"), Err(SdjError::InvalidUri(_))));
+        assert!(matches!(try_parse_query("x://?a=%zz"), Err(SdjError::InvalidUri(_))));
+    }
+
+    #[test]
+    fn should_encode_form() {
+        let form = encode_form([("vp_token", r#"{"pid":["a~b~"]}"#), ("state", "x y")]);
+        assert_eq!(form, "vp_token=%7B%22pid%22%3A%5B%22a%7Eb%7E%22%5D%7D&state=x+y");
+        let parsed = try_parse_query(&format!("?{form}")).unwrap();
+        assert_eq!(parsed[0].1, r#"{"pid":["a~b~"]}"#);
+    }
+
+    #[test]
+    fn should_read_host() {
+        assert_eq!(host("https://verifier.example.com/post"), Some("verifier.example.com"));
+        assert_eq!(
+            host("https://verifier.example.com:8443?a=b"),
+            Some("verifier.example.com")
+        );
+        assert_eq!(host("http://verifier.example.com"), None);
+        assert_eq!(host("https://"), None);
+    }
+}
@@ -1,25 +1,41 @@
 use crate::crypto::{
-    error::{CryptoError, CryptoResult},
-    Salt,
+    error::CryptoResult,
+    salt_source::{ChaCha20SaltSource, SaltSource},
+    Salt, DEFAULT_SALT_SIZE,
 };
 
 /// A reusable backend for all crypto related stuff
-pub struct CryptoBackend<const SALT_SIZE: usize = { super::DEFAULT_SALT_SIZE }> {
-    rng: std::sync::RwLock<rand_chacha::ChaCha20Rng>,
+pub struct CryptoBackend {
+    salt_source: Box<dyn SaltSource + Send + Sync>,
+    salt_size: usize,
 }
 
-impl<const SALT_SIZE: usize> CryptoBackend<SALT_SIZE> {
+impl CryptoBackend {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        use rand_chacha::rand_core::SeedableRng as _;
-        let rng = rand_chacha::ChaCha20Rng::from_entropy();
-        let rng = std::sync::RwLock::new(rng);
-        Self { rng }
+        Self {
+            salt_source: Box::new(ChaCha20SaltSource::new()),
+            salt_size: DEFAULT_SALT_SIZE,
+        }
     }
 
-    pub fn new_salt(&mut self) -> CryptoResult<Salt<SALT_SIZE>> {
-        // TODO: do we need to reseed RNG every time to have truly independent salts ?
-        let mut rng = self.rng.write().map_err(|_| CryptoError::PoisonError)?;
-        Salt::try_new(&mut rng)
+    pub fn set_salt_source(&mut self, salt_source: impl SaltSource + Send + Sync + 'static) {
+        self.salt_source = Box::new(salt_source);
+    }
+
+    /// Number of random bytes in the salts, at least [DEFAULT_SALT_SIZE]
+    pub fn set_salt_size(&mut self, salt_size: usize) {
+        self.salt_size = salt_size;
+    }
+
+    pub fn new_salt(&mut self) -> CryptoResult<Salt> {
+        Salt::try_new(self.salt_source.as_mut(), self.salt_size)
+    }
+
+    /// A digest matching no disclosure, hashing a fresh salt
+    pub fn new_decoy_digest(&mut self) -> CryptoResult<String> {
+        use sha2::Digest as _;
+        let digest = sha2::Sha256::digest(self.new_salt()?.as_bytes());
+        Ok(base64_simd::URL_SAFE_NO_PAD.encode_to_string(digest))
     }
 }
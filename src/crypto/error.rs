@@ -5,12 +5,20 @@ pub enum CryptoError {
     #[cfg(feature = "issuer")]
     #[error(transparent)]
     RngError(#[from] rand_chacha::rand_core::Error),
+    #[error("Failed generating random bytes")]
+    RandomError,
     #[error("Lock is poisonned")]
     PoisonError,
     #[error("Salt size is below the recommended size (16)")]
     SaltTooSmall,
+    #[error("No salt left in the salt source")]
+    SaltsExhausted,
     #[error("Invalid salt")]
     InvalidSalt,
+    #[error("Failed encrypting")]
+    EncryptionError,
+    #[error("Failed decrypting")]
+    DecryptionError,
     #[error(transparent)]
     Base64Error(#[from] base64_simd::Error),
     #[error("Internal error: {0}")]
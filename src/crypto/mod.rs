@@ -3,9 +3,14 @@ pub mod backend;
 
 pub mod error;
 pub mod hash;
+#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
+pub mod random;
 pub mod salt;
+#[cfg(feature = "issuer")]
+pub mod salt_source;
 pub mod sign;
 
 #[cfg(feature = "issuer")]
 pub use backend::CryptoBackend;
+#[cfg(feature = "issuer")]
 pub use salt::{Salt, DEFAULT_SALT_SIZE};
@@ -0,0 +1,8 @@
+use crate::crypto::error::{CryptoError, CryptoResult};
+
+/// A base64url encoded random value with 128 bits of entropy, e.g. for a nonce
+pub fn try_new_random_string() -> CryptoResult<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|_| CryptoError::RandomError)?;
+    Ok(base64_simd::URL_SAFE_NO_PAD.encode_to_string(bytes))
+}
@@ -1,38 +1,51 @@
 use crate::crypto::error::{CryptoError, CryptoResult};
 
 /// Recommended default minimum length
+#[cfg(feature = "issuer")]
 pub const DEFAULT_SALT_SIZE: usize = 128 / 8;
 
+/// Salt of a disclosure. Generated salts are base64url encoded random bytes, while the salts of
+/// parsed disclosures are kept as they are since they only SHOULD be base64url, of any length.
+///
 /// Security considerations:
 ///
 /// * The security model that conceals the plaintext claims relies on the fact that salts not
-/// revealed to an attacker cannot be learned or guessed by the attacker, even if other salts
-/// have been revealed. It is vitally important to adhere to this principle. As such, each salt
-/// MUST be created in such a manner that it is cryptographically random, long enough, and has
-/// high entropy that it is not practical for the attacker to guess. A new salt MUST be chosen
-/// for each claim independently from other salts.
+///   revealed to an attacker cannot be learned or guessed by the attacker, even if other salts
+///   have been revealed. It is vitally important to adhere to this principle. As such, each salt
+///   MUST be created in such a manner that it is cryptographically random, long enough, and has
+///   high entropy that it is not practical for the attacker to guess. A new salt MUST be chosen
+///   for each claim independently from other salts.
 ///
 /// * The RECOMMENDED minimum length of the randomly-generated portion of the salt is 128 bits.
-/// The Issuer MUST ensure that a new salt value is chosen for each claim, including when the
-/// same claim name occurs at different places in the structure of the SD-JWT. This can be seen
-/// in Example 3 in the Appendix, where multiple claims with the name type appear, but each of
-/// them has a different salt.
+///   The Issuer MUST ensure that a new salt value is chosen for each claim, including when the
+///   same claim name occurs at different places in the structure of the SD-JWT. This can be seen
+///   in Example 3 in the Appendix, where multiple claims with the name type appear, but each of
+///   them has a different salt.
 ///
 /// See also: Section [9.3](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.3)
 /// & [9.4](https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-9.4)
-#[derive(Debug, Clone, Eq, PartialEq)] // TODO: ct eq
-pub struct Salt<const SIZE: usize = DEFAULT_SALT_SIZE>([u8; SIZE]);
+#[derive(Debug, Clone, Eq, PartialEq, Hash)] // TODO: ct eq
+pub struct Salt(String);
 
 #[cfg(feature = "issuer")]
-impl<const SIZE: usize> Salt<SIZE> {
-    pub fn try_new(rng: &mut rand_chacha::ChaCha20Rng) -> CryptoResult<Self> {
-        use rand_chacha::rand_core::RngCore as _;
-        let mut bytes = [0u8; SIZE];
-        if bytes.len() < DEFAULT_SALT_SIZE {
+impl Salt {
+    /// Generates `size` random bytes, at least [DEFAULT_SALT_SIZE]
+    pub fn try_new(
+        source: &mut (impl crate::crypto::salt_source::SaltSource + ?Sized),
+        size: usize,
+    ) -> CryptoResult<Self> {
+        if size < DEFAULT_SALT_SIZE {
             return Err(CryptoError::SaltTooSmall);
         }
-        rng.try_fill_bytes(&mut bytes)?;
-        Ok(Self(bytes))
+        let mut bytes = vec![0u8; size];
+        source.try_fill(&mut bytes)?;
+        Ok(Self(base64_simd::URL_SAFE_NO_PAD.encode_to_string(bytes)))
+    }
+}
+
+impl Salt {
+    pub fn as_str(&self) -> &str {
+        &self.0
     }
 }
 
@@ -42,29 +55,26 @@ impl serde::Serialize for Salt {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        serializer.serialize_str(&self.0)
     }
 }
 
-impl ToString for Salt {
-    fn to_string(&self) -> String {
-        use base64ct::Encoding as _;
-        base64ct::Base64UrlUnpadded::encode_string(&self.0)
+impl std::fmt::Display for Salt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-impl<const SIZE: usize> std::str::FromStr for Salt<SIZE> {
+impl std::str::FromStr for Salt {
     type Err = CryptoError;
 
     fn from_str(s: &str) -> CryptoResult<Self> {
-        let bytes = base64_simd::URL_SAFE_NO_PAD.decode_to_vec(s)?;
-        let bytes = bytes.try_into().map_err(|_| CryptoError::InvalidSalt)?;
-        Ok(Self(bytes))
+        Ok(Self(s.to_string()))
     }
 }
 
-impl<const SIZE: usize> std::ops::Deref for Salt<SIZE> {
-    type Target = [u8; SIZE];
+impl std::ops::Deref for Salt {
+    type Target = str;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -74,14 +84,22 @@ impl<const SIZE: usize> std::ops::Deref for Salt<SIZE> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use rand_chacha::rand_core::SeedableRng;
+    use crate::crypto::salt_source::ChaCha20SaltSource;
 
     #[test]
     fn should_fail_when_size_too_small() {
-        let mut rng = rand_chacha::ChaCha20Rng::from_entropy();
+        let mut source = ChaCha20SaltSource::new();
         assert!(matches!(
-            Salt::<15>::try_new(&mut rng).unwrap_err(),
+            Salt::try_new(&mut source, 15).unwrap_err(),
             CryptoError::SaltTooSmall
         ));
+        assert_eq!(Salt::try_new(&mut source, 32).unwrap().len(), 43);
+    }
+
+    #[test]
+    fn should_parse_any_salt() {
+        for salt in ["_26bc4LT-ac6q2KI6cBW5es", "short", "not base64url: äöü!", ""] {
+            assert_eq!(salt.parse::<Salt>().unwrap().as_str(), salt);
+        }
     }
 }
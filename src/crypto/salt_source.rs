@@ -0,0 +1,168 @@
+use crate::crypto::error::{CryptoError, CryptoResult};
+
+/// Where the Issuer gets the salts of the disclosures from
+///
+/// Salts must be unpredictable to anyone but the Holder, otherwise undisclosed claims can be
+/// guessed from their digest. Only [OsSaltSource], [ChaCha20SaltSource::new] and [HkdfSaltSource]
+/// are fit for production, the deterministic sources are for golden tests & test vectors.
+pub trait SaltSource {
+    /// Fills the salt of the next disclosure
+    fn try_fill(&mut self, salt: &mut [u8]) -> CryptoResult<()>;
+}
+
+/// Salts straight from the OS random number generator
+#[derive(Debug, Copy, Clone, Default)]
+pub struct OsSaltSource;
+
+impl SaltSource for OsSaltSource {
+    fn try_fill(&mut self, salt: &mut [u8]) -> CryptoResult<()> {
+        getrandom::getrandom(salt).map_err(|_| CryptoError::RandomError)
+    }
+}
+
+/// Salts from a ChaCha20 CSPRNG, the default
+#[derive(Debug, Clone)]
+pub struct ChaCha20SaltSource(rand_chacha::ChaCha20Rng);
+
+impl ChaCha20SaltSource {
+    /// Seeded from the OS random number generator
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        use rand_chacha::rand_core::SeedableRng as _;
+        Self(rand_chacha::ChaCha20Rng::from_entropy())
+    }
+
+    /// Seeded with a fixed value, hence yielding the same salts every time.
+    ///
+    /// INSECURE: anyone knowing the seed can recover every undisclosed claim, only use it in tests.
+    pub fn insecure_from_seed(seed: [u8; 32]) -> Self {
+        use rand_chacha::rand_core::SeedableRng as _;
+        Self(rand_chacha::ChaCha20Rng::from_seed(seed))
+    }
+}
+
+impl SaltSource for ChaCha20SaltSource {
+    fn try_fill(&mut self, salt: &mut [u8]) -> CryptoResult<()> {
+        use rand_chacha::rand_core::RngCore as _;
+        Ok(self.0.try_fill_bytes(salt)?)
+    }
+}
+
+/// A fixed sequence of salts, e.g. the ones of a test vector, handed out in order
+///
+/// INSECURE: the salts are known in advance, only use it in tests.
+#[derive(Debug, Clone)]
+pub struct FixedSaltSource(std::collections::VecDeque<Vec<u8>>);
+
+impl FixedSaltSource {
+    /// From base64url encoded salts
+    pub fn insecure_try_new<S: AsRef<str>>(salts: impl IntoIterator<Item = S>) -> CryptoResult<Self> {
+        let salts = salts
+            .into_iter()
+            .map(|s| Ok(base64_simd::URL_SAFE_NO_PAD.decode_to_vec(s.as_ref())?))
+            .collect::<CryptoResult<_>>()?;
+        Ok(Self(salts))
+    }
+
+    /// Number of salts left
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl SaltSource for FixedSaltSource {
+    fn try_fill(&mut self, salt: &mut [u8]) -> CryptoResult<()> {
+        let next = self.0.pop_front().ok_or(CryptoError::SaltsExhausted)?;
+        if next.len() != salt.len() {
+            return Err(CryptoError::InvalidSalt);
+        }
+        salt.copy_from_slice(&next);
+        Ok(())
+    }
+}
+
+/// Salts derived with HKDF-SHA256 from an Issuer secret & a credential identifier, so that the
+/// salts of a credential can be regenerated instead of being stored. The secret must have at least
+/// 256 bits of entropy and never leave the Issuer, and every credential needs its own identifier.
+#[derive(Clone)]
+pub struct HkdfSaltSource {
+    hkdf: hkdf::Hkdf<sha2::Sha256>,
+    counter: u32,
+}
+
+impl HkdfSaltSource {
+    const INFO: &'static [u8] = b"sd-jwt disclosure salt";
+
+    pub fn new(issuer_secret: &[u8], credential_id: &str) -> Self {
+        Self {
+            hkdf: hkdf::Hkdf::new(Some(credential_id.as_bytes()), issuer_secret),
+            counter: 0,
+        }
+    }
+}
+
+impl std::fmt::Debug for HkdfSaltSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HkdfSaltSource")
+            .field("counter", &self.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SaltSource for HkdfSaltSource {
+    fn try_fill(&mut self, salt: &mut [u8]) -> CryptoResult<()> {
+        let info = [Self::INFO, &self.counter.to_be_bytes()].concat();
+        self.hkdf
+            .expand(&info, salt)
+            .map_err(|_| CryptoError::ImplementationError("HKDF output too long"))?;
+        self.counter += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::crypto::Salt;
+
+    fn salts(source: &mut impl SaltSource, n: usize) -> Vec<String> {
+        (0..n).map(|_| Salt::try_new(source, 16).unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn should_be_deterministic_when_insecure() {
+        let seed = [7u8; 32];
+        let a = salts(&mut ChaCha20SaltSource::insecure_from_seed(seed), 3);
+        assert_eq!(a, salts(&mut ChaCha20SaltSource::insecure_from_seed(seed), 3));
+        assert_ne!(a[0], a[1]);
+        assert_ne!(
+            salts(&mut ChaCha20SaltSource::new(), 1),
+            salts(&mut ChaCha20SaltSource::new(), 1)
+        );
+
+        let mut fixed =
+            FixedSaltSource::insecure_try_new(["2GLC42sKQveCfGfryNRN9w", "eluV5Og3gSNII8EYnsxA_A"]).unwrap();
+        assert_eq!(
+            salts(&mut fixed, 2),
+            ["2GLC42sKQveCfGfryNRN9w", "eluV5Og3gSNII8EYnsxA_A"]
+        );
+        assert!(matches!(
+            Salt::try_new(&mut fixed, 16).unwrap_err(),
+            CryptoError::SaltsExhausted
+        ));
+    }
+
+    #[test]
+    fn should_regenerate_hkdf_salts() {
+        let secret = [42u8; 32];
+        let a = salts(&mut HkdfSaltSource::new(&secret, "credential-1"), 3);
+        assert_eq!(a, salts(&mut HkdfSaltSource::new(&secret, "credential-1"), 3));
+        assert_ne!(a[0], a[1]);
+        assert_ne!(a, salts(&mut HkdfSaltSource::new(&secret, "credential-2"), 3));
+        assert_ne!(a, salts(&mut HkdfSaltSource::new(&[43u8; 32], "credential-1"), 3));
+    }
+}
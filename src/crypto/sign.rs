@@ -1,5 +1,5 @@
 /// Signature Algorithm
-#[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum JwsAlgorithm {
     /// EdDSA using Ed25519
     ///
@@ -23,3 +23,23 @@ pub enum JwsAlgorithm {
     /// [1]: https://tools.ietf.org/html/rfc7518#section-3.4
     P384,
 }
+
+impl JwsAlgorithm {
+    /// Value of the `alg` JOSE header
+    pub fn to_jws_alg(&self) -> &'static str {
+        match self {
+            Self::Ed25519 => "EdDSA",
+            Self::P256 => "ES256",
+            Self::P384 => "ES384",
+        }
+    }
+
+    pub fn from_jws_alg(alg: &str) -> Option<Self> {
+        match alg {
+            "EdDSA" => Some(Self::Ed25519),
+            "ES256" => Some(Self::P256),
+            "ES384" => Some(Self::P384),
+            _ => None,
+        }
+    }
+}
@@ -15,6 +15,16 @@ pub enum SdjError {
     JsonPointerError(String),
     #[error("Invalid Json pointer path {0}")]
     InvalidJsonPointerPath(String),
+    #[error("Invalid JSONPath {0}")]
+    InvalidJsonPath(String),
+    #[error("Invalid claims path {0}")]
+    InvalidClaimsPath(String),
+    #[error("No claim at claims path {0}")]
+    MissingClaim(crate::core::dcql::claims_path::ClaimsPath),
+    #[error("Invalid disclosure frame: {0}")]
+    InvalidDisclosureFrame(String),
+    #[error("Decision '{0}' matches no claim")]
+    UnmatchedDecision(String),
     #[error("Invalid format of the SD-Jwt")]
     InvalidSerializedSdJwt,
     #[error("Invalid JWT")]
@@ -23,6 +33,55 @@ pub enum SdjError {
     UnknownDisclosure,
     #[error("Invalid Disclosure")]
     InvalidDisclosure,
+    #[error("A Disclosure is either repeated or referenced more than once")]
+    DuplicateDisclosure,
+    #[error("A Disclosure is not referenced by any digest")]
+    UnreferencedDisclosure,
+    #[error("Unsupported hash algorithm '{0}'")]
+    UnsupportedHashAlgorithm(String),
+    #[error(transparent)]
+    DcqlError(#[from] crate::core::dcql::error::DcqlError),
+    #[error("Invalid JWK")]
+    InvalidJwk,
+    #[error("Unsupported signature algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+    #[error(transparent)]
+    Oid4vciError(#[from] crate::core::oid4vci::error::Oid4vciError),
+    #[error(transparent)]
+    Oid4vpError(#[from] crate::core::oid4vp::error::Oid4vpError),
+    #[error("Invalid X.509 certificate")]
+    InvalidCertificate,
+    #[error("Invalid URI '{0}'")]
+    InvalidUri(String),
+    #[error(transparent)]
+    PresentationExchangeError(#[from] crate::core::presentation_exchange::error::PresentationExchangeError),
+    #[error("Invalid Key Binding JWT: {0}")]
+    InvalidKeyBinding(String),
+    #[error("Nonce '{0}' was already used, has expired or was never issued")]
+    ReplayedNonce(String),
+    #[error("Invalid JWE")]
+    InvalidJwe,
+    #[error("Verified claims don't match the expected type: {0}")]
+    UnexpectedClaims(String),
+    #[error("Invalid vp_token")]
+    InvalidVpToken,
+    #[cfg(feature = "holder")]
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[cfg(feature = "holder")]
+    #[error("No credential with id '{0}' in the store")]
+    CredentialNotFound(String),
+    #[cfg(feature = "holder")]
+    #[error("Unsupported credential backup version {0}")]
+    UnsupportedBackupVersion(u32),
+    #[cfg(feature = "holder")]
+    #[error("HTTP request failed with status {0}")]
+    HttpError(u16),
+    #[cfg(feature = "holder")]
+    #[error("Transport error: {0}")]
+    TransportError(String),
+    #[error("{0} requires the '{1}' feature")]
+    FeatureRequired(&'static str, &'static str),
     #[error("Unexpected internal error")]
     ImplementationError,
 }
@@ -0,0 +1,246 @@
+use crate::{
+    core::dcql::{error::DcqlError, DcqlQuery},
+    error::SdjResult,
+    holder::{
+        presentation::Presentation,
+        selection::Selection,
+        store::{CredentialId, CredentialStore},
+        verified::VerifiedSDJwt,
+    },
+};
+use std::collections::BTreeMap;
+
+/// A credential satisfying a credential query, along with the minimal disclosures for that
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DcqlMatch {
+    pub query_id: String,
+    pub credential_id: CredentialId,
+    /// Json pointers of the requested claims
+    pub claims: Vec<String>,
+    /// Positions of the disclosures to present
+    pub disclosures: Vec<usize>,
+}
+
+/// All the credentials satisfying (or not) every credential query of a [DcqlQuery]
+#[derive(Debug, Clone)]
+pub struct DcqlEvaluation {
+    pub credentials: Vec<CredentialQueryEvaluation>,
+    /// Ids of the credential queries left without candidate for satisfying the query
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CredentialQueryEvaluation {
+    pub query_id: String,
+    pub candidates: Vec<DcqlMatch>,
+    /// Why the other credentials were discarded
+    pub unmet: Vec<(CredentialId, DcqlError)>,
+}
+
+impl DcqlQuery {
+    /// Finds the candidate credentials for every credential query
+    pub fn evaluate<'a>(&self, credentials: impl IntoIterator<Item = &'a VerifiedSDJwt>) -> DcqlEvaluation {
+        let credentials = credentials.into_iter().collect::<Vec<_>>();
+        let evaluations = self
+            .credentials
+            .iter()
+            .map(|query| {
+                let mut evaluation = CredentialQueryEvaluation {
+                    query_id: query.id.clone(),
+                    candidates: vec![],
+                    unmet: vec![],
+                };
+                for credential in &credentials {
+                    match query.try_match(credential.claims()) {
+                        Ok(claims) => evaluation
+                            .candidates
+                            .push(minimal_disclosures(&query.id, claims, credential)),
+                        Err(e) => evaluation.unmet.push((credential.id(), e)),
+                    }
+                }
+                evaluation
+            })
+            .collect::<Vec<CredentialQueryEvaluation>>();
+        let missing = self.missing(|id| evaluations.iter().any(|e| e.query_id == id && !e.candidates.is_empty()));
+        DcqlEvaluation {
+            credentials: evaluations,
+            missing,
+        }
+    }
+
+    /// Finds the candidate credentials in the store for every credential query
+    pub fn try_evaluate_store(&self, store: &impl CredentialStore) -> SdjResult<DcqlEvaluation> {
+        let credentials = store
+            .list()?
+            .into_iter()
+            .map(|c| VerifiedSDJwt::try_from_trusted(c.sd_jwt))
+            .collect::<SdjResult<Vec<_>>>()?;
+        Ok(self.evaluate(&credentials))
+    }
+
+    /// Builds the presentations of the chosen credentials, by credential query id as expected in
+    /// the `vp_token`, disclosing only what each credential query requests
+    pub fn try_present<'a>(
+        &self,
+        choices: &[(&str, &'a VerifiedSDJwt)],
+    ) -> SdjResult<BTreeMap<String, Vec<Presentation<'a>>>> {
+        let mut presentations = BTreeMap::<String, Vec<Presentation<'a>>>::new();
+        for (query_id, credential) in choices {
+            let query = self
+                .credential(query_id)
+                .ok_or_else(|| DcqlError::UnknownCredentialQuery(query_id.to_string()))?;
+            let matched = minimal_disclosures(&query.id, query.try_match(credential.claims())?, credential);
+            let presented = presentations.entry(query.id.clone()).or_default();
+            if !query.multiple && !presented.is_empty() {
+                return Err(DcqlError::MultipleNotAllowed(query.id.clone()).into());
+            }
+            presented.push(credential.select_disclosures(&matched.disclosures));
+        }
+
+        let missing = self.missing(|id| presentations.contains_key(id));
+        if !missing.is_empty() {
+            return Err(DcqlError::NotSatisfied { missing }.into());
+        }
+        Ok(presentations)
+    }
+}
+
+fn minimal_disclosures(query_id: &str, claims: Vec<String>, credential: &VerifiedSDJwt) -> DcqlMatch {
+    let selection = claims.iter().fold(Selection::none(), |selection, pointer| {
+        selection.subtree(pointer.clone())
+    });
+    let mut disclosures = selection.select(credential.processed());
+    disclosures.sort_unstable();
+    disclosures.dedup();
+    DcqlMatch {
+        query_id: query_id.to_string(),
+        credential_id: credential.id(),
+        claims,
+        disclosures,
+    }
+}
+
+impl DcqlEvaluation {
+    /// Whether the candidates can satisfy the query
+    pub fn is_satisfied(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// The disclosures to present for every candidate credential, by credential id
+    pub fn disclosures_by_credential(&self) -> BTreeMap<CredentialId, Vec<&DcqlMatch>> {
+        let mut matches = BTreeMap::<CredentialId, Vec<&DcqlMatch>>::new();
+        for candidate in self.credentials.iter().flat_map(|c| &c.candidates) {
+            matches
+                .entry(candidate.credential_id.clone())
+                .or_default()
+                .push(candidate);
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        holder::{store::memory::InMemoryCredentialStore, store::StoredCredential, verified::tests::verified},
+        prelude::{Issuer, IssuerOptions, SdjError},
+    };
+    use serde_json::{json, Value};
+
+    fn query(value: Value) -> DcqlQuery {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn should_compute_minimal_disclosures() {
+        let verified = verified();
+        let query = query(json!({
+            "credentials": [{
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "claims": [
+                    {"path": ["given_name"]},
+                    {"path": ["address", "country"]},
+                    {"path": ["nationalities", null], "values": ["DE"]}
+                ]
+            }]
+        }));
+
+        let evaluation = query.evaluate([&verified]);
+        assert!(evaluation.is_satisfied());
+        let by_credential = evaluation.disclosures_by_credential();
+        let matched = &by_credential[&verified.id()][0];
+        assert_eq!(
+            matched.claims,
+            vec!["/given_name", "/address/country", "/nationalities/1"]
+        );
+
+        let presentations = query.try_present(&[("pid", &verified)]).unwrap();
+        let names = presentations["pid"][0]
+            .disclosures
+            .iter()
+            .map(|d| {
+                d.name()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| d.value().to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["given_name", "country", "address", "\"DE\""]);
+    }
+
+    #[test]
+    fn should_find_candidates_in_store() {
+        let mut store = InMemoryCredentialStore::default();
+        let mut issuer = Issuer::try_new().unwrap();
+        for vct in ["pid", "mdl"] {
+            let sd_jwt = issuer
+                .try_generate_sd_jwt(
+                    json!({"vct": vct, "given_name": "John"}),
+                    &["/given_name"],
+                    IssuerOptions::default(),
+                )
+                .unwrap();
+            store.save(StoredCredential::try_new(sd_jwt).unwrap()).unwrap();
+        }
+
+        let query = query(json!({
+            "credentials": [
+                {"id": "mdl", "format": "dc+sd-jwt", "meta": {"vct_values": ["mdl"]}, "claims": [{"path": ["given_name"]}]},
+                {"id": "passport", "format": "dc+sd-jwt", "meta": {"vct_values": ["passport"]}}
+            ],
+            "credential_sets": [{"options": [["mdl"], ["passport"]]}]
+        }));
+        let evaluation = query.try_evaluate_store(&store).unwrap();
+        assert!(evaluation.is_satisfied());
+        let mdl = &evaluation.credentials[0];
+        assert_eq!(mdl.candidates.len(), 1);
+        assert!(matches!(mdl.unmet[0].1, DcqlError::UnexpectedVct { .. }));
+        assert!(evaluation.credentials[1].candidates.is_empty());
+
+        let candidate = store.get(&mdl.candidates[0].credential_id).unwrap().unwrap();
+        assert_eq!(candidate.metadata.vct.as_deref(), Some("mdl"));
+    }
+
+    #[test]
+    fn should_fail_presenting_unsatisfied_query() {
+        let verified = verified();
+        let query = query(json!({
+            "credentials": [
+                {"id": "pid", "format": "dc+sd-jwt"},
+                {"id": "mdl", "format": "dc+sd-jwt"}
+            ]
+        }));
+        assert!(!query.evaluate([]).is_satisfied());
+        assert!(matches!(
+            query.try_present(&[("pid", &verified)]).unwrap_err(),
+            SdjError::DcqlError(DcqlError::NotSatisfied { missing }) if missing == vec!["mdl"]
+        ));
+        assert!(matches!(
+            query
+                .try_present(&[("pid", &verified), ("pid", &verified)])
+                .unwrap_err(),
+            SdjError::DcqlError(DcqlError::MultipleNotAllowed(_))
+        ));
+    }
+}
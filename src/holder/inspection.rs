@@ -0,0 +1,221 @@
+use crate::{
+    core::{
+        disclosure::Disclosure,
+        json_pointer::escape,
+        processing::{is_ancestor_or_self, ProcessedClaims, ProcessedDisclosure},
+    },
+    holder::verified::VerifiedSDJwt,
+};
+use serde_json::Value;
+
+/// Everything a credential contains e.g. for displaying it on a consent screen before sharing it
+#[derive(Debug, Clone)]
+pub struct Inspection {
+    /// The top-level claims
+    pub claims: Vec<ClaimNode>,
+    /// Inconsistencies for which the Issuer is to blame
+    pub issuer_errors: Vec<IssuerInconsistency>,
+}
+
+/// A claim or an array item of the credential, once all its disclosures have been disclosed
+#[derive(Debug, Clone)]
+pub struct ClaimNode {
+    /// Json pointer to the claim in the disclosed-claim view
+    pub path: String,
+    /// Claim name. None for array items
+    pub name: Option<String>,
+    /// Fully disclosed value
+    pub value: Value,
+    pub visibility: ClaimVisibility,
+    /// Digest of the closest disclosure this claim is nested in. It has to be disclosed as well for
+    /// this claim to be revealed
+    pub parent_disclosure: Option<String>,
+    /// Digests of the closest disclosures nested in this claim, which can be disclosed or not
+    pub child_disclosures: Vec<String>,
+    /// Nested claims or array items
+    pub children: Vec<ClaimNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimVisibility {
+    /// In clear in the Issuer-signed JWT, the Verifier will always see it
+    AlwaysVisible,
+    /// Has its own disclosure
+    SelectivelyDisclosable {
+        /// Digest of the disclosure
+        digest: String,
+    },
+    /// In clear in the value of its parent disclosure, revealed along with it
+    DisclosedWithParent,
+}
+
+/// An inconsistency for which the Issuer is to blame
+#[derive(Debug, Clone)]
+pub enum IssuerInconsistency {
+    /// A disclosure which no digest in the payload references, hence of no use to the Verifier
+    UnreferencedDisclosure { digest: String, disclosure: Disclosure },
+}
+
+impl Inspection {
+    pub(crate) fn new(verified: &VerifiedSDJwt) -> Self {
+        let processed = verified.processed();
+        let disclosures = &verified.sd_jwt().disclosures;
+
+        let issuer_errors = processed
+            .unreferenced
+            .iter()
+            .map(|&i| IssuerInconsistency::UnreferencedDisclosure {
+                digest: disclosures[i].hash().map(Into::into).unwrap_or_default(),
+                disclosure: disclosures[i].clone(),
+            })
+            .collect();
+
+        Self {
+            claims: children(processed, &processed.claims, "", None),
+            issuer_errors,
+        }
+    }
+
+    /// Finds a claim by its Json pointer in the disclosed-claim view
+    pub fn find(&self, path: &str) -> Option<&ClaimNode> {
+        let mut nodes = &self.claims;
+        loop {
+            let node = nodes.iter().find(|n| is_ancestor_or_self(&n.path, path))?;
+            if node.path == path {
+                return Some(node);
+            }
+            nodes = &node.children;
+        }
+    }
+}
+
+fn children(
+    processed: &ProcessedClaims,
+    value: &Value,
+    path: &str,
+    enclosing: Option<&ProcessedDisclosure>,
+) -> Vec<ClaimNode> {
+    let children: Vec<(Option<&String>, String, &Value)> = match value {
+        Value::Object(object) => object.iter().map(|(k, v)| (Some(k), escape(k), v)).collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (None, i.to_string(), v))
+            .collect(),
+        _ => return vec![],
+    };
+    children
+        .into_iter()
+        .map(|(name, token, child)| node(processed, name.cloned(), child, format!("{path}/{token}"), enclosing))
+        .collect()
+}
+
+fn node(
+    processed: &ProcessedClaims,
+    name: Option<String>,
+    value: &Value,
+    path: String,
+    enclosing: Option<&ProcessedDisclosure>,
+) -> ClaimNode {
+    let own = processed.disclosures.iter().find(|d| d.path == path);
+    let visibility = match (own, enclosing) {
+        (Some(d), _) => ClaimVisibility::SelectivelyDisclosable {
+            digest: d.digest.clone(),
+        },
+        (None, Some(_)) => ClaimVisibility::DisclosedWithParent,
+        (None, None) => ClaimVisibility::AlwaysVisible,
+    };
+
+    let parent_disclosure = match own {
+        Some(d) => d
+            .parent
+            .and_then(|p| processed.disclosures.iter().find(|d| d.index == p))
+            .map(|d| d.digest.clone()),
+        None => enclosing.map(|d| d.digest.clone()),
+    };
+
+    let closest = own.or(enclosing).map(|d| d.index);
+    let child_disclosures = processed
+        .disclosures
+        .iter()
+        .filter(|d| d.path != path && is_ancestor_or_self(&path, &d.path) && d.parent == closest)
+        .map(|d| d.digest.clone())
+        .collect();
+
+    ClaimNode {
+        children: children(processed, value, &path, own.or(enclosing)),
+        path,
+        name,
+        value: value.clone(),
+        visibility,
+        parent_disclosure,
+        child_disclosures,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        holder::verified::tests::verified,
+        prelude::{Holder, Issuer, IssuerOptions},
+    };
+    use serde_json::json;
+
+    #[test]
+    fn should_inspect_every_claim() {
+        let verified = verified();
+        let inspection = Holder::inspect(&verified);
+        assert!(inspection.issuer_errors.is_empty());
+
+        let iss = inspection.find("/iss").unwrap();
+        assert_eq!(iss.name.as_deref(), Some("iss"));
+        assert_eq!(iss.value, json!("https://example.com/issuer"));
+        assert_eq!(iss.visibility, ClaimVisibility::AlwaysVisible);
+        assert!(iss.parent_disclosure.is_none());
+
+        let address = inspection.find("/address").unwrap();
+        let ClaimVisibility::SelectivelyDisclosable { digest: address_digest } = &address.visibility else {
+            panic!("address should be selectively disclosable")
+        };
+        assert_eq!(address.child_disclosures.len(), 1);
+
+        let country = inspection.find("/address/country").unwrap();
+        assert!(matches!(
+            country.visibility,
+            ClaimVisibility::SelectivelyDisclosable { .. }
+        ));
+        assert_eq!(country.parent_disclosure.as_ref(), Some(address_digest));
+        assert_eq!(country.value, json!("US"));
+
+        let street = inspection.find("/address/street_address").unwrap();
+        assert_eq!(street.visibility, ClaimVisibility::DisclosedWithParent);
+        assert_eq!(street.parent_disclosure.as_ref(), Some(address_digest));
+
+        let nationalities = inspection.find("/nationalities").unwrap();
+        assert_eq!(nationalities.visibility, ClaimVisibility::AlwaysVisible);
+        assert_eq!(nationalities.child_disclosures.len(), 2);
+        let de = inspection.find("/nationalities/1").unwrap();
+        assert!(de.name.is_none());
+        assert!(matches!(de.visibility, ClaimVisibility::SelectivelyDisclosable { .. }));
+        assert!(de.parent_disclosure.is_none());
+    }
+
+    #[test]
+    fn should_flag_unreferenced_disclosures() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let other = issuer
+            .try_generate_sd_jwt(json!({"a": 1}), &["/a"], IssuerOptions::default())
+            .unwrap();
+
+        let mut sd_jwt = verified().into_sd_jwt();
+        sd_jwt.disclosures.extend(other.disclosures);
+        let verified = VerifiedSDJwt::try_from_trusted(sd_jwt).unwrap();
+
+        let inspection = Holder::inspect(&verified);
+        assert!(matches!(
+            &inspection.issuer_errors[..],
+            [IssuerInconsistency::UnreferencedDisclosure { disclosure, .. }] if disclosure.name() == Some("a")
+        ));
+    }
+}
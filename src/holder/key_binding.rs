@@ -0,0 +1,187 @@
+use crate::{
+    core::{
+        jwk::Jwk,
+        jwt::JwtHeader,
+        key_binding::{try_sd_hash, try_transaction_data_hashes, KeyBindingClaims, KB_JWT_TYP},
+        oid4vp::TransactionData,
+    },
+    error::{SdjError, SdjResult},
+    holder::presentation::Presentation,
+    prelude::{HashAlgorithm, JwsAlgorithm},
+};
+use serde_json::{Map, Value};
+
+/// Builds the Key Binding JWT of a [Presentation], see [Presentation::key_binding]
+pub struct KeyBindingBuilder<'a> {
+    presentation: Presentation<'a>,
+    claims: KeyBindingClaims,
+}
+
+impl<'a> Presentation<'a> {
+    /// Appends a Key Binding JWT signed with the holder key the credential is bound to, proving
+    /// this presentation is meant for the Verifier `aud` in the transaction identified by `nonce`
+    ///
+    /// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-08.html#name-key-binding-jwt
+    pub fn try_bind(self, alg: JwsAlgorithm, key_pair: &str, aud: &str, nonce: &str, now: u64) -> SdjResult<Self> {
+        self.key_binding(aud, nonce, now).try_sign(alg, key_pair)
+    }
+
+    /// Starts a Key Binding JWT which, besides `aud` & `nonce`, can carry transaction data or
+    /// other claims
+    pub fn key_binding(self, aud: &str, nonce: &str, now: u64) -> KeyBindingBuilder<'a> {
+        KeyBindingBuilder {
+            presentation: self,
+            claims: KeyBindingClaims {
+                iat: now,
+                aud: aud.to_string(),
+                nonce: nonce.to_string(),
+                sd_hash: String::new(),
+                transaction_data_hashes: None,
+                transaction_data_hashes_alg: None,
+                other: Map::new(),
+            },
+        }
+    }
+}
+
+impl<'a> KeyBindingBuilder<'a> {
+    /// Binds the `transaction_data` of an OpenID4VP request, as passed i.e. base64url encoded
+    pub fn try_with_transaction_data(mut self, transaction_data: &[String]) -> SdjResult<Self> {
+        let alg = HashAlgorithm::SHA256.to_jwt_claim();
+        for encoded in transaction_data {
+            let decoded = TransactionData::try_decode(encoded)?;
+            if !decoded.accepts_hash_alg(alg) {
+                let accepted = decoded.transaction_data_hashes_alg.unwrap_or_default().join(", ");
+                return Err(SdjError::UnsupportedHashAlgorithm(accepted));
+            }
+        }
+        self.claims.transaction_data_hashes = Some(try_transaction_data_hashes(transaction_data, Some(alg))?);
+        self.claims.transaction_data_hashes_alg = Some(alg.to_string());
+        Ok(self)
+    }
+
+    /// Adds a claim, which can't be one of [KeyBindingClaims::RESERVED]
+    pub fn with_claim(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.claims.other.insert(name.into(), value.into());
+        self
+    }
+
+    /// Signs the KB-JWT with the holder key the credential is bound to
+    pub fn try_sign(self, alg: JwsAlgorithm, key_pair: &str) -> SdjResult<Presentation<'a>> {
+        let Self {
+            mut presentation,
+            mut claims,
+        } = self;
+        if let Some(reserved) = KeyBindingClaims::RESERVED
+            .iter()
+            .find(|c| claims.other.contains_key(**c))
+        {
+            return Err(SdjError::InvalidKeyBinding(format!("'{reserved}' can't be overridden")));
+        }
+        let payload = presentation.jws.try_read_unverified_payload()?;
+        let jwk = Jwk::try_from_key_pair_pem(alg, key_pair)?;
+        if let Some(cnf) = payload.pointer("/cnf/jwk") {
+            if serde_json::from_value::<Jwk>(cnf.clone()).ok().as_ref() != Some(&jwk) {
+                return Err(SdjError::InvalidKeyBinding(
+                    "not the key the credential is bound to".to_string(),
+                ));
+            }
+        }
+
+        presentation.key_binding = None;
+        claims.sd_hash = try_sd_hash(&presentation.try_serialize()?, &payload)?;
+        presentation.key_binding = Some(JwtHeader::new(alg, KB_JWT_TYP).try_sign(&claims, key_pair)?);
+        Ok(presentation)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        core::jwt::try_decode_unverified,
+        holder::verified::VerifiedSDJwt,
+        prelude::{Issuer, IssuerOptions, SDJwt},
+    };
+    use jwt_simple::prelude::ES256KeyPair;
+    use serde_json::json;
+
+    #[test]
+    fn should_bind_presentation() {
+        let key_pair = ES256KeyPair::generate().to_pem().unwrap();
+        let options = IssuerOptions {
+            cnf: Some(Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &key_pair).unwrap()),
+            ..Default::default()
+        };
+        let input = json!({"given_name": "John", "family_name": "Doe"});
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt(input, &["/given_name", "/family_name"], options)
+            .unwrap();
+        let verified = VerifiedSDJwt::try_from_trusted(sd_jwt).unwrap();
+
+        let presentation = verified.select(["/given_name"]).unwrap();
+        let unbound = presentation.try_serialize().unwrap();
+        let bound = presentation
+            .try_bind(
+                JwsAlgorithm::P256,
+                &key_pair,
+                "x509_san_dns:verifier.example.com",
+                "n-0S6",
+                1_700_000_000,
+            )
+            .unwrap()
+            .try_serialize()
+            .unwrap();
+        assert!(bound.starts_with(&unbound));
+
+        let kb_jwt = bound.parse::<SDJwt>().unwrap().key_binding.unwrap();
+        let (header, claims) = try_decode_unverified(&kb_jwt).unwrap();
+        assert_eq!(header.typ.as_deref(), Some(KB_JWT_TYP));
+        let claims: KeyBindingClaims = serde_json::from_value(claims).unwrap();
+        assert_eq!(claims.aud, "x509_san_dns:verifier.example.com");
+        assert_eq!(claims.nonce, "n-0S6");
+        assert_eq!(claims.sd_hash, try_sd_hash(&unbound, &json!({})).unwrap());
+
+        let transaction_data = TransactionData {
+            r#type: "payment".to_string(),
+            credential_ids: vec!["pid".to_string()],
+            transaction_data_hashes_alg: None,
+            details: Map::from_iter([("amount".to_string(), json!("42.00 EUR"))]),
+        }
+        .try_encode()
+        .unwrap();
+        let bound = verified
+            .select(["/given_name"])
+            .unwrap()
+            .key_binding("x509_san_dns:verifier.example.com", "n-0S6", 1_700_000_000)
+            .try_with_transaction_data(std::slice::from_ref(&transaction_data))
+            .unwrap()
+            .with_claim("purpose", "checkout")
+            .try_sign(JwsAlgorithm::P256, &key_pair)
+            .unwrap()
+            .to_sd_jwt();
+        let (_, claims) = bound.try_read_key_binding().unwrap().unwrap();
+        assert_eq!(
+            claims.transaction_data_hashes.unwrap(),
+            try_transaction_data_hashes(&[transaction_data], None).unwrap()
+        );
+        assert_eq!(claims.transaction_data_hashes_alg.as_deref(), Some("sha-256"));
+        assert_eq!(claims.other["purpose"], "checkout");
+
+        let overriding = verified
+            .select(["/given_name"])
+            .unwrap()
+            .key_binding("aud", "nonce", 1_700_000_000)
+            .with_claim("nonce", "other")
+            .try_sign(JwsAlgorithm::P256, &key_pair);
+        assert!(matches!(overriding, Err(SdjError::InvalidKeyBinding(_))));
+
+        let other = ES256KeyPair::generate().to_pem().unwrap();
+        let presentation = verified.select(["/given_name"]).unwrap();
+        assert!(matches!(
+            presentation.try_bind(JwsAlgorithm::P256, &other, "aud", "nonce", 1_700_000_000),
+            Err(SdjError::InvalidKeyBinding(_))
+        ));
+    }
+}
@@ -1,77 +1,78 @@
-use crate::core::disclosure::Disclosure;
-use crate::core::json_pointer::path::JsonPointerPath;
-use crate::core::json_pointer::JsonPointer;
-use crate::error::{SdjError, SdjResult};
-use crate::prelude::{JwsAlgorithm, SDJwt};
-use serde_json::json;
+use crate::error::SdjResult;
+use crate::prelude::{ClaimsPath, JwsAlgorithm, SDJwt};
+
+pub mod dcql;
+pub mod inspection;
+pub mod key_binding;
+pub mod oid4vci;
+pub mod oid4vp;
+pub mod presentation;
+pub mod presentation_exchange;
+pub mod preview;
+pub mod selection;
+pub mod store;
+pub mod transport;
+pub mod verified;
 
 pub struct Holder;
 
 impl crate::ThirdParty for Holder {}
 
 impl Holder {
-    // TODO: highly inefficient implementation ! To improve
-    pub fn select(
+    /// Verifies the Issuer signature once so that presentations can then be derived from the
+    /// returned [verified::VerifiedSDJwt] without verifying it again
+    pub fn verify(sd_jwt: SDJwt, alg: JwsAlgorithm, issuer_verifying_key: &str) -> SdjResult<verified::VerifiedSDJwt> {
+        verified::VerifiedSDJwt::try_new(sd_jwt, alg, issuer_verifying_key)
+    }
+
+    /// Lists everything the credential contains, along with what depends on what
+    pub fn inspect(verified: &verified::VerifiedSDJwt) -> inspection::Inspection {
+        inspection::Inspection::new(verified)
+    }
+
+    /// Computes exactly what a Verifier will learn from presenting the claims picked by the
+    /// [selection::Selection], without signing anything
+    pub fn preview(
+        verified: &verified::VerifiedSDJwt,
+        selection: &selection::Selection,
+    ) -> SdjResult<preview::PresentationPreview> {
+        verified.select_claims(selection).try_preview()
+    }
+
+    /// Keeps only the disclosures at those Json pointers in the Issuer payload.
+    /// See [verified::VerifiedSDJwt::select]
+    pub fn select<P: AsRef<str>>(
         sd_jwt: &str,
-        paths: &[&'static str],
+        paths: &[P],
         alg: JwsAlgorithm,
         issuer_verifying_key: &str,
     ) -> SdjResult<SDJwt> {
-        let mut sd_jwt = sd_jwt.parse::<SDJwt>()?;
-        let paths = paths
-            .iter()
-            .map(|&p| JsonPointerPath::try_from(p))
-            .collect::<SdjResult<Vec<_>>>()?;
-
-        let payload = sd_jwt.jws.try_read_payload(alg, issuer_verifying_key)?;
-        let payload = json!(payload);
-
-        let mut selected_disclosures = vec![];
-
-        for path in paths {
-            let disclosure = if let Some(key) = path.object_key() {
-                let disclosure_hashes = payload
-                    .try_find_disclosure(&path)?
-                    .as_array()
-                    .ok_or(SdjError::ImplementationError)?
-                    .iter()
-                    .map(|v| v.as_str().ok_or(SdjError::InvalidDisclosure))
-                    .collect::<SdjResult<Vec<_>>>()?;
-
-                let disclosure = sd_jwt
-                    .disclosures
-                    .iter()
-                    .find(|d| matches!(d, Disclosure::Object { name, .. } if name == key))
-                    .ok_or(SdjError::UnknownDisclosure)?;
-
-                let hash = disclosure.hash()?;
-                let is_included = disclosure_hashes.contains(&hash.as_str());
-
-                if is_included {
-                    disclosure
-                } else {
-                    return Err(SdjError::UnknownDisclosure);
-                }
-            } else {
-                let disclosure_hash = payload
-                    .try_find_disclosure(&path)?
-                    .as_str()
-                    .ok_or(SdjError::ImplementationError)?;
-
-                sd_jwt
-                    .disclosures
-                    .iter()
-                    .find(|d| match d {
-                        Disclosure::Array { .. } => d.hash().unwrap().as_str() == disclosure_hash,
-                        _ => false,
-                    })
-                    .ok_or(SdjError::UnknownDisclosure)?
-            };
-            selected_disclosures.push(disclosure.clone());
-        }
+        let verified = Self::verify(sd_jwt.parse()?, alg, issuer_verifying_key)?;
+        let presentation = verified.select(paths)?;
+        Ok(presentation.to_sd_jwt())
+    }
 
-        sd_jwt.disclosures.retain(|d| selected_disclosures.contains(d));
+    /// Keeps only the disclosures required for revealing the claims at those claims paths.
+    /// See [verified::VerifiedSDJwt::select_paths]
+    pub fn select_paths(
+        sd_jwt: &str,
+        paths: &[ClaimsPath],
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+    ) -> SdjResult<SDJwt> {
+        let verified = Self::verify(sd_jwt.parse()?, alg, issuer_verifying_key)?;
+        Ok(verified.select_paths(paths)?.to_sd_jwt())
+    }
 
-        Ok(sd_jwt)
+    /// Keeps only the disclosures required for revealing the claims picked by the [selection::Selection],
+    /// including the parent disclosures they are nested in.
+    pub fn select_claims(
+        sd_jwt: &str,
+        selection: &selection::Selection,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+    ) -> SdjResult<SDJwt> {
+        let verified = Self::verify(sd_jwt.parse()?, alg, issuer_verifying_key)?;
+        Ok(verified.select_claims(selection).to_sd_jwt())
     }
 }
@@ -0,0 +1,439 @@
+use crate::{
+    core::{
+        dcql::CredentialQuery,
+        jwk::Jwk,
+        jwt::JwtHeader,
+        oid4vci::{
+            error::Oid4vciError, CredentialErrorResponse, CredentialIssuerMetadata, CredentialOffer, CredentialRequest,
+            CredentialResponse, NonceResponse, ProofClaims, Proofs, CREDENTIAL_OFFER_SCHEME, PROOF_JWT_TYP,
+        },
+        url::try_parse_query,
+    },
+    error::{SdjError, SdjResult},
+    holder::transport::{HttpRequest, HttpResponse, Transport},
+    prelude::{JwsAlgorithm, SDJwt},
+};
+use serde_json::Value;
+
+/// The Wallet side of [OpenID4VCI](https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html).
+/// Obtaining the access token is up to the caller.
+pub struct WalletClient<T: Transport> {
+    transport: T,
+}
+
+/// Path of the Credential Issuer metadata, appended to the Credential Issuer identifier
+pub const CREDENTIAL_ISSUER_METADATA_PATH: &str = "/.well-known/openid-credential-issuer";
+
+impl<T: Transport> WalletClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    /// Reads a `openid-credential-offer://` URI, fetching the offer when passed by reference
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-sending-credential-offer-by-
+    pub fn try_resolve_offer(&self, uri: &str) -> SdjResult<CredentialOffer> {
+        let invalid = |reason: &str| Oid4vciError::InvalidCredentialOffer(reason.to_string());
+        if !uri.starts_with(CREDENTIAL_OFFER_SCHEME) {
+            return Err(invalid("unexpected scheme").into());
+        }
+        let parameters = try_parse_query(uri)?;
+        let parameter = |name: &str| parameters.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+        let offer = match (parameter("credential_offer"), parameter("credential_offer_uri")) {
+            (Some(offer), None) => serde_json::from_str(offer).map_err(|_| invalid("invalid Json"))?,
+            (None, Some(offer_uri)) => self.try_get_json(offer_uri)?,
+            _ => return Err(invalid("expected either credential_offer or credential_offer_uri").into()),
+        };
+        Ok(offer)
+    }
+
+    /// Fetches the metadata and checks it is the one of the expected Credential Issuer
+    pub fn try_fetch_metadata(&self, credential_issuer: &str) -> SdjResult<CredentialIssuerMetadata> {
+        let url = format!(
+            "{}{CREDENTIAL_ISSUER_METADATA_PATH}",
+            credential_issuer.trim_end_matches('/')
+        );
+        let metadata: CredentialIssuerMetadata = self.try_get_json(&url)?;
+        if metadata.credential_issuer != credential_issuer {
+            return Err(Oid4vciError::InvalidMetadata("unexpected credential_issuer".to_string()).into());
+        }
+        Ok(metadata)
+    }
+
+    /// Fetches a fresh `c_nonce`, when the Credential Issuer requires one, with a POST without
+    /// any body
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-nonce-request
+    pub fn try_fetch_nonce(&self, metadata: &CredentialIssuerMetadata) -> SdjResult<Option<String>> {
+        let Some(nonce_endpoint) = &metadata.nonce_endpoint else {
+            return Ok(None);
+        };
+        let request = HttpRequest::post(nonce_endpoint);
+        let response: NonceResponse = self.try_send_json(request)?;
+        Ok(Some(response.c_nonce))
+    }
+
+    /// Sends a credential request as is
+    pub fn try_request_credential(
+        &self,
+        metadata: &CredentialIssuerMetadata,
+        request: &CredentialRequest,
+        access_token: &str,
+    ) -> SdjResult<CredentialResponse> {
+        let request = HttpRequest::try_post_json(&metadata.credential_endpoint, request)?
+            .with_header("Authorization", format!("Bearer {access_token}"));
+        self.try_send_json(request)
+    }
+
+    /// Requests one credential per holder key, proving the possession of each of them, then
+    /// checks the issued credentials against the metadata. Signatures are not verified since the
+    /// Issuer key is not in the metadata, see [crate::prelude::Holder::verify] for that.
+    pub fn try_obtain(
+        &self,
+        metadata: &CredentialIssuerMetadata,
+        credential_configuration_id: &str,
+        access_token: &str,
+        holder_keys: &[(JwsAlgorithm, &str)],
+        now: u64,
+    ) -> SdjResult<Vec<SDJwt>> {
+        let c_nonce = self.try_fetch_nonce(metadata)?;
+        let proofs = holder_keys
+            .iter()
+            .map(|(alg, key_pair)| {
+                try_build_proof(&metadata.credential_issuer, c_nonce.as_deref(), *alg, key_pair, now)
+            })
+            .collect::<SdjResult<Vec<_>>>()?;
+        let request = CredentialRequest {
+            credential_configuration_id: Some(credential_configuration_id.to_string()),
+            proofs: Some(Proofs { jwt: proofs }),
+            ..Default::default()
+        };
+        let response = self.try_request_credential(metadata, &request, access_token)?;
+
+        let holder_jwks = holder_keys
+            .iter()
+            .map(|(alg, key_pair)| Jwk::try_from_key_pair_pem(*alg, key_pair))
+            .collect::<SdjResult<Vec<_>>>()?;
+        try_read_credentials(metadata, credential_configuration_id, &response, &holder_jwks)
+    }
+
+    fn try_get_json<R: serde::de::DeserializeOwned>(&self, url: &str) -> SdjResult<R> {
+        self.try_send_json(HttpRequest::get(url))
+    }
+
+    fn try_send_json<R: serde::de::DeserializeOwned>(&self, request: HttpRequest) -> SdjResult<R> {
+        let response: HttpResponse = self.transport.send(request)?;
+        if !response.is_success() {
+            return Err(
+                match serde_json::from_slice::<CredentialErrorResponse>(&response.body) {
+                    Ok(error) => Oid4vciError::ErrorResponse(error).into(),
+                    Err(_) => SdjError::HttpError(response.status),
+                },
+            );
+        }
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+}
+
+/// Builds a `openid4vci-proof+jwt` key proof, with the holder key in the header
+///
+/// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-jwt-proof-type
+pub fn try_build_proof(
+    credential_issuer: &str,
+    c_nonce: Option<&str>,
+    alg: JwsAlgorithm,
+    key_pair: &str,
+    now: u64,
+) -> SdjResult<String> {
+    let header = JwtHeader::new(alg, PROOF_JWT_TYP).with_jwk(Jwk::try_from_key_pair_pem(alg, key_pair)?);
+    let claims = ProofClaims {
+        iss: None,
+        aud: credential_issuer.to_string(),
+        iat: now,
+        nonce: c_nonce.map(str::to_string),
+    };
+    header.try_sign(&claims, key_pair)
+}
+
+/// Reads the credentials of a credential response, single or batch, and checks they match the
+/// Credential Issuer metadata and, when provided, are bound to the holder keys (in order)
+pub fn try_read_credentials(
+    metadata: &CredentialIssuerMetadata,
+    credential_configuration_id: &str,
+    response: &CredentialResponse,
+    holder_jwks: &[Jwk],
+) -> SdjResult<Vec<SDJwt>> {
+    let invalid = |reason: &str| SdjError::from(Oid4vciError::InvalidCredential(reason.to_string()));
+
+    let configuration = metadata
+        .credential_configurations_supported
+        .get(credential_configuration_id)
+        .ok_or_else(|| Oid4vciError::UnknownCredentialConfiguration(credential_configuration_id.to_string()))?;
+    if !CredentialQuery::SD_JWT_FORMATS.contains(&configuration.format.as_str()) {
+        return Err(invalid("not a SD-JWT"));
+    }
+
+    let credentials = match (&response.credentials, &response.credential) {
+        (Some(batch), _) => batch.iter().map(|c| c.credential.as_str()).collect(),
+        (None, Some(single)) => vec![single.as_str()],
+        (None, None) => return Err(invalid("no credential, deferred issuance is not supported")),
+    };
+    if !holder_jwks.is_empty() && credentials.len() != holder_jwks.len() {
+        return Err(invalid("not one credential per holder key"));
+    }
+
+    credentials
+        .into_iter()
+        .enumerate()
+        .map(|(i, credential)| {
+            let sd_jwt = credential.parse::<SDJwt>()?;
+            let payload = sd_jwt.jws.try_read_unverified_payload()?;
+            if payload.get("iss").and_then(Value::as_str) != Some(&metadata.credential_issuer) {
+                return Err(invalid("unexpected iss"));
+            }
+            if let Some(vct) = &configuration.vct {
+                if payload.get("vct").and_then(Value::as_str) != Some(vct) {
+                    return Err(invalid("unexpected vct"));
+                }
+            }
+            if let Some(jwk) = holder_jwks.get(i) {
+                let cnf = payload.pointer("/cnf/jwk").cloned().map(serde_json::from_value::<Jwk>);
+                if !matches!(cnf, Some(Ok(cnf)) if &cnf == jwk) {
+                    return Err(invalid("not bound to the holder key"));
+                }
+            }
+            Ok(sd_jwt)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        holder::{
+            store::{memory::InMemoryCredentialStore, CredentialStore, StoredCredential},
+            transport::HttpMethod,
+        },
+        prelude::{
+            CredentialConfiguration, CredentialConfigurationSupported, CredentialIssuer, Holder, Issuer, IssuerOptions,
+            ProofTypeSupported,
+        },
+    };
+    use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
+    use serde_json::json;
+    use std::{cell::RefCell, collections::BTreeMap};
+
+    const NOW: u64 = 1_700_000_000;
+    const ISSUER: &str = "https://issuer.example.com";
+    const ACCESS_TOKEN: &str = "czZCaGRSa3F0MzpnWDFmQmF0M2JW";
+
+    /// A Credential Issuer answering in memory
+    pub struct MockIssuer {
+        pub credential_issuer: RefCell<CredentialIssuer>,
+        pub offer: Value,
+    }
+
+    impl MockIssuer {
+        pub fn new() -> Self {
+            let metadata = CredentialConfigurationSupported {
+                format: "dc+sd-jwt".to_string(),
+                vct: Some("https://credentials.example.com/identity_credential".to_string()),
+                cryptographic_binding_methods_supported: Some(vec!["jwk".to_string()]),
+                proof_types_supported: Some(BTreeMap::from([(
+                    "jwt".to_string(),
+                    ProofTypeSupported {
+                        proof_signing_alg_values_supported: vec!["ES256".to_string(), "EdDSA".to_string()],
+                    },
+                )])),
+                ..Default::default()
+            };
+            let configuration = CredentialConfiguration {
+                metadata,
+                decisions: vec!["/given_name".to_string(), "/family_name".to_string()],
+                options: IssuerOptions::default(),
+            };
+            let credential_issuer =
+                CredentialIssuer::new(Issuer::try_new().unwrap(), ISSUER, format!("{ISSUER}/credential"))
+                    .with_nonce_endpoint(format!("{ISSUER}/nonce"))
+                    .with_configuration("pid", configuration);
+            Self {
+                credential_issuer: RefCell::new(credential_issuer),
+                offer: json!({"credential_issuer": ISSUER, "credential_configuration_ids": ["pid"]}),
+            }
+        }
+
+        fn respond(&self, request: &HttpRequest) -> SdjResult<(u16, Value)> {
+            let mut credential_issuer = self.credential_issuer.borrow_mut();
+            Ok(match (request.method, request.url.strip_prefix(ISSUER)) {
+                (HttpMethod::Get, Some(CREDENTIAL_ISSUER_METADATA_PATH)) => {
+                    (200, serde_json::to_value(credential_issuer.metadata())?)
+                }
+                (HttpMethod::Get, Some("/offer")) => (200, self.offer.clone()),
+                (HttpMethod::Post, Some("/nonce"))
+                    if request.body.is_some() || request.header("content-type").is_some() =>
+                {
+                    (400, json!({}))
+                }
+                (HttpMethod::Post, Some("/nonce")) => {
+                    (200, serde_json::to_value(credential_issuer.try_issue_nonce(NOW)?)?)
+                }
+                (HttpMethod::Post, Some("/credential")) => {
+                    if request.header("authorization") != Some(&format!("Bearer {ACCESS_TOKEN}")) {
+                        return Ok((401, json!({})));
+                    }
+                    let body: CredentialRequest = serde_json::from_slice(request.body.as_deref().unwrap_or_default())?;
+                    let claims = json!({"given_name": "John", "family_name": "Doe"});
+                    match credential_issuer.try_issue(&body, claims, NOW) {
+                        Ok(response) => (200, serde_json::to_value(response)?),
+                        Err(SdjError::Oid4vciError(e)) => (400, serde_json::to_value(e.to_error_response())?),
+                        Err(e) => return Err(e),
+                    }
+                }
+                _ => (404, json!({})),
+            })
+        }
+    }
+
+    impl Transport for &MockIssuer {
+        fn send(&self, request: HttpRequest) -> SdjResult<HttpResponse> {
+            let (status, body) = self.respond(&request)?;
+            Ok(HttpResponse {
+                status,
+                headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+                body: serde_json::to_vec(&body)?,
+            })
+        }
+    }
+
+    #[test]
+    fn should_resolve_offers() {
+        let issuer = MockIssuer::new();
+        let client = WalletClient::new(&issuer);
+
+        let by_value = format!(
+            "{CREDENTIAL_OFFER_SCHEME}?credential_offer=%7B%22credential_issuer%22%3A%22https%3A%2F%2Fissuer.example.com%22%2C%22credential_configuration_ids%22%3A%5B%22pid%22%5D%7D"
+        );
+        let offer = client.try_resolve_offer(&by_value).unwrap();
+        assert_eq!(offer.credential_issuer, ISSUER);
+        assert_eq!(offer.credential_configuration_ids, vec!["pid"]);
+
+        let by_reference =
+            format!("{CREDENTIAL_OFFER_SCHEME}?credential_offer_uri=https%3A%2F%2Fissuer.example.com%2Foffer");
+        assert_eq!(client.try_resolve_offer(&by_reference).unwrap(), offer);
+
+        for invalid in ["https://example.com", CREDENTIAL_OFFER_SCHEME] {
+            assert!(matches!(
+                client.try_resolve_offer(invalid),
+                Err(SdjError::Oid4vciError(Oid4vciError::InvalidCredentialOffer(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn should_obtain_credentials_from_offer() {
+        let issuer = MockIssuer::new();
+        let client = WalletClient::new(&issuer);
+        let offer = client
+            .try_resolve_offer(&format!(
+                "{CREDENTIAL_OFFER_SCHEME}?credential_offer_uri=https%3A%2F%2Fissuer.example.com%2Foffer"
+            ))
+            .unwrap();
+        let metadata = client.try_fetch_metadata(&offer.credential_issuer).unwrap();
+
+        let es256 = ES256KeyPair::generate().to_pem().unwrap();
+        let ed25519 = Ed25519KeyPair::generate().to_pem();
+        let keys = [
+            (JwsAlgorithm::P256, es256.as_str()),
+            (JwsAlgorithm::Ed25519, ed25519.as_str()),
+        ];
+        let sd_jwts = client
+            .try_obtain(
+                &metadata,
+                &offer.credential_configuration_ids[0],
+                ACCESS_TOKEN,
+                &keys,
+                NOW,
+            )
+            .unwrap();
+        assert_eq!(sd_jwts.len(), 2);
+
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.credential_issuer.borrow().issuer().get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let mut store = InMemoryCredentialStore::default();
+        for sd_jwt in sd_jwts {
+            let verified = Holder::verify(sd_jwt, JwsAlgorithm::Ed25519, &issuer_key).unwrap();
+            assert_eq!(verified.claims()["given_name"], json!("John"));
+            store
+                .save(StoredCredential::try_new(verified.into_sd_jwt()).unwrap())
+                .unwrap();
+        }
+        let stored = store.list().unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].metadata.issuer.as_deref(), Some(ISSUER));
+    }
+
+    #[test]
+    fn should_surface_issuer_errors() {
+        let issuer = MockIssuer::new();
+        let client = WalletClient::new(&issuer);
+        let metadata = client.try_fetch_metadata(ISSUER).unwrap();
+        let key_pair = ES256KeyPair::generate().to_pem().unwrap();
+
+        // proof without the c_nonce
+        let proof = try_build_proof(ISSUER, None, JwsAlgorithm::P256, &key_pair, NOW).unwrap();
+        let request = CredentialRequest {
+            credential_configuration_id: Some("pid".to_string()),
+            proofs: Some(Proofs { jwt: vec![proof] }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            client.try_request_credential(&metadata, &request, ACCESS_TOKEN),
+            Err(SdjError::Oid4vciError(Oid4vciError::ErrorResponse(e))) if e.error == "invalid_nonce"
+        ));
+        assert!(matches!(
+            client.try_request_credential(&metadata, &request, "wrong"),
+            Err(SdjError::HttpError(401))
+        ));
+        assert!(matches!(
+            client.try_fetch_metadata("https://issuer.example.com/other"),
+            Err(SdjError::HttpError(404))
+        ));
+    }
+
+    #[test]
+    fn should_check_credentials_against_metadata() {
+        let issuer = MockIssuer::new();
+        let client = WalletClient::new(&issuer);
+        let mut metadata = client.try_fetch_metadata(ISSUER).unwrap();
+        let key_pair = ES256KeyPair::generate().to_pem().unwrap();
+        let keys = [(JwsAlgorithm::P256, key_pair.as_str())];
+        let sd_jwt = client
+            .try_obtain(&metadata, "pid", ACCESS_TOKEN, &keys, NOW)
+            .unwrap()
+            .remove(0);
+        let response = CredentialResponse {
+            credential: Some(sd_jwt.try_serialize().unwrap()),
+            ..Default::default()
+        };
+        let jwks = [Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &key_pair).unwrap()];
+        assert_eq!(
+            try_read_credentials(&metadata, "pid", &response, &jwks).unwrap().len(),
+            1
+        );
+
+        let other =
+            Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &ES256KeyPair::generate().to_pem().unwrap()).unwrap();
+        let invalid =
+            |r: SdjResult<Vec<SDJwt>>| matches!(r, Err(SdjError::Oid4vciError(Oid4vciError::InvalidCredential(_))));
+        assert!(invalid(try_read_credentials(&metadata, "pid", &response, &[other])));
+
+        metadata.credential_configurations_supported.get_mut("pid").unwrap().vct = Some("mdl".to_string());
+        assert!(invalid(try_read_credentials(&metadata, "pid", &response, &jwks)));
+
+        metadata.credential_issuer = "https://other.example.com".to_string();
+        assert!(invalid(try_read_credentials(&metadata, "pid", &response, &jwks)));
+    }
+}
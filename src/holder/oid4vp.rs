@@ -0,0 +1,658 @@
+#[cfg(feature = "jwe")]
+use crate::core::jwe::{try_encrypt, ContentEncryption};
+use crate::{
+    core::{
+        jwk::Jwk,
+        jwt::{try_decode_unverified, try_verify},
+        oid4vp::{
+            error::Oid4vpError, AuthorizationRequest, AuthorizationResponse, ClientIdPrefix, ResponseMode,
+            VerifierAttestationClaims, REQUEST_OBJECT_TYP, VERIFIER_ATTESTATION_TYP,
+        },
+        presentation_exchange::PresentationSubmission,
+        url::{host, try_parse_query},
+        x509::Certificate,
+    },
+    error::{SdjError, SdjResult},
+    holder::{
+        presentation::Presentation,
+        transport::{HttpRequest, Transport},
+    },
+    prelude::JwsAlgorithm,
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// What the Wallet trusts for authenticating Verifiers
+pub trait VerifierTrust {
+    /// Whether the DER encoded certificate chain of a `x509_san_dns` request, leaf first, chains
+    /// up to a trust anchor
+    fn is_trusted_chain(&self, chain: &[Vec<u8>]) -> bool;
+
+    /// The algorithm & PEM encoded verifying key of a trusted issuer of Verifier Attestations
+    fn attestation_issuer_key(&self, iss: &str) -> Option<(JwsAlgorithm, String)>;
+}
+
+/// The Wallet side of [OpenID4VP](https://openid.net/specs/openid-4-verifiable-presentations-1_0.html):
+/// reads authorization requests then sends the presentations back
+pub struct PresentationClient<T: Transport> {
+    transport: T,
+}
+
+impl<T: Transport> PresentationClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    /// Reads an authorization request passed in a URI, either as parameters, as a request object
+    /// (`request`) or as a reference to one (`request_uri`), then authenticates the Verifier
+    /// according to the prefix of its `client_id`
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-client-identifier-prefix-an
+    pub fn try_resolve_request(
+        &self,
+        uri: &str,
+        trust: &impl VerifierTrust,
+        now: u64,
+    ) -> SdjResult<AuthorizationRequest> {
+        let parameters = try_parse_query(uri)?;
+        let parameter = |name: &str| parameters.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        let client_id = parameter("client_id").ok_or_else(|| invalid_request("missing client_id"))?;
+
+        let request_object = match (parameter("request"), parameter("request_uri")) {
+            (Some(request), None) => Some(request.to_string()),
+            (None, Some(request_uri)) => Some(self.try_fetch_request_object(request_uri)?),
+            (None, None) => None,
+            _ => return Err(invalid_request("both request and request_uri").into()),
+        };
+        let request = match request_object {
+            Some(request_object) => try_authenticate(&request_object, trust, now)?,
+            None => try_read_unsigned(&parameters)?,
+        };
+        if request.client_id != client_id {
+            return Err(Oid4vpError::InvalidRequestObject("client_id mismatch".to_string()).into());
+        }
+        request.try_validate()?;
+        Ok(request)
+    }
+
+    /// Sends the response with the `direct_post` or, with the `jwe` feature, the `direct_post.jwt`
+    /// response mode. Returns the URI the Verifier might want the End-User to be redirected to.
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-response-mode-direct_postjw
+    pub fn try_send_response(
+        &self,
+        request: &AuthorizationRequest,
+        response: &AuthorizationResponse,
+    ) -> SdjResult<Option<String>> {
+        let response_uri = request
+            .response_uri
+            .as_deref()
+            .ok_or_else(|| invalid_request("missing response_uri"))?;
+        let http_request = match request.response_mode {
+            Some(ResponseMode::DirectPost) => {
+                let vp_token = serde_json::to_string(&response.vp_token)?;
+                let submission = response
+                    .presentation_submission
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()?;
+                let mut form = vec![("vp_token", vp_token.as_str())];
+                form.extend(submission.as_deref().map(|s| ("presentation_submission", s)));
+                form.extend(response.state.as_deref().map(|s| ("state", s)));
+                HttpRequest::post_form(response_uri, form)
+            }
+            #[cfg(feature = "jwe")]
+            Some(ResponseMode::DirectPostJwt) => {
+                let jwe = try_encrypt_response(request, response)?;
+                HttpRequest::post_form(response_uri, [("response", jwe.as_str())])
+            }
+            #[cfg(not(feature = "jwe"))]
+            Some(ResponseMode::DirectPostJwt) => return Err(SdjError::FeatureRequired("direct_post.jwt", "jwe")),
+            mode => {
+                let mode = serde_json::to_value(mode)?;
+                return Err(Oid4vpError::UnsupportedResponseMode(mode.as_str().unwrap_or("none").to_string()).into());
+            }
+        };
+        let response = self.transport.send(http_request)?;
+        if !response.is_success() {
+            return Err(SdjError::HttpError(response.status));
+        }
+        let redirect_uri = serde_json::from_slice::<Value>(&response.body)
+            .ok()
+            .and_then(|body| body.get("redirect_uri")?.as_str().map(str::to_string));
+        Ok(redirect_uri)
+    }
+
+    fn try_fetch_request_object(&self, request_uri: &str) -> SdjResult<String> {
+        let request = HttpRequest::get(request_uri).with_header("Accept", "application/oauth-authz-req+jwt");
+        let response = self.transport.send(request)?;
+        if !response.is_success() {
+            return Err(SdjError::HttpError(response.status));
+        }
+        String::from_utf8(response.body).map_err(|_| Oid4vpError::InvalidRequestObject("not a JWT".to_string()).into())
+    }
+}
+
+impl AuthorizationRequest {
+    /// Answers a DCQL query with the presentations built by [crate::prelude::DcqlQuery::try_present],
+    /// appending to each of them a KB-JWT signed with the matching holder key
+    pub fn try_respond_dcql(
+        &self,
+        presentations: BTreeMap<String, Vec<Presentation>>,
+        holder_keys: &[(JwsAlgorithm, &str)],
+        now: u64,
+    ) -> SdjResult<AuthorizationResponse> {
+        let mut vp_token = Map::new();
+        for (query_id, presentations) in presentations {
+            let presentations = presentations
+                .into_iter()
+                .map(|p| self.try_bind(&query_id, p, holder_keys, now))
+                .collect::<SdjResult<Vec<_>>>()?;
+            vp_token.insert(query_id, presentations.into());
+        }
+        Ok(AuthorizationResponse {
+            vp_token: vp_token.into(),
+            presentation_submission: None,
+            state: self.state.clone(),
+        })
+    }
+
+    /// Answers a presentation definition with what
+    /// [crate::prelude::PresentationDefinition::try_present] built, appending to each
+    /// presentation a KB-JWT signed with the matching holder key
+    pub fn try_respond_presentation_exchange(
+        &self,
+        (presentations, submission): (Vec<Presentation>, PresentationSubmission),
+        holder_keys: &[(JwsAlgorithm, &str)],
+        now: u64,
+    ) -> SdjResult<AuthorizationResponse> {
+        let mut presentations = presentations
+            .into_iter()
+            .zip(&submission.descriptor_map)
+            .map(|(p, descriptor_map)| self.try_bind(&descriptor_map.id, p, holder_keys, now))
+            .collect::<SdjResult<Vec<_>>>()?;
+        let vp_token = match presentations.len() {
+            1 => presentations.remove(0).into(),
+            _ => presentations.into(),
+        };
+        Ok(AuthorizationResponse {
+            vp_token,
+            presentation_submission: Some(submission),
+            state: self.state.clone(),
+        })
+    }
+
+    /// Binds the presentation answering `credential_id` to the `client_id`, the `nonce` & the
+    /// transaction data with the key its credential is bound to. Credentials without `cnf` are
+    /// presented as is.
+    fn try_bind(
+        &self,
+        credential_id: &str,
+        presentation: Presentation,
+        holder_keys: &[(JwsAlgorithm, &str)],
+        now: u64,
+    ) -> SdjResult<String> {
+        let transaction_data = self.try_transaction_data(credential_id)?;
+        let payload = presentation.jws.try_read_unverified_payload()?;
+        let Some(cnf) = payload.pointer("/cnf/jwk") else {
+            if !transaction_data.is_empty() {
+                return Err(SdjError::InvalidKeyBinding(
+                    "transaction data can't be bound to a credential without cnf".to_string(),
+                ));
+            }
+            return presentation.try_serialize();
+        };
+        let cnf = serde_json::from_value::<Jwk>(cnf.clone()).map_err(|_| SdjError::InvalidJwk)?;
+        let (alg, key_pair) = holder_keys
+            .iter()
+            .find(|(alg, key_pair)| Jwk::try_from_key_pair_pem(*alg, key_pair).ok().as_ref() == Some(&cnf))
+            .ok_or_else(|| SdjError::InvalidKeyBinding("no holder key for the credential".to_string()))?;
+        let mut key_binding = presentation.key_binding(&self.client_id, &self.nonce, now);
+        if !transaction_data.is_empty() {
+            key_binding = key_binding.try_with_transaction_data(&transaction_data)?;
+        }
+        key_binding.try_sign(*alg, key_pair)?.try_serialize()
+    }
+}
+
+fn invalid_request(reason: &str) -> Oid4vpError {
+    Oid4vpError::InvalidRequest(reason.to_string())
+}
+
+/// Reads a request passed as URI parameters, only allowed when the Verifier is its `redirect_uri`
+fn try_read_unsigned(parameters: &[(String, String)]) -> SdjResult<AuthorizationRequest> {
+    let mut request = Map::new();
+    for (name, value) in parameters {
+        let value = if AuthorizationRequest::JSON_PARAMETERS.contains(&name.as_str()) {
+            serde_json::from_str(value).map_err(|_| invalid_request(name))?
+        } else {
+            Value::String(value.clone())
+        };
+        request.insert(name.clone(), value);
+    }
+    let request: AuthorizationRequest =
+        serde_json::from_value(request.into()).map_err(|e| invalid_request(&e.to_string()))?;
+
+    match ClientIdPrefix::try_split(&request.client_id)? {
+        (ClientIdPrefix::RedirectUri, uri) => {
+            let response_uri = request.response_uri.as_ref().or(request.redirect_uri.as_ref());
+            if response_uri.map(String::as_str) != Some(uri) {
+                return Err(Oid4vpError::InvalidClient("responses have to go to the client_id".to_string()).into());
+            }
+        }
+        (prefix, _) => {
+            let reason = format!("requests have to be signed with {}", prefix.as_str());
+            return Err(invalid_request(&reason).into());
+        }
+    }
+    Ok(request)
+}
+
+/// Verifies the signature of a request object with a key bound to the `client_id`
+fn try_authenticate(request_object: &str, trust: &impl VerifierTrust, now: u64) -> SdjResult<AuthorizationRequest> {
+    let invalid_client = |reason: &str| SdjError::from(Oid4vpError::InvalidClient(reason.to_string()));
+    let invalid_object = |reason: &str| SdjError::from(Oid4vpError::InvalidRequestObject(reason.to_string()));
+
+    let (header, claims) = try_decode_unverified(request_object)?;
+    if header.typ.as_deref() != Some(REQUEST_OBJECT_TYP) {
+        return Err(invalid_object("unexpected typ"));
+    }
+    let request: AuthorizationRequest = serde_json::from_value(claims).map_err(|e| invalid_object(&e.to_string()))?;
+    let response_uri = request.response_uri.as_ref().or(request.redirect_uri.as_ref());
+
+    let verifying_key = match ClientIdPrefix::try_split(&request.client_id)? {
+        (ClientIdPrefix::RedirectUri, _) => return Err(invalid_object("requests cannot be signed with redirect_uri")),
+        (ClientIdPrefix::X509SanDns, dns_name) => {
+            let chain = header
+                .other
+                .get("x5c")
+                .and_then(Value::as_array)
+                .ok_or_else(|| invalid_object("missing x5c"))?
+                .iter()
+                .map(|c| c.as_str().ok_or_else(|| invalid_object("invalid x5c")))
+                .map(|c| Ok(base64_simd::STANDARD.decode_to_vec(c?)?))
+                .collect::<SdjResult<Vec<_>>>()?;
+            let leaf = Certificate::try_from_der(chain.first().ok_or_else(|| invalid_object("empty x5c"))?)?;
+            if !trust.is_trusted_chain(&chain) {
+                return Err(invalid_client("untrusted certificate chain"));
+            }
+            if !leaf.dns_names.iter().any(|n| n == dns_name) {
+                return Err(invalid_client("client_id is not a DNS name of the certificate"));
+            }
+            if response_uri.and_then(|uri| host(uri)) != Some(dns_name) {
+                return Err(invalid_client("responses have to go to the client_id"));
+            }
+            leaf.try_verifying_key(header.try_alg()?)?
+        }
+        (ClientIdPrefix::VerifierAttestation, sub) => {
+            let attestation = header
+                .other
+                .get("jwt")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid_object("missing Verifier Attestation"))?;
+            let (attestation_header, attestation_claims) = try_decode_unverified(attestation)?;
+            let iss = attestation_claims
+                .get("iss")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let (alg, issuer_key) = trust
+                .attestation_issuer_key(iss)
+                .ok_or_else(|| invalid_client("untrusted Verifier Attestation issuer"))?;
+            if attestation_header.typ.as_deref() != Some(VERIFIER_ATTESTATION_TYP)
+                || attestation_header.try_alg()? != alg
+            {
+                return Err(invalid_client("invalid Verifier Attestation"));
+            }
+            try_verify(attestation, &issuer_key).map_err(|_| invalid_client("invalid Verifier Attestation"))?;
+            let attestation: VerifierAttestationClaims = serde_json::from_value(attestation_claims)?;
+            if attestation.sub != sub || attestation.exp <= now {
+                return Err(invalid_client("Verifier Attestation not valid for the client_id"));
+            }
+            if let Some(redirect_uris) = &attestation.redirect_uris {
+                if !response_uri.is_some_and(|uri| redirect_uris.contains(uri)) {
+                    return Err(invalid_client("responses have to go to an attested redirect_uri"));
+                }
+            }
+            if attestation.cnf.jwk.try_alg()? != header.try_alg()? {
+                return Err(invalid_object("not signed with the attested key"));
+            }
+            attestation.cnf.jwk.try_to_public_pem()?
+        }
+    };
+    try_verify(request_object, &verifying_key).map_err(|_| invalid_object("invalid signature"))?;
+    Ok(request)
+}
+
+/// Encrypts the response to the key of the Verifier with the first content encryption algorithm
+/// both support
+#[cfg(feature = "jwe")]
+fn try_encrypt_response(request: &AuthorizationRequest, response: &AuthorizationResponse) -> SdjResult<String> {
+    let recipient = request
+        .encryption_key()
+        .ok_or_else(|| invalid_request("no key to encrypt the response to"))?;
+    let supported = request
+        .client_metadata
+        .as_ref()
+        .and_then(|m| m.encrypted_response_enc_values_supported.clone())
+        .unwrap_or_else(|| vec![ContentEncryption::default().as_str().to_string()]);
+    let enc = supported
+        .iter()
+        .find_map(|enc| serde_json::from_value::<ContentEncryption>(enc.as_str().into()).ok())
+        .ok_or_else(|| invalid_request("unsupported encrypted_response_enc_values_supported"))?;
+    try_encrypt(&serde_json::to_vec(response)?, recipient, enc)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        core::{
+            jwe::try_decrypt,
+            jwk::JwkSet,
+            jwt::JwtHeader,
+            key_binding::KeyBindingClaims,
+            oid4vp::{ClientMetadata, Confirmation},
+            url::encode_form,
+            x509::tests::{CERTIFICATE, KEY_PAIR},
+        },
+        holder::transport::{HttpMethod, HttpResponse},
+        prelude::{DcqlQuery, Issuer, IssuerOptions, SDJwt, VerifiedSDJwt},
+    };
+    use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    const NOW: u64 = 1_700_000_000;
+    const RESPONSE_URI: &str = "https://verifier.example.com/post";
+    const REQUEST_URI: &str = "https://verifier.example.com/request/1234";
+
+    struct Trust {
+        attestation_issuer: (JwsAlgorithm, String),
+    }
+
+    impl VerifierTrust for Trust {
+        fn is_trusted_chain(&self, chain: &[Vec<u8>]) -> bool {
+            chain.first() == base64_simd::STANDARD.decode_to_vec(CERTIFICATE).ok().as_ref()
+        }
+
+        fn attestation_issuer_key(&self, iss: &str) -> Option<(JwsAlgorithm, String)> {
+            (iss == "https://attestation.example.com").then(|| self.attestation_issuer.clone())
+        }
+    }
+
+    /// Serves a request object then records the posted responses
+    #[derive(Default)]
+    struct MockVerifier {
+        request_object: String,
+        posted: RefCell<Vec<Vec<(String, String)>>>,
+    }
+
+    impl Transport for &MockVerifier {
+        fn send(&self, request: HttpRequest) -> SdjResult<HttpResponse> {
+            let (status, body) = match (request.method, request.url.as_str()) {
+                (HttpMethod::Get, REQUEST_URI) => (200, self.request_object.clone().into_bytes()),
+                (HttpMethod::Post, RESPONSE_URI) => {
+                    let body = String::from_utf8(request.body.unwrap_or_default()).unwrap();
+                    self.posted.borrow_mut().push(try_parse_query(&format!("?{body}"))?);
+                    (200, br#"{"redirect_uri":"https://verifier.example.com/done"}"#.to_vec())
+                }
+                _ => (404, vec![]),
+            };
+            Ok(HttpResponse {
+                status,
+                headers: vec![],
+                body,
+            })
+        }
+    }
+
+    fn request(client_id: &str) -> AuthorizationRequest {
+        serde_json::from_value(json!({
+            "client_id": client_id,
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "response_uri": RESPONSE_URI,
+            "nonce": "n-0S6_WzA2Mj",
+            "state": "eyJhb",
+            "dcql_query": {"credentials": [{
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "claims": [{"path": ["given_name"]}]
+            }]}
+        }))
+        .unwrap()
+    }
+
+    fn x509_request_object(request: &AuthorizationRequest) -> String {
+        let mut header = JwtHeader::new(JwsAlgorithm::P256, REQUEST_OBJECT_TYP);
+        header.other.insert("x5c".to_string(), json!([CERTIFICATE]));
+        header.try_sign(request, KEY_PAIR).unwrap()
+    }
+
+    fn trust() -> Trust {
+        Trust {
+            attestation_issuer: (JwsAlgorithm::Ed25519, Ed25519KeyPair::generate().to_pem()),
+        }
+    }
+
+    fn credential(holder_key: &str) -> VerifiedSDJwt {
+        let options = IssuerOptions {
+            cnf: Some(Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, holder_key).unwrap()),
+            ..Default::default()
+        };
+        let input = json!({"given_name": "John", "family_name": "Doe"});
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt(input, &["/given_name", "/family_name"], options)
+            .unwrap();
+        VerifiedSDJwt::try_from_trusted(sd_jwt).unwrap()
+    }
+
+    #[test]
+    fn should_answer_request_by_reference() {
+        let verifier = MockVerifier {
+            request_object: x509_request_object(&request("x509_san_dns:verifier.example.com")),
+            ..Default::default()
+        };
+        let client = PresentationClient::new(&verifier);
+        let uri = format!(
+            "openid4vp://?{}",
+            encode_form([
+                ("client_id", "x509_san_dns:verifier.example.com"),
+                ("request_uri", REQUEST_URI)
+            ])
+        );
+        let request = client.try_resolve_request(&uri, &trust(), NOW).unwrap();
+
+        let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+        let credential = credential(&holder_key);
+        let query: &DcqlQuery = request.dcql_query.as_ref().unwrap();
+        let presentations = query.try_present(&[("pid", &credential)]).unwrap();
+        let response = request
+            .try_respond_dcql(presentations, &[(JwsAlgorithm::P256, &holder_key)], NOW)
+            .unwrap();
+        let redirect_uri = client.try_send_response(&request, &response).unwrap();
+        assert_eq!(redirect_uri.as_deref(), Some("https://verifier.example.com/done"));
+
+        let posted = verifier.posted.borrow();
+        let form: BTreeMap<_, _> = posted[0].iter().cloned().collect();
+        assert_eq!(form["state"], "eyJhb");
+        let vp_token: Value = serde_json::from_str(&form["vp_token"]).unwrap();
+        let presentation = vp_token["pid"][0].as_str().unwrap().parse::<SDJwt>().unwrap();
+        assert_eq!(presentation.disclosures.len(), 1);
+        let kb_jwt = presentation.key_binding.unwrap();
+        let holder_pem = Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key)
+            .unwrap()
+            .try_to_public_pem()
+            .unwrap();
+        let (_, claims) = try_verify(&kb_jwt, &holder_pem).unwrap();
+        let claims: KeyBindingClaims = serde_json::from_value(claims).unwrap();
+        assert_eq!(claims.aud, "x509_san_dns:verifier.example.com");
+        assert_eq!(claims.nonce, "n-0S6_WzA2Mj");
+    }
+
+    #[test]
+    fn should_authenticate_x509_san_dns() {
+        let verifier = MockVerifier::default();
+        let client = PresentationClient::new(&verifier);
+        let resolve = |request: &AuthorizationRequest, request_object: String| {
+            let uri = format!(
+                "openid4vp://?{}",
+                encode_form([("client_id", request.client_id.as_str()), ("request", &request_object)])
+            );
+            client.try_resolve_request(&uri, &trust(), NOW)
+        };
+        let valid = request("x509_san_dns:verifier.example.com");
+        assert!(resolve(&valid, x509_request_object(&valid)).is_ok());
+
+        // DNS name not in the certificate
+        let mut other = request("x509_san_dns:other.example.com");
+        other.response_uri = Some("https://other.example.com/post".to_string());
+        assert!(matches!(
+            resolve(&other, x509_request_object(&other)),
+            Err(SdjError::Oid4vpError(Oid4vpError::InvalidClient(_)))
+        ));
+
+        // response sent elsewhere
+        let mut elsewhere = request("x509_san_dns:verifier.example.com");
+        elsewhere.response_uri = Some("https://attacker.example.com/post".to_string());
+        assert!(matches!(
+            resolve(&elsewhere, x509_request_object(&elsewhere)),
+            Err(SdjError::Oid4vpError(Oid4vpError::InvalidClient(_)))
+        ));
+
+        // signed with another key
+        let mut header = JwtHeader::new(JwsAlgorithm::P256, REQUEST_OBJECT_TYP);
+        header.other.insert("x5c".to_string(), json!([CERTIFICATE]));
+        let other_key = ES256KeyPair::generate().to_pem().unwrap();
+        let forged = header.try_sign(&valid, &other_key).unwrap();
+        assert!(matches!(
+            resolve(&valid, forged),
+            Err(SdjError::Oid4vpError(Oid4vpError::InvalidRequestObject(_)))
+        ));
+
+        // unsigned
+        let dcql_query = serde_json::to_string(&valid.dcql_query).unwrap();
+        let uri = format!(
+            "openid4vp://?{}",
+            encode_form([
+                ("client_id", valid.client_id.as_str()),
+                ("response_type", "vp_token"),
+                ("response_mode", "direct_post"),
+                ("response_uri", RESPONSE_URI),
+                ("nonce", "n-0S6_WzA2Mj"),
+                ("dcql_query", &dcql_query),
+            ])
+        );
+        assert!(matches!(
+            client.try_resolve_request(&uri, &trust(), NOW),
+            Err(SdjError::Oid4vpError(Oid4vpError::InvalidRequest(_)))
+        ));
+    }
+
+    #[test]
+    fn should_authenticate_verifier_attestation() {
+        let trust = trust();
+        let verifier_key = ES256KeyPair::generate().to_pem().unwrap();
+        let attestation = |sub: &str, exp: u64| {
+            let claims = VerifierAttestationClaims {
+                iss: "https://attestation.example.com".to_string(),
+                sub: sub.to_string(),
+                exp,
+                cnf: Confirmation {
+                    jwk: Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &verifier_key).unwrap(),
+                },
+                redirect_uris: Some(vec![RESPONSE_URI.to_string()]),
+            };
+            JwtHeader::new(JwsAlgorithm::Ed25519, VERIFIER_ATTESTATION_TYP)
+                .try_sign(&claims, &trust.attestation_issuer.1)
+                .unwrap()
+        };
+        let verifier = MockVerifier::default();
+        let client = PresentationClient::new(&verifier);
+        let resolve = |attestation: String| {
+            let request = request("verifier_attestation:verifier.example.com");
+            let mut header = JwtHeader::new(JwsAlgorithm::P256, REQUEST_OBJECT_TYP);
+            header.other.insert("jwt".to_string(), attestation.into());
+            let request_object = header.try_sign(&request, &verifier_key).unwrap();
+            let uri = format!(
+                "openid4vp://?{}",
+                encode_form([("client_id", request.client_id.as_str()), ("request", &request_object)])
+            );
+            client.try_resolve_request(&uri, &trust, NOW)
+        };
+        assert!(resolve(attestation("verifier.example.com", NOW + 60)).is_ok());
+        for invalid in [
+            attestation("other.example.com", NOW + 60),
+            attestation("verifier.example.com", NOW - 60),
+        ] {
+            assert!(matches!(
+                resolve(invalid),
+                Err(SdjError::Oid4vpError(Oid4vpError::InvalidClient(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn should_read_unsigned_redirect_uri_request() {
+        let verifier = MockVerifier::default();
+        let client = PresentationClient::new(&verifier);
+        let client_id = format!("redirect_uri:{RESPONSE_URI}");
+        let uri = |response_uri: &str| {
+            format!(
+                "openid4vp://?{}",
+                encode_form([
+                    ("client_id", client_id.as_str()),
+                    ("response_type", "vp_token"),
+                    ("response_mode", "direct_post"),
+                    ("response_uri", response_uri),
+                    ("nonce", "n-0S6_WzA2Mj"),
+                    ("dcql_query", r#"{"credentials":[{"id":"pid","format":"dc+sd-jwt"}]}"#),
+                ])
+            )
+        };
+        let request = client.try_resolve_request(&uri(RESPONSE_URI), &trust(), NOW).unwrap();
+        assert_eq!(request.dcql_query.unwrap().credentials[0].id, "pid");
+        assert!(matches!(
+            client.try_resolve_request(&uri("https://attacker.example.com/post"), &trust(), NOW),
+            Err(SdjError::Oid4vpError(Oid4vpError::InvalidClient(_)))
+        ));
+    }
+
+    #[test]
+    fn should_encrypt_response() {
+        let verifier = MockVerifier::default();
+        let client = PresentationClient::new(&verifier);
+        let encryption_key = ES256KeyPair::generate().to_pem().unwrap();
+        let mut request = request("x509_san_dns:verifier.example.com");
+        request.response_mode = Some(ResponseMode::DirectPostJwt);
+        request.client_metadata = Some(ClientMetadata {
+            jwks: Some(JwkSet {
+                keys: vec![Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &encryption_key).unwrap()],
+            }),
+            encrypted_response_enc_values_supported: Some(vec!["A256GCM".to_string()]),
+            vp_formats_supported: None,
+        });
+
+        let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+        let credential = credential(&holder_key);
+        let presentations = request
+            .dcql_query
+            .as_ref()
+            .unwrap()
+            .try_present(&[("pid", &credential)])
+            .unwrap();
+        let response = request
+            .try_respond_dcql(presentations, &[(JwsAlgorithm::P256, &holder_key)], NOW)
+            .unwrap();
+        client.try_send_response(&request, &response).unwrap();
+
+        let posted = verifier.posted.borrow();
+        let (name, jwe) = &posted[0][0];
+        assert_eq!(name, "response");
+        let decrypted: AuthorizationResponse =
+            serde_json::from_slice(&try_decrypt(jwe, JwsAlgorithm::P256, &encryption_key).unwrap()).unwrap();
+        assert_eq!(decrypted.vp_token, response.vp_token);
+        assert_eq!(decrypted.state.as_deref(), Some("eyJhb"));
+    }
+}
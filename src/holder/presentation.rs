@@ -0,0 +1,30 @@
+use crate::{
+    core::{disclosure::Disclosure, jws::Jws},
+    error::SdjResult,
+    prelude::SDJwt,
+};
+
+/// The SD-JWT a Holder presents to a Verifier. Borrows from the credential it has been derived
+/// from, see [crate::prelude::VerifiedSDJwt].
+#[derive(Debug, Clone)]
+pub struct Presentation<'a> {
+    pub jws: &'a Jws,
+    /// The selected disclosures, in the order they appear in the credential
+    pub disclosures: Vec<&'a Disclosure>,
+    pub key_binding: Option<String>,
+}
+
+impl<'a> Presentation<'a> {
+    pub fn try_serialize(&self) -> SdjResult<String> {
+        SDJwt::try_serialize_parts(self.jws, self.disclosures.iter().copied(), self.key_binding.as_deref())
+    }
+
+    /// Clones everything into a standalone SD-JWT
+    pub fn to_sd_jwt(&self) -> SDJwt {
+        SDJwt {
+            jws: self.jws.clone(),
+            disclosures: self.disclosures.iter().map(|&d| d.clone()).collect(),
+            key_binding: self.key_binding.clone(),
+        }
+    }
+}
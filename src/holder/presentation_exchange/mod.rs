@@ -0,0 +1,376 @@
+use crate::{
+    core::{
+        presentation_exchange::{
+            error::{PresentationExchangeError, PresentationExchangeResult},
+            DescriptorMap, InputDescriptor, PresentationDefinition, PresentationSubmission,
+        },
+        processing::is_ancestor_or_self,
+    },
+    crypto::random::try_new_random_string,
+    error::SdjResult,
+    holder::{
+        presentation::Presentation,
+        selection::Selection,
+        store::{CredentialId, CredentialStore},
+        verified::VerifiedSDJwt,
+    },
+};
+
+/// A credential satisfying an input descriptor, along with the minimal disclosures for that
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorMatch {
+    pub descriptor_id: String,
+    pub credential_id: CredentialId,
+    /// Json pointers of the claims matched by the fields
+    pub claims: Vec<String>,
+    /// Positions of the disclosures to present
+    pub disclosures: Vec<usize>,
+}
+
+/// All the credentials satisfying (or not) every input descriptor of a definition
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub descriptors: Vec<DescriptorEvaluation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DescriptorEvaluation {
+    pub descriptor_id: String,
+    pub candidates: Vec<DescriptorMatch>,
+    /// Why the other credentials were discarded
+    pub unmet: Vec<(CredentialId, PresentationExchangeError)>,
+}
+
+impl PresentationDefinition {
+    /// Finds the candidate credentials for every input descriptor
+    pub fn evaluate<'a>(&self, credentials: impl IntoIterator<Item = &'a VerifiedSDJwt>) -> Evaluation {
+        let credentials = credentials.into_iter().collect::<Vec<_>>();
+        let descriptors = self
+            .input_descriptors
+            .iter()
+            .map(|descriptor| {
+                let mut evaluation = DescriptorEvaluation {
+                    descriptor_id: descriptor.id.clone(),
+                    candidates: vec![],
+                    unmet: vec![],
+                };
+                for credential in &credentials {
+                    match descriptor.try_match(credential) {
+                        Ok(m) => evaluation.candidates.push(m),
+                        Err(e) => evaluation.unmet.push((credential.id(), e)),
+                    }
+                }
+                evaluation
+            })
+            .collect();
+        Evaluation { descriptors }
+    }
+
+    /// Finds the candidate credentials in the store for every input descriptor
+    pub fn try_evaluate_store(&self, store: &impl CredentialStore) -> SdjResult<Evaluation> {
+        let credentials = store
+            .list()?
+            .into_iter()
+            .map(|c| VerifiedSDJwt::try_from_trusted(c.sd_jwt))
+            .collect::<SdjResult<Vec<_>>>()?;
+        Ok(self.evaluate(&credentials))
+    }
+
+    /// Builds one presentation per input descriptor, with the chosen credential, disclosing only
+    /// what the descriptor requires. Presentations are in the order of the `vp_token`.
+    pub fn try_present<'a>(
+        &self,
+        choices: &[(&str, &'a VerifiedSDJwt)],
+    ) -> SdjResult<(Vec<Presentation<'a>>, PresentationSubmission)> {
+        let mut presentations = Vec::with_capacity(choices.len());
+        let mut descriptor_map = Vec::with_capacity(choices.len());
+        for (i, (descriptor_id, credential)) in choices.iter().enumerate() {
+            let descriptor = self
+                .input_descriptors
+                .iter()
+                .find(|d| &d.id == descriptor_id)
+                .ok_or_else(|| PresentationExchangeError::UnknownInputDescriptor(descriptor_id.to_string()))?;
+            let format =
+                self.sd_jwt_format(descriptor)
+                    .ok_or_else(|| PresentationExchangeError::UnsupportedFormat {
+                        descriptor_id: descriptor.id.clone(),
+                        accepted: self
+                            .format
+                            .iter()
+                            .filter_map(serde_json::Value::as_object)
+                            .flat_map(|f| f.keys().cloned())
+                            .collect(),
+                    })?;
+            let matched = descriptor.try_match(credential)?;
+            presentations.push(credential.select_disclosures(&matched.disclosures));
+            descriptor_map.push(DescriptorMap {
+                id: descriptor.id.clone(),
+                format: format.to_string(),
+                path: if choices.len() == 1 {
+                    "$".to_string()
+                } else {
+                    format!("$[{i}]")
+                },
+            });
+        }
+
+        let submission = PresentationSubmission {
+            id: try_new_random_string()?,
+            definition_id: self.id.clone(),
+            descriptor_map,
+        };
+        Ok((presentations, submission))
+    }
+}
+
+impl InputDescriptor {
+    /// Checks that the credential satisfies every constraint, then computes the minimal
+    /// disclosures for revealing the claims matched by the fields. Those are disclosed entirely
+    /// unless `limit_disclosure` is `required`, in which case the claims nested in them stay
+    /// undisclosed.
+    pub fn try_match(&self, credential: &VerifiedSDJwt) -> PresentationExchangeResult<DescriptorMatch> {
+        let claims = self.try_match_claims(credential.claims())?;
+        let mut disclosures = if self.limits_disclosure() {
+            credential
+                .processed()
+                .disclosures
+                .iter()
+                .filter(|d| claims.iter().any(|c| is_ancestor_or_self(&d.path, c)))
+                .map(|d| d.index)
+                .collect()
+        } else {
+            let selection = claims.iter().fold(Selection::none(), |selection, pointer| {
+                selection.subtree(pointer.clone())
+            });
+            selection.select(credential.processed())
+        };
+        disclosures.sort_unstable();
+        disclosures.dedup();
+
+        Ok(DescriptorMatch {
+            descriptor_id: self.id.clone(),
+            credential_id: credential.id(),
+            claims,
+            disclosures,
+        })
+    }
+}
+
+impl Evaluation {
+    /// Whether every input descriptor has at least a candidate
+    pub fn is_satisfied(&self) -> bool {
+        self.descriptors.iter().all(|d| !d.candidates.is_empty())
+    }
+
+    /// The first candidate of every input descriptor
+    pub fn try_first_candidates(&self) -> PresentationExchangeResult<Vec<&DescriptorMatch>> {
+        self.descriptors
+            .iter()
+            .map(|d| {
+                d.candidates
+                    .first()
+                    .ok_or_else(|| PresentationExchangeError::NoCandidate {
+                        descriptor_id: d.descriptor_id.clone(),
+                        unmet: d.unmet.iter().map(|(_, e)| e.clone()).collect(),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        holder::{store::memory::InMemoryCredentialStore, store::StoredCredential, verified::tests::verified},
+        prelude::{Issuer, IssuerOptions},
+    };
+    use serde_json::{json, Value};
+
+    fn definition(fields: Value) -> PresentationDefinition {
+        serde_json::from_value(json!({
+            "id": "32f54163-7166-48f1-93d8-ff217bdb0653",
+            "input_descriptors": [{
+                "id": "pid",
+                "format": {"vc+sd-jwt": {"sd-jwt_alg_values": ["EdDSA"]}},
+                "constraints": {"limit_disclosure": "required", "fields": fields}
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn disclosed_names(presentation: &Presentation) -> Vec<String> {
+        presentation
+            .disclosures
+            .iter()
+            .map(|d| {
+                d.name()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| d.value().to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_compute_minimal_disclosures() {
+        let verified = verified();
+        let definition = definition(json!([
+            {"path": ["$.iss"], "filter": {"const": "https://example.com/issuer"}},
+            {"path": ["$.given_name"]},
+            {"path": ["$.address.country", "$.country"]},
+            {"path": ["$.nationalities[*]"], "filter": {"const": "DE"}}
+        ]));
+
+        let evaluation = definition.evaluate([&verified]);
+        assert!(evaluation.is_satisfied());
+        let matched = &evaluation.try_first_candidates().unwrap()[0];
+        assert_eq!(
+            matched.claims,
+            vec!["/iss", "/given_name", "/address/country", "/nationalities/1"]
+        );
+
+        let (presentations, submission) = definition.try_present(&[("pid", &verified)]).unwrap();
+        assert_eq!(
+            disclosed_names(&presentations[0]),
+            vec!["given_name", "country", "address", "\"DE\""]
+        );
+        assert_eq!(submission.definition_id, definition.id);
+        assert_eq!(
+            submission.descriptor_map,
+            vec![DescriptorMap {
+                id: "pid".to_string(),
+                format: "vc+sd-jwt".to_string(),
+                path: "$".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn should_limit_disclosure_when_required() {
+        let verified = verified();
+        let mut definition = definition(json!([{"path": ["$.address"]}]));
+        let (presentations, _) = definition.try_present(&[("pid", &verified)]).unwrap();
+        assert_eq!(disclosed_names(&presentations[0]), vec!["address"]);
+
+        definition.input_descriptors[0].constraints.limit_disclosure = None;
+        let (presentations, _) = definition.try_present(&[("pid", &verified)]).unwrap();
+        assert_eq!(disclosed_names(&presentations[0]), vec!["country", "address"]);
+    }
+
+    #[test]
+    fn should_map_accepted_format() {
+        let verified = verified();
+        let mut definition = definition(json!([{"path": ["$.given_name"]}]));
+        let format = |f: Value| Some(f.as_object().unwrap().clone());
+        let presented_format = |definition: &PresentationDefinition| {
+            let (_, submission) = definition.try_present(&[("pid", &verified)])?;
+            SdjResult::Ok(submission.descriptor_map[0].format.clone())
+        };
+        assert_eq!(presented_format(&definition).unwrap(), "vc+sd-jwt");
+
+        definition.input_descriptors[0].format = format(json!({"dc+sd-jwt": {}}));
+        assert_eq!(presented_format(&definition).unwrap(), "dc+sd-jwt");
+
+        definition.input_descriptors[0].format = None;
+        definition.format = Some(json!({"dc+sd-jwt": {}, "jwt_vc": {}}));
+        assert_eq!(presented_format(&definition).unwrap(), "dc+sd-jwt");
+
+        definition.format = Some(json!({"jwt_vc": {}}));
+        assert!(matches!(
+            presented_format(&definition).unwrap_err(),
+            crate::error::SdjError::PresentationExchangeError(PresentationExchangeError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn should_explain_unmet_constraints() {
+        let verified = verified();
+
+        let missing = definition(json!([{"path": ["$.birthdate"]}]));
+        let evaluation = missing.evaluate([&verified]);
+        assert!(!evaluation.is_satisfied());
+        assert!(matches!(
+            &evaluation.descriptors[0].unmet[0].1,
+            PresentationExchangeError::MissingField { descriptor_id, paths } if descriptor_id == "pid" && paths == &["$.birthdate"]
+        ));
+        assert!(matches!(
+            evaluation.try_first_candidates().unwrap_err(),
+            PresentationExchangeError::NoCandidate { descriptor_id, unmet } if descriptor_id == "pid" && unmet.len() == 1
+        ));
+
+        let filtered = definition(json!([{"path": ["$.given_name"], "filter": {"const": "Jane"}}]));
+        assert!(matches!(
+            &filtered.evaluate([&verified]).descriptors[0].unmet[0].1,
+            PresentationExchangeError::FilterNotSatisfied { .. }
+        ));
+
+        let optional = definition(json!([{"path": ["$.birthdate"], "optional": true}]));
+        assert!(optional.evaluate([&verified]).is_satisfied());
+    }
+
+    #[test]
+    fn should_reject_unsupported_format() {
+        let mut definition = definition(json!([]));
+        definition.input_descriptors[0].format = Some(json!({"jwt_vc": {}}).as_object().unwrap().clone());
+        assert!(matches!(
+            &definition.evaluate([&verified()]).descriptors[0].unmet[0].1,
+            PresentationExchangeError::UnsupportedFormat { .. }
+        ));
+    }
+
+    #[test]
+    fn should_find_candidates_in_store() {
+        let mut store = InMemoryCredentialStore::default();
+        let mut issuer = Issuer::try_new().unwrap();
+        for vct in ["pid", "mdl"] {
+            let sd_jwt = issuer
+                .try_generate_sd_jwt(
+                    json!({"vct": vct, "given_name": "John"}),
+                    &["/given_name"],
+                    IssuerOptions::default(),
+                )
+                .unwrap();
+            store.save(StoredCredential::try_new(sd_jwt).unwrap()).unwrap();
+        }
+
+        let definition = definition(json!([
+            {"path": ["$.vct"], "filter": {"const": "mdl"}},
+            {"path": ["$.given_name"]}
+        ]));
+        let evaluation = definition.try_evaluate_store(&store).unwrap();
+        let descriptor = &evaluation.descriptors[0];
+        assert_eq!(descriptor.candidates.len(), 1);
+        assert_eq!(descriptor.unmet.len(), 1);
+
+        let candidate = store.get(&descriptor.candidates[0].credential_id).unwrap().unwrap();
+        assert_eq!(candidate.metadata.vct.as_deref(), Some("mdl"));
+    }
+
+    #[test]
+    fn should_map_descriptors_in_vp_token() {
+        let verified = verified();
+        let definition: PresentationDefinition = serde_json::from_value(json!({
+            "id": "multi",
+            "input_descriptors": [
+                {"id": "name", "constraints": {"fields": [{"path": ["$.given_name"]}]}},
+                {"id": "address", "constraints": {"fields": [{"path": ["$.address"]}]}}
+            ]
+        }))
+        .unwrap();
+        let (presentations, submission) = definition
+            .try_present(&[("name", &verified), ("address", &verified)])
+            .unwrap();
+        assert_eq!(presentations.len(), 2);
+        let paths = submission
+            .descriptor_map
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["$[0]", "$[1]"]);
+
+        assert!(matches!(
+            definition.try_present(&[("unknown", &verified)]).unwrap_err(),
+            crate::error::SdjError::PresentationExchangeError(PresentationExchangeError::UnknownInputDescriptor(_))
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{
+    core::{disclosure::Disclosure, json_pointer::visit, processing::ProcessedClaims},
+    error::SdjResult,
+    holder::presentation::Presentation,
+};
+use serde_json::Value;
+
+/// Exactly what a Verifier will learn from a [Presentation]
+#[derive(Debug, Clone)]
+pub struct PresentationPreview {
+    /// The claims the Verifier will reconstruct
+    pub claims: Value,
+    /// Json pointers of the claims the Verifier sees whatever the selection, as they appear when
+    /// nothing is disclosed
+    pub always_visible: Vec<String>,
+    /// Number of digests the Verifier sees without being able to know what they stand for. Leaks
+    /// an upper bound on the number of undisclosed claims
+    pub undisclosed_digests: usize,
+    /// Json pointers (in [Self::claims]) of the arrays along with their actual length, including
+    /// undisclosed items
+    pub array_lengths: Vec<(String, usize)>,
+}
+
+impl<'a> Presentation<'a> {
+    /// Computes what a Verifier will see, with the very same algorithm it uses
+    pub fn try_preview(&self) -> SdjResult<PresentationPreview> {
+        let payload = self.jws.try_read_unverified_payload()?;
+        let processed = ProcessedClaims::try_verify(&payload, &self.disclosures)?;
+
+        let nothing_disclosed = ProcessedClaims::try_new::<Disclosure>(&payload, &[])?;
+        let mut always_visible = vec![];
+        visit(&nothing_disclosed.claims, "", &mut |path, _| {
+            always_visible.push(path.to_string())
+        });
+
+        Ok(PresentationPreview {
+            claims: processed.claims,
+            always_visible,
+            undisclosed_digests: processed.undisclosed_digests,
+            array_lengths: processed.array_lengths,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        holder::verified::tests::{verified, verified_with_key},
+        prelude::{Holder, JwsAlgorithm, Selection, Verifier},
+    };
+    use serde_json::json;
+
+    #[test]
+    fn should_preview_what_verifier_sees() {
+        let verified = verified();
+        let selection = Selection::none().claim("given_name").matching(|_, v| v == "DE");
+        let preview = Holder::preview(&verified, &selection).unwrap();
+
+        assert_eq!(
+            preview.claims,
+            json!({
+                "iss": "https://example.com/issuer",
+                "given_name": "John",
+                "nationalities": ["DE"]
+            })
+        );
+        assert_eq!(preview.always_visible, vec!["/iss", "/nationalities"]);
+        // family_name, address & nationalities[0]
+        assert_eq!(preview.undisclosed_digests, 3);
+        assert_eq!(preview.array_lengths, vec![("/nationalities".to_string(), 2)]);
+    }
+
+    #[test]
+    fn should_match_verifier() {
+        let (verified, issuer_pk) = verified_with_key();
+        let presentation = verified.select_claims(&Selection::none().subtree("/address"));
+        let preview = presentation.try_preview().unwrap();
+
+        let serialized = presentation.try_serialize().unwrap();
+        let verified_claims = Verifier::verify(&serialized, JwsAlgorithm::Ed25519, &issuer_pk).unwrap();
+        assert_eq!(preview.claims, verified_claims);
+        assert_eq!(
+            preview.claims["address"],
+            json!({"street_address": "123 Main St", "country": "US"})
+        );
+    }
+}
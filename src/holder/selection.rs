@@ -0,0 +1,224 @@
+use crate::core::{
+    dcql::claims_path::ClaimsPath,
+    json_pointer::{escape, visit},
+    processing::{is_ancestor_or_self, ProcessedClaims},
+};
+use serde_json::Value;
+
+/// Selects the claims to disclose on the disclosed-claim view of a SD-JWT rather than on the
+/// Issuer payload. Every selected claim is disclosed entirely i.e. along with all its children,
+/// and with the parent disclosures it is nested in.
+#[derive(Default)]
+pub struct Selection {
+    all: bool,
+    selectors: Vec<ClaimSelector>,
+}
+
+enum ClaimSelector {
+    /// Every claim with this name, wherever it is
+    Name(String),
+    /// The claim at this Json pointer
+    Subtree(String),
+    /// The claims at this claims path
+    Path(ClaimsPath),
+    /// Every claim for which this returns true
+    Predicate(ClaimPredicate),
+}
+
+type ClaimPredicate = Box<dyn Fn(&str, &Value) -> bool>;
+
+impl Selection {
+    /// Discloses every disclosure
+    pub fn all() -> Self {
+        Self {
+            all: true,
+            selectors: vec![],
+        }
+    }
+
+    /// Discloses nothing
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Selects every claim with this name, wherever it is
+    pub fn claim(mut self, name: impl Into<String>) -> Self {
+        self.selectors.push(ClaimSelector::Name(name.into()));
+        self
+    }
+
+    /// Selects the claim at this Json pointer (e.g. `/address`) with everything under it
+    pub fn subtree(mut self, pointer: impl Into<String>) -> Self {
+        self.selectors.push(ClaimSelector::Subtree(pointer.into()));
+        self
+    }
+
+    /// Selects the claims at this claims path (e.g. `["degrees", null, "type"]`) with everything
+    /// under them
+    pub fn path(mut self, path: ClaimsPath) -> Self {
+        self.selectors.push(ClaimSelector::Path(path));
+        self
+    }
+
+    /// Selects every claim for which the predicate, called with the Json pointer to the claim and
+    /// its disclosed value, returns true
+    pub fn matching(mut self, predicate: impl Fn(&str, &Value) -> bool + 'static) -> Self {
+        self.selectors.push(ClaimSelector::Predicate(Box::new(predicate)));
+        self
+    }
+
+    /// Computes the minimal set of disclosures (by their position in the SD-JWT) revealing the
+    /// selected claims
+    pub(crate) fn select(&self, processed: &ProcessedClaims) -> Vec<usize> {
+        if self.all {
+            return processed.disclosures.iter().map(|d| d.index).collect();
+        }
+
+        let mut selected_paths = vec![];
+        visit(&processed.claims, "", &mut |path, value| {
+            if self.selectors.iter().any(|s| s.matches(path, value)) {
+                selected_paths.push(path.to_string());
+            }
+        });
+        for selector in &self.selectors {
+            if let ClaimSelector::Path(path) = selector {
+                selected_paths.extend(path.find(&processed.claims).into_iter().map(|(p, _)| p));
+            }
+        }
+
+        processed
+            .disclosures
+            .iter()
+            .filter(|d| {
+                selected_paths
+                    .iter()
+                    .any(|p| is_ancestor_or_self(&d.path, p) || is_ancestor_or_self(p, &d.path))
+            })
+            .map(|d| d.index)
+            .collect()
+    }
+}
+
+impl ClaimSelector {
+    fn matches(&self, path: &str, value: &Value) -> bool {
+        match self {
+            Self::Name(name) => path
+                .rsplit_once('/')
+                .map(|(_, last)| last == escape(name))
+                .unwrap_or_default(),
+            Self::Subtree(pointer) => path == pointer,
+            Self::Predicate(predicate) => predicate(path, value),
+            Self::Path(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions, SDJwt};
+    use serde_json::json;
+
+    fn processed() -> (SDJwt, ProcessedClaims) {
+        let input = json!({
+            "iss": "https://example.com/issuer",
+            "given_name": "John",
+            "email": "johndoe@example.com",
+            "address": {"street_address": "123 Main St", "country": "US"},
+            "nationalities": ["US", "DE"]
+        });
+        let decisions = &[
+            "/given_name",
+            "/email",
+            "/address/street_address",
+            "/address/country",
+            "/address",
+            "/nationalities/0",
+            "/nationalities/1",
+        ];
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt(input, decisions, IssuerOptions::default())
+            .unwrap();
+        let payload = sd_jwt.jws.try_read_unverified_payload().unwrap();
+        let processed = ProcessedClaims::try_new(&payload, &sd_jwt.disclosures).unwrap();
+        (sd_jwt, processed)
+    }
+
+    fn selected_paths(processed: &ProcessedClaims, selection: Selection) -> Vec<String> {
+        let mut paths = selection
+            .select(processed)
+            .into_iter()
+            .map(|i| {
+                processed
+                    .disclosures
+                    .iter()
+                    .find(|d| d.index == i)
+                    .unwrap()
+                    .path
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn should_select_all_or_none() {
+        let (sd_jwt, processed) = processed();
+        assert_eq!(Selection::all().select(&processed).len(), sd_jwt.disclosures.len());
+        assert!(Selection::none().select(&processed).is_empty());
+    }
+
+    #[test]
+    fn should_select_by_name_with_parents() {
+        let (_, processed) = processed();
+        assert_eq!(
+            selected_paths(&processed, Selection::none().claim("given_name")),
+            vec!["/given_name"]
+        );
+        assert_eq!(
+            selected_paths(&processed, Selection::none().claim("country")),
+            vec!["/address", "/address/country"]
+        );
+        assert!(selected_paths(&processed, Selection::none().claim("iss")).is_empty());
+    }
+
+    #[test]
+    fn should_select_subtree() {
+        let (_, processed) = processed();
+        assert_eq!(
+            selected_paths(&processed, Selection::none().subtree("/address")),
+            vec!["/address", "/address/country", "/address/street_address"]
+        );
+        assert_eq!(
+            selected_paths(&processed, Selection::none().subtree("/nationalities/1")),
+            vec!["/nationalities/1"]
+        );
+    }
+
+    #[test]
+    fn should_select_by_claims_path() {
+        let (_, processed) = processed();
+        let path = |p: &str| p.parse::<ClaimsPath>().unwrap();
+        assert_eq!(
+            selected_paths(&processed, Selection::none().path(path(r#"["address", "country"]"#))),
+            vec!["/address", "/address/country"]
+        );
+        assert_eq!(
+            selected_paths(&processed, Selection::none().path(path(r#"["nationalities", null]"#))),
+            vec!["/nationalities/0", "/nationalities/1"]
+        );
+        assert!(selected_paths(&processed, Selection::none().path(path(r#"["address", 0]"#))).is_empty());
+    }
+
+    #[test]
+    fn should_select_by_predicate() {
+        let (_, processed) = processed();
+        let selection = Selection::none().matching(|_, value| value == "DE" || value == "John");
+        assert_eq!(
+            selected_paths(&processed, selection),
+            vec!["/given_name", "/nationalities/1"]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    crypto::error::CryptoError,
+    error::SdjResult,
+    holder::store::{
+        memory::InMemoryCredentialStore, CredentialBackup, CredentialId, CredentialStore, StoredCredential,
+    },
+};
+use aes_gcm::{aead::Aead as _, Aes256Gcm, KeyInit as _};
+use std::path::PathBuf;
+
+/// A [CredentialStore] persisted in a single file, encrypted at rest with AES-256-GCM under a
+/// caller supplied key. The file is entirely, and atomically, rewritten on every mutation.
+pub struct FileCredentialStore {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    cache: InMemoryCredentialStore,
+}
+
+impl FileCredentialStore {
+    const NONCE_SIZE: usize = 12;
+
+    /// Opens the store at this path, creating it if it does not exist yet. Fails if the file
+    /// cannot be decrypted with this key.
+    pub fn try_open(path: impl Into<PathBuf>, key: &[u8; 32]) -> SdjResult<Self> {
+        let path = path.into();
+        let cipher = Aes256Gcm::new(key.into());
+        let mut store = Self {
+            path,
+            cipher,
+            cache: InMemoryCredentialStore::default(),
+        };
+        if store.path.exists() {
+            let encrypted = std::fs::read(&store.path)?;
+            for credential in store.decrypt(&encrypted)? {
+                store.cache.save(credential)?;
+            }
+        }
+        Ok(store)
+    }
+
+    /// Applies a mutation to a copy of the cache, which only replaces the cache once persisted
+    fn try_mutate<T>(&mut self, mutate: impl FnOnce(&mut InMemoryCredentialStore) -> SdjResult<T>) -> SdjResult<T> {
+        let mut staged = self.cache.clone();
+        let result = mutate(&mut staged)?;
+        self.persist(&staged)?;
+        self.cache = staged;
+        Ok(result)
+    }
+
+    /// Writes to a temporary file next to the store then renames it over the store, so that a
+    /// crash mid-write never leaves a truncated store behind
+    fn persist(&self, cache: &InMemoryCredentialStore) -> SdjResult<()> {
+        let encrypted = self.encrypt(&cache.list()?)?;
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        let mut file = std::fs::File::create(&temp)?;
+        std::io::Write::write_all(&mut file, &encrypted)?;
+        file.sync_all()?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+
+    fn encrypt(&self, credentials: &[StoredCredential]) -> SdjResult<Vec<u8>> {
+        let plaintext = serde_json::to_vec(credentials)?;
+        let mut nonce = [0u8; Self::NONCE_SIZE];
+        getrandom::getrandom(&mut nonce).map_err(|_| CryptoError::EncryptionError)?;
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce.into(), plaintext.as_slice())
+            .map_err(|_| CryptoError::EncryptionError)?;
+        Ok([nonce.as_slice(), ciphertext.as_slice()].concat())
+    }
+
+    fn decrypt(&self, encrypted: &[u8]) -> SdjResult<Vec<StoredCredential>> {
+        if encrypted.len() < Self::NONCE_SIZE {
+            return Err(CryptoError::DecryptionError.into());
+        }
+        let (nonce, ciphertext) = encrypted.split_at(Self::NONCE_SIZE);
+        let plaintext = self
+            .cipher
+            .decrypt(nonce.into(), ciphertext)
+            .map_err(|_| CryptoError::DecryptionError)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+impl CredentialStore for FileCredentialStore {
+    fn save(&mut self, credential: StoredCredential) -> SdjResult<CredentialId> {
+        self.try_mutate(|cache| cache.save(credential))
+    }
+
+    fn get(&self, id: &CredentialId) -> SdjResult<Option<StoredCredential>> {
+        self.cache.get(id)
+    }
+
+    fn list(&self) -> SdjResult<Vec<StoredCredential>> {
+        self.cache.list()
+    }
+
+    fn update(&mut self, credential: StoredCredential) -> SdjResult<()> {
+        self.try_mutate(|cache| cache.update(credential))
+    }
+
+    fn delete(&mut self, id: &CredentialId) -> SdjResult<Option<StoredCredential>> {
+        if self.cache.get(id)?.is_none() {
+            return Ok(None);
+        }
+        self.try_mutate(|cache| cache.delete(id))
+    }
+
+    /// Imports every credential in memory then rewrites the file once, so that a failed import
+    /// leaves the store untouched
+    fn import_backup(&mut self, backup: CredentialBackup) -> SdjResult<usize> {
+        self.try_mutate(|cache| cache.import_backup(backup))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{error::SdjError, holder::store::tests::credential};
+
+    fn temp_path() -> PathBuf {
+        let mut suffix = [0u8; 8];
+        getrandom::getrandom(&mut suffix).unwrap();
+        let suffix = base64_simd::URL_SAFE_NO_PAD.encode_to_string(suffix);
+        std::env::temp_dir().join(format!("sd-jwt-store-{suffix}"))
+    }
+
+    #[test]
+    fn should_persist_across_openings() {
+        let path = temp_path();
+        let key = [7u8; 32];
+
+        let mut store = FileCredentialStore::try_open(&path, &key).unwrap();
+        let id = store.save(credential("https://example.com/issuer", "pid")).unwrap();
+        drop(store);
+
+        let mut store = FileCredentialStore::try_open(&path, &key).unwrap();
+        assert!(store.get(&id).unwrap().is_some());
+        store.delete(&id).unwrap();
+        drop(store);
+
+        let store = FileCredentialStore::try_open(&path, &key).unwrap();
+        assert!(store.list().unwrap().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_encrypt_at_rest() {
+        let path = temp_path();
+        let mut store = FileCredentialStore::try_open(&path, &[7u8; 32]).unwrap();
+        store.save(credential("https://example.com/issuer", "pid")).unwrap();
+
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("example.com"));
+
+        assert!(matches!(
+            FileCredentialStore::try_open(&path, &[8u8; 32]),
+            Err(SdjError::CryptoError(CryptoError::DecryptionError))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_import_backup_at_once() {
+        let path = temp_path();
+        let key = [7u8; 32];
+        let mut memory = InMemoryCredentialStore::default();
+        memory.save(credential("https://example.com/issuer", "pid")).unwrap();
+        memory.save(credential("https://example.com/issuer", "mdl")).unwrap();
+        let backup = memory.export_backup().unwrap();
+
+        let mut store = FileCredentialStore::try_open(&path, &key).unwrap();
+        assert_eq!(store.import_backup(backup.clone()).unwrap(), 2);
+        drop(store);
+        let mut store = FileCredentialStore::try_open(&path, &key).unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        // a failed import leaves the store as it was
+        store.delete(&backup.credentials[0].id).unwrap();
+        let unsupported = CredentialBackup {
+            version: CredentialBackup::VERSION + 1,
+            ..backup.clone()
+        };
+        assert!(matches!(
+            store.import_backup(unsupported),
+            Err(SdjError::UnsupportedBackupVersion(_))
+        ));
+        assert_eq!(store.list().unwrap().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+
+        let dir = temp_path();
+        std::fs::create_dir(&dir).unwrap();
+        let mut store = FileCredentialStore::try_open(dir.join("store"), &key).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(store.import_backup(backup), Err(SdjError::IoError(_))));
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_keep_cache_in_sync_when_write_fails() {
+        let dir = temp_path();
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("store");
+        let mut store = FileCredentialStore::try_open(&path, &[7u8; 32]).unwrap();
+        let id = store.save(credential("https://example.com/issuer", "pid")).unwrap();
+        assert!(!dir.join("store.tmp").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            store.save(credential("https://example.com/issuer", "mdl")),
+            Err(SdjError::IoError(_))
+        ));
+        assert!(store.delete(&id).is_err());
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(store.get(&id).unwrap().is_some());
+    }
+}
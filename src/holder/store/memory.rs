@@ -0,0 +1,118 @@
+use crate::{
+    error::{SdjError, SdjResult},
+    holder::store::{CredentialId, CredentialStore, StoredCredential},
+};
+use std::collections::BTreeMap;
+
+/// A non-persistent [CredentialStore]
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryCredentialStore {
+    credentials: BTreeMap<CredentialId, StoredCredential>,
+}
+
+impl CredentialStore for InMemoryCredentialStore {
+    fn save(&mut self, credential: StoredCredential) -> SdjResult<CredentialId> {
+        let id = credential.id.clone();
+        self.credentials.insert(id.clone(), credential);
+        Ok(id)
+    }
+
+    fn get(&self, id: &CredentialId) -> SdjResult<Option<StoredCredential>> {
+        Ok(self.credentials.get(id).cloned())
+    }
+
+    fn list(&self) -> SdjResult<Vec<StoredCredential>> {
+        Ok(self.credentials.values().cloned().collect())
+    }
+
+    fn update(&mut self, credential: StoredCredential) -> SdjResult<()> {
+        let existing = self
+            .credentials
+            .get_mut(&credential.id)
+            .ok_or_else(|| SdjError::CredentialNotFound(credential.id.to_string()))?;
+        *existing = credential;
+        Ok(())
+    }
+
+    fn delete(&mut self, id: &CredentialId) -> SdjResult<Option<StoredCredential>> {
+        Ok(self.credentials.remove(id))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::holder::store::{tests::credential, CredentialFilter};
+
+    #[test]
+    fn should_save_get_and_delete() {
+        let mut store = InMemoryCredentialStore::default();
+        let credential = credential("https://example.com/issuer", "pid");
+        let id = store.save(credential.clone()).unwrap();
+        assert_eq!(id, credential.id);
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        // saving twice does not duplicate
+        store.save(credential.clone()).unwrap();
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        let found = store.get(&id).unwrap().unwrap();
+        assert_eq!(
+            found.sd_jwt.try_serialize().unwrap(),
+            credential.sd_jwt.try_serialize().unwrap()
+        );
+
+        assert!(store.delete(&id).unwrap().is_some());
+        assert!(store.get(&id).unwrap().is_none());
+        assert!(store.delete(&id).unwrap().is_none());
+    }
+
+    #[test]
+    fn should_update() {
+        let mut store = InMemoryCredentialStore::default();
+        let mut credential = credential("https://example.com/issuer", "pid");
+        assert!(matches!(
+            store.update(credential.clone()).unwrap_err(),
+            SdjError::CredentialNotFound(_)
+        ));
+
+        store.save(credential.clone()).unwrap();
+        credential.metadata.status = Some(serde_json::json!({"status_list": {"idx": 0, "uri": "https://example.com"}}));
+        store.update(credential.clone()).unwrap();
+        let found = store.get(&credential.id).unwrap().unwrap();
+        assert_eq!(found.metadata, credential.metadata);
+    }
+
+    #[test]
+    fn should_query() {
+        let mut store = InMemoryCredentialStore::default();
+        store.save(credential("https://a.com", "pid")).unwrap();
+        store.save(credential("https://a.com", "mdl")).unwrap();
+        store.save(credential("https://b.com", "pid")).unwrap();
+
+        let by_issuer = store
+            .query(&CredentialFilter::default().issuer("https://a.com"))
+            .unwrap();
+        assert_eq!(by_issuer.len(), 2);
+        let by_vct = store.query(&CredentialFilter::default().vct("pid")).unwrap();
+        assert_eq!(by_vct.len(), 2);
+        let by_claim = store.query(&CredentialFilter::default().with_claim("email")).unwrap();
+        assert_eq!(by_claim.len(), 3);
+        let none = store.query(&CredentialFilter::default().with_claim("nope")).unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn should_export_and_import_backup() {
+        let mut store = InMemoryCredentialStore::default();
+        store.save(credential("https://a.com", "pid")).unwrap();
+        store.save(credential("https://b.com", "pid")).unwrap();
+
+        let backup = serde_json::to_string(&store.export_backup().unwrap()).unwrap();
+
+        let mut restored = InMemoryCredentialStore::default();
+        let count = restored.import_backup(serde_json::from_str(&backup).unwrap()).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(restored.list().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    error::{SdjError, SdjResult},
+    prelude::SDJwt,
+};
+use serde_json::Value;
+
+#[cfg(feature = "file-store")]
+pub mod file;
+pub mod memory;
+
+/// Persists the SD-JWTs a [crate::prelude::Holder] has been issued, along with their metadata.
+///
+/// Credentials are expected to have been verified before being saved, hence a store never
+/// verifies anything by itself.
+pub trait CredentialStore {
+    /// Saves a new credential and returns its identifier. Saving the same credential twice
+    /// overwrites it.
+    fn save(&mut self, credential: StoredCredential) -> SdjResult<CredentialId>;
+
+    /// Fetches a credential by its identifier
+    fn get(&self, id: &CredentialId) -> SdjResult<Option<StoredCredential>>;
+
+    /// Lists all the credentials in the store
+    fn list(&self) -> SdjResult<Vec<StoredCredential>>;
+
+    /// Replaces an existing credential. Fails when it is not in the store
+    fn update(&mut self, credential: StoredCredential) -> SdjResult<()>;
+
+    /// Removes a credential and returns it, if it was in the store
+    fn delete(&mut self, id: &CredentialId) -> SdjResult<Option<StoredCredential>>;
+
+    /// Lists all the credentials matching the filter
+    fn query(&self, filter: &CredentialFilter) -> SdjResult<Vec<StoredCredential>> {
+        Ok(self.list()?.into_iter().filter(|c| filter.matches(c)).collect())
+    }
+
+    /// Exports all the credentials in the store. The backup is not encrypted.
+    fn export_backup(&self) -> SdjResult<CredentialBackup> {
+        Ok(CredentialBackup {
+            version: CredentialBackup::VERSION,
+            credentials: self.list()?,
+        })
+    }
+
+    /// Imports all the credentials of a backup, overwriting the ones already present.
+    /// Returns the number of imported credentials
+    fn import_backup(&mut self, backup: CredentialBackup) -> SdjResult<usize> {
+        if backup.version != CredentialBackup::VERSION {
+            return Err(SdjError::UnsupportedBackupVersion(backup.version));
+        }
+        let count = backup.credentials.len();
+        for credential in backup.credentials {
+            self.save(credential)?;
+        }
+        Ok(count)
+    }
+}
+
+/// Identifies a credential in a [CredentialStore]. Derived from the Issuer-signed JWT so that
+/// saving the same credential twice does not duplicate it.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::AsRef,
+    derive_more::From,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct CredentialId(String);
+
+impl CredentialId {
+    pub(crate) fn from_sd_jwt(sd_jwt: &SDJwt) -> Self {
+        use sha2::Digest as _;
+        let hash = sha2::Sha256::digest(sd_jwt.jws.as_bytes());
+        Self(base64_simd::URL_SAFE_NO_PAD.encode_to_string(hash))
+    }
+}
+
+impl std::fmt::Display for CredentialId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A credential along with its metadata
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredCredential {
+    pub id: CredentialId,
+    pub sd_jwt: SDJwt,
+    pub metadata: CredentialMetadata,
+}
+
+impl StoredCredential {
+    /// Extracts the metadata from the (already verified) Issuer-signed JWT
+    pub fn try_new(sd_jwt: SDJwt) -> SdjResult<Self> {
+        let payload = sd_jwt.jws.try_read_unverified_payload()?;
+        Ok(Self {
+            id: CredentialId::from_sd_jwt(&sd_jwt),
+            metadata: CredentialMetadata::from_payload(&payload),
+            sd_jwt,
+        })
+    }
+
+    /// Whether a claim with this name is either always visible or selectively disclosable
+    pub fn has_claim(&self, name: &str) -> bool {
+        fn is_visible(value: &Value, name: &str) -> bool {
+            match value {
+                Value::Object(map) => map.iter().any(|(k, v)| k == name || is_visible(v, name)),
+                Value::Array(items) => items.iter().any(|v| is_visible(v, name)),
+                _ => false,
+            }
+        }
+
+        let is_disclosable = self
+            .sd_jwt
+            .disclosures
+            .iter()
+            .any(|d| d.name() == Some(name) || is_visible(d.value(), name));
+        is_disclosable
+            || self
+                .sd_jwt
+                .jws
+                .try_read_unverified_payload()
+                .map(|p| is_visible(&p, name))
+                .unwrap_or_default()
+    }
+}
+
+/// Metadata of a credential, extracted from its Issuer-signed JWT
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CredentialMetadata {
+    /// The `iss` claim
+    pub issuer: Option<String>,
+    /// The `vct` claim of a SD-JWT VC
+    pub vct: Option<String>,
+    /// The `iat` claim
+    pub issued_at: Option<u64>,
+    /// The `exp` claim
+    pub expires_at: Option<u64>,
+    /// The `status` claim e.g. a reference in a Token Status List
+    pub status: Option<Value>,
+}
+
+impl CredentialMetadata {
+    fn from_payload(payload: &Value) -> Self {
+        let string = |claim| payload.get(claim).and_then(Value::as_str).map(ToString::to_string);
+        let timestamp = |claim| payload.get(claim).and_then(Value::as_u64);
+        Self {
+            issuer: string("iss"),
+            vct: string("vct"),
+            issued_at: timestamp("iat"),
+            expires_at: timestamp("exp"),
+            status: payload.get("status").cloned(),
+        }
+    }
+
+    /// Whether the credential is expired at the given unix timestamp (in seconds)
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.map(|exp| exp <= now).unwrap_or_default()
+    }
+}
+
+/// Criteria for querying a [CredentialStore]. All of them have to match.
+#[derive(Debug, Clone, Default)]
+pub struct CredentialFilter {
+    pub issuer: Option<String>,
+    pub vct: Option<String>,
+    /// Names of the claims the credential must contain, either visible or disclosable
+    pub claims: Vec<String>,
+}
+
+impl CredentialFilter {
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    pub fn vct(mut self, vct: impl Into<String>) -> Self {
+        self.vct = Some(vct.into());
+        self
+    }
+
+    pub fn with_claim(mut self, name: impl Into<String>) -> Self {
+        self.claims.push(name.into());
+        self
+    }
+
+    pub fn matches(&self, credential: &StoredCredential) -> bool {
+        let metadata = &credential.metadata;
+        let issuer_matches = self.issuer.is_none() || self.issuer == metadata.issuer;
+        let vct_matches = self.vct.is_none() || self.vct == metadata.vct;
+        issuer_matches && vct_matches && self.claims.iter().all(|c| credential.has_claim(c))
+    }
+}
+
+/// Plaintext export of all the credentials of a [CredentialStore]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CredentialBackup {
+    pub version: u32,
+    pub credentials: Vec<StoredCredential>,
+}
+
+impl CredentialBackup {
+    pub const VERSION: u32 = 1;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions};
+    use serde_json::json;
+
+    pub fn credential(issuer: &str, vct: &str) -> StoredCredential {
+        let input = json!({
+            "iss": issuer,
+            "vct": vct,
+            "exp": 1883000000,
+            "given_name": "John",
+            "email": "johndoe@example.com",
+            "address": {"country": "US"}
+        });
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt(input, &["/given_name", "/address"], IssuerOptions::default())
+            .unwrap();
+        StoredCredential::try_new(sd_jwt).unwrap()
+    }
+
+    #[test]
+    fn should_extract_metadata() {
+        let credential = credential("https://example.com/issuer", "urn:eu.europa.ec.eudi:pid:1");
+        assert_eq!(
+            credential.metadata.issuer.as_deref(),
+            Some("https://example.com/issuer")
+        );
+        assert_eq!(credential.metadata.vct.as_deref(), Some("urn:eu.europa.ec.eudi:pid:1"));
+        assert_eq!(credential.metadata.expires_at, Some(1883000000));
+        assert!(credential.metadata.is_expired(1883000000));
+        assert!(!credential.metadata.is_expired(1683000000));
+    }
+
+    #[test]
+    fn should_find_visible_and_disclosable_claims() {
+        let credential = credential("https://example.com/issuer", "urn:eu.europa.ec.eudi:pid:1");
+        assert!(credential.has_claim("email"));
+        assert!(credential.has_claim("given_name"));
+        assert!(credential.has_claim("country"));
+        assert!(!credential.has_claim("family_name"));
+    }
+
+    #[test]
+    fn should_filter() {
+        let credential = credential("https://example.com/issuer", "urn:eu.europa.ec.eudi:pid:1");
+        assert!(CredentialFilter::default().matches(&credential));
+        assert!(CredentialFilter::default()
+            .issuer("https://example.com/issuer")
+            .vct("urn:eu.europa.ec.eudi:pid:1")
+            .with_claim("given_name")
+            .matches(&credential));
+        assert!(!CredentialFilter::default()
+            .issuer("https://other.com")
+            .matches(&credential));
+        assert!(!CredentialFilter::default().vct("other").matches(&credential));
+        assert!(!CredentialFilter::default()
+            .with_claim("family_name")
+            .matches(&credential));
+    }
+}
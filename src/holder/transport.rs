@@ -0,0 +1,96 @@
+use crate::error::SdjResult;
+
+/// Sends the HTTP requests of the protocols built on top of SD-JWT, so that the crate does not
+/// depend on a specific HTTP client and flows can be driven in memory in tests
+pub trait Transport {
+    fn send(&self, request: HttpRequest) -> SdjResult<HttpResponse>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            method: HttpMethod::Get,
+            url: url.into(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    /// A POST without any body
+    pub fn post(url: impl Into<String>) -> Self {
+        Self {
+            method: HttpMethod::Post,
+            url: url.into(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    pub fn try_post_json(url: impl Into<String>, body: &impl serde::Serialize) -> SdjResult<Self> {
+        Ok(Self {
+            method: HttpMethod::Post,
+            url: url.into(),
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: Some(serde_json::to_vec(body)?),
+        })
+    }
+
+    pub fn post_form<'a>(url: impl Into<String>, parameters: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        Self {
+            method: HttpMethod::Post,
+            url: url.into(),
+            headers: vec![(
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded".to_string(),
+            )],
+            body: Some(crate::core::url::encode_form(parameters).into_bytes()),
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
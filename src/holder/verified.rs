@@ -0,0 +1,265 @@
+use crate::{
+    core::{
+        dcql::claims_path::ClaimsPath,
+        json_pointer::{path::JsonPointerPath, DigestRef, JsonPointer},
+        processing::ProcessedClaims,
+    },
+    error::{SdjError, SdjResult},
+    holder::{presentation::Presentation, selection::Selection, store::CredentialId},
+    prelude::{JwsAlgorithm, SDJwt},
+};
+use serde_json::{json, Value};
+
+/// A SD-JWT whose Issuer signature has been verified once and for all, from which as many
+/// presentations as needed can then be derived.
+#[derive(Debug, Clone)]
+pub struct VerifiedSDJwt {
+    sd_jwt: SDJwt,
+    payload: Value,
+    processed: ProcessedClaims,
+}
+
+impl VerifiedSDJwt {
+    /// Verifies the Issuer signature and matches every disclosure with the payload
+    pub fn try_new(sd_jwt: SDJwt, alg: JwsAlgorithm, issuer_verifying_key: &str) -> SdjResult<Self> {
+        let payload = json!(sd_jwt.jws.try_read_payload(alg, issuer_verifying_key)?);
+        Self::try_from_payload(sd_jwt, payload)
+    }
+
+    /// For a SD-JWT which has already been verified e.g. when it was received, before being saved
+    /// in a [crate::prelude::CredentialStore]. The Issuer signature is NOT verified.
+    pub fn try_from_trusted(sd_jwt: SDJwt) -> SdjResult<Self> {
+        let payload = sd_jwt.jws.try_read_unverified_payload()?;
+        Self::try_from_payload(sd_jwt, payload)
+    }
+
+    fn try_from_payload(sd_jwt: SDJwt, payload: Value) -> SdjResult<Self> {
+        let processed = ProcessedClaims::try_new(&payload, &sd_jwt.disclosures)?;
+        Ok(Self {
+            sd_jwt,
+            payload,
+            processed,
+        })
+    }
+
+    /// Same identifier as in a [crate::prelude::CredentialStore]
+    pub fn id(&self) -> CredentialId {
+        CredentialId::from_sd_jwt(&self.sd_jwt)
+    }
+
+    pub fn sd_jwt(&self) -> &SDJwt {
+        &self.sd_jwt
+    }
+
+    /// The Issuer-signed JWT payload, with digests
+    pub fn payload(&self) -> &Value {
+        &self.payload
+    }
+
+    /// All the claims once every disclosure has been disclosed
+    pub fn claims(&self) -> &Value {
+        &self.processed.claims
+    }
+
+    pub(crate) fn processed(&self) -> &ProcessedClaims {
+        &self.processed
+    }
+
+    pub fn into_sd_jwt(self) -> SDJwt {
+        self.sd_jwt
+    }
+
+    /// Discloses the claims at those Json pointers in the Issuer payload e.g. `/nationalities/0`
+    /// for the first item of the `nationalities` array. Fails if any of them is not selectively
+    /// disclosable.
+    pub fn select<P: AsRef<str>>(&self, paths: impl IntoIterator<Item = P>) -> SdjResult<Presentation<'_>> {
+        let mut selected = vec![];
+        for path in paths {
+            let path = JsonPointerPath::try_from(path.as_ref())?;
+            let index = self.find_disclosure(&path)?;
+            if !selected.contains(&index) {
+                selected.push(index);
+            }
+        }
+        selected.sort_unstable();
+        Ok(self.select_disclosures(&selected))
+    }
+
+    /// Discloses the claims at those claims paths along with the parent disclosures they are nested
+    /// in. Unlike [Self::select], paths go through the values of nested disclosures. Fails with
+    /// [SdjError::MissingClaim] if any of them matches no claim.
+    pub fn select_paths(&self, paths: &[ClaimsPath]) -> SdjResult<Presentation<'_>> {
+        let mut selection = Selection::none();
+        for path in paths {
+            if path.find(&self.processed.claims).is_empty() {
+                return Err(SdjError::MissingClaim(path.clone()));
+            }
+            selection = selection.path(path.clone());
+        }
+        Ok(self.select_claims(&selection))
+    }
+
+    /// Discloses the claims picked by the [Selection] along with the parent disclosures they are
+    /// nested in
+    pub fn select_claims(&self, selection: &Selection) -> Presentation<'_> {
+        let mut selected = selection.select(&self.processed);
+        selected.sort_unstable();
+        selected.dedup();
+        self.select_disclosures(&selected)
+    }
+
+    /// Presents the disclosures at those positions
+    pub(crate) fn select_disclosures(&self, selected: &[usize]) -> Presentation<'_> {
+        Presentation {
+            jws: &self.sd_jwt.jws,
+            disclosures: selected.iter().map(|&i| &self.sd_jwt.disclosures[i]).collect(),
+            key_binding: None,
+        }
+    }
+
+    /// Finds the position of the disclosure whose digest is at this path in the payload
+    fn find_disclosure(&self, path: &JsonPointerPath) -> SdjResult<usize> {
+        let disclosures = self.sd_jwt.disclosures.iter().enumerate();
+        let mut found = match self.payload.try_find_disclosure(path)? {
+            DigestRef::Object(digests) => {
+                let key = path.last().ok_or(SdjError::UnknownDisclosure)?;
+                disclosures
+                    .filter(|(_, d)| d.name() == Some(key))
+                    .filter_map(|(i, d)| Some((i, d.hash().ok()?)))
+                    .filter(|(_, hash)| digests.iter().any(|digest| digest.as_str() == Some(hash.as_str())))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>()
+            }
+            DigestRef::ArrayItem(digest) => disclosures
+                .filter(|(_, d)| d.name().is_none())
+                .filter_map(|(i, d)| Some((i, d.hash().ok()?)))
+                .filter(|(_, hash)| hash.as_str() == digest)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+        };
+        found.pop().ok_or(SdjError::UnknownDisclosure)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions};
+    use jwt_simple::prelude::Ed25519KeyPair;
+
+    pub fn verified() -> VerifiedSDJwt {
+        verified_with_key().0
+    }
+
+    /// Along with the Issuer verifying key
+    pub fn verified_with_key() -> (VerifiedSDJwt, String) {
+        let input = json!({
+            "iss": "https://example.com/issuer",
+            "given_name": "John",
+            "family_name": "Doe",
+            "address": {"street_address": "123 Main St", "country": "US"},
+            "nationalities": ["US", "DE"]
+        });
+        let decisions = &[
+            "/given_name",
+            "/family_name",
+            "/address/country",
+            "/address",
+            "/nationalities/0",
+            "/nationalities/1",
+        ];
+        let mut issuer = Issuer::try_new().unwrap();
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(input, decisions, IssuerOptions::default())
+            .unwrap();
+        let issuer_pk = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let verified = VerifiedSDJwt::try_new(sd_jwt, JwsAlgorithm::Ed25519, &issuer_pk).unwrap();
+        (verified, issuer_pk)
+    }
+
+    #[test]
+    fn should_fail_when_signature_invalid() {
+        let sd_jwt = verified().into_sd_jwt();
+        let other_pk = Ed25519KeyPair::generate().public_key().to_pem();
+        assert!(matches!(
+            VerifiedSDJwt::try_new(sd_jwt, JwsAlgorithm::Ed25519, &other_pk).unwrap_err(),
+            SdjError::InvalidJwt
+        ));
+    }
+
+    #[test]
+    fn should_select_runtime_paths() {
+        let verified = verified();
+        // e.g. coming from a request
+        let paths = vec!["/given_name".to_string(), format!("/nationalities/{}", 1)];
+        let presentation = verified.select(&paths).unwrap();
+        assert_eq!(presentation.disclosures.len(), 2);
+        assert!(std::ptr::eq(presentation.jws, &verified.sd_jwt().jws));
+
+        let presentation = verified.select(["/family_name"]).unwrap();
+        assert!(matches!(&presentation.disclosures[..], [d] if d.name() == Some("family_name")));
+    }
+
+    #[test]
+    fn should_select_claims_paths() {
+        let (verified, issuer_pk) = verified_with_key();
+        let paths = [
+            r#"["address", "country"]"#.parse().unwrap(),
+            r#"["nationalities", null]"#.parse().unwrap(),
+        ];
+        let presentation = verified.select_paths(&paths).unwrap();
+        // through the disclosure of `address`
+        assert_eq!(presentation.disclosures.len(), 4);
+        let claims = crate::prelude::Verifier::verify(
+            &presentation.try_serialize().unwrap(),
+            JwsAlgorithm::Ed25519,
+            &issuer_pk,
+        )
+        .unwrap();
+        assert_eq!(
+            claims["address"],
+            json!({"street_address": "123 Main St", "country": "US"})
+        );
+        assert_eq!(claims["nationalities"], json!(["US", "DE"]));
+        assert!(claims.get("given_name").is_none());
+
+        let unknown = r#"["address", "region"]"#.parse::<ClaimsPath>().unwrap();
+        assert!(matches!(
+            verified.select_paths(std::slice::from_ref(&unknown)).unwrap_err(),
+            SdjError::MissingClaim(p) if p == unknown
+        ));
+    }
+
+    #[test]
+    fn should_fail_when_path_not_disclosable() {
+        let verified = verified();
+        assert!(verified.select(["/iss"]).is_err());
+        assert!(verified.select(["/unknown"]).is_err());
+    }
+
+    #[test]
+    fn should_select_claims() {
+        let verified = verified();
+        let presentation = verified.select_claims(&Selection::none().claim("country"));
+        assert_eq!(presentation.disclosures.len(), 2);
+        assert!(verified.select_claims(&Selection::none()).disclosures.is_empty());
+    }
+
+    #[test]
+    fn should_serialize_presentation() {
+        let verified = verified();
+        let presentation = verified.select_claims(&Selection::none());
+        let serialized = presentation.try_serialize().unwrap();
+        assert_eq!(serialized, format!("{}~", verified.sd_jwt().jws.as_str()));
+        assert!(serialized.parse::<SDJwt>().unwrap().disclosures.is_empty());
+
+        let presentation = verified.select(["/given_name"]).unwrap();
+        let serialized = presentation.try_serialize().unwrap();
+        let parsed = serialized.parse::<SDJwt>().unwrap();
+        assert_eq!(parsed.disclosures, presentation.to_sd_jwt().disclosures);
+        assert!(parsed.key_binding.is_none());
+    }
+}
@@ -1,5 +1,330 @@
-use crate::core::json_pointer::path::JsonPointerPath;
+use crate::{
+    core::{
+        dcql::claims_path::ClaimsPath,
+        json_path::JsonPath,
+        json_pointer::{escape, path::JsonPointerPath, visit},
+    },
+    error::{SdjError, SdjResult},
+};
+use serde_json::Value;
 
-/// Json Pointer path for marking claims as selectively disclosable.
+/// Json Pointer path for marking claims as selectively disclosable. Wildcard pointers e.g.
+/// `/nationalities/*`, JSONPath expressions e.g. `$..email` and claims paths e.g.
+/// `["degrees", null, "type"]` are expanded against the input, for arrays whose length is only
+/// known at issuance.
 #[derive(Debug, Clone, derive_more::AsRef)]
-pub struct Decisions<'a>(pub(crate) Vec<JsonPointerPath<'a>>);
+pub struct Decisions<'a>(pub(crate) Vec<Decision<'a>>);
+
+/// Marks the claims it matches in the input as selectively disclosable
+#[derive(Debug, Clone)]
+pub(crate) enum Decision<'a> {
+    /// e.g. `/address/street_address`
+    Pointer(JsonPointerPath<'a>),
+    /// Json pointer where `*` tokens match every member or item e.g. `/degrees/*/grade`. A member
+    /// actually named `*` is addressed with JSONPath instead e.g. `$['*']`
+    Wildcard(JsonPointerPath<'a>),
+    /// e.g. `$..email` or `$.addresses[*].city`
+    JsonPath(JsonPath),
+    /// e.g. `["degrees", null, "type"]`
+    ClaimsPath(ClaimsPath),
+    /// Every claim nested under the one at this Json pointer, recursively
+    Subtree(JsonPointerPath<'a>),
+}
+
+/// What to do with a decision matching no claim of the input
+#[derive(Debug, Copy, Clone, Default)]
+pub enum UnmatchedDecisions {
+    /// Issuance fails with [SdjError::UnmatchedDecision]
+    #[default]
+    Fail,
+    /// Issuance goes on, each unmatched decision being handed to this function e.g. for logging it
+    Warn(fn(&str)),
+}
+
+impl Decisions<'_> {
+    /// Json pointers of the claims matched by every decision, in order. Nested claims come before the
+    /// claims containing them, whatever the order of the decisions, so that they are disclosed
+    /// recursively.
+    pub(crate) fn try_expand(&self, input: &Value, unmatched: UnmatchedDecisions) -> SdjResult<Vec<String>> {
+        let mut pointers: Vec<String> = vec![];
+        for decision in &self.0 {
+            let mut matched = decision.expand(input);
+            if matched.is_empty() {
+                match unmatched {
+                    UnmatchedDecisions::Fail => return Err(SdjError::UnmatchedDecision(decision.to_string())),
+                    UnmatchedDecisions::Warn(warn) => warn(&decision.to_string()),
+                }
+            }
+            matched.sort_by_key(|pointer| std::cmp::Reverse(pointer.matches('/').count()));
+            for pointer in matched {
+                if pointers.contains(&pointer) {
+                    continue;
+                }
+                match pointers.iter().position(|p| pointer.starts_with(&format!("{p}/"))) {
+                    Some(i) => pointers.insert(i, pointer),
+                    None => pointers.push(pointer),
+                }
+            }
+        }
+        Ok(pointers)
+    }
+}
+
+impl Decision<'_> {
+    const WILDCARD: &'static str = "*";
+
+    fn expand(&self, input: &Value) -> Vec<String> {
+        match self {
+            Self::Pointer(pointer) => pointer
+                .resolve(input)
+                .map(|v| vec![(pointer.to_string(), v)])
+                .unwrap_or_default(),
+            Self::Wildcard(pointer) => pointer
+                .tokens()
+                .iter()
+                .fold(vec![(String::new(), input)], |nodes, token| {
+                    nodes
+                        .into_iter()
+                        .flat_map(|(path, node)| Self::children(path, node, token))
+                        .collect()
+                }),
+            Self::JsonPath(path) => path.find(input),
+            Self::ClaimsPath(path) => path.find(input),
+            Self::Subtree(pointer) => {
+                let mut nested = vec![];
+                if let Some(value) = pointer.resolve(input) {
+                    visit(value, pointer, &mut |path, _| nested.push(path.to_string()));
+                }
+                return nested;
+            }
+        }
+        .into_iter()
+        .map(|(pointer, _)| pointer)
+        .collect()
+    }
+
+    fn children<'v>(path: String, node: &'v Value, token: &str) -> Vec<(String, &'v Value)> {
+        use crate::core::json_pointer::path::Step;
+        match (node, token) {
+            (Value::Object(object), Self::WILDCARD) => object
+                .iter()
+                .map(|(k, v)| (format!("{path}/{}", escape(k)), v))
+                .collect(),
+            (Value::Array(items), Self::WILDCARD) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("{path}/{i}"), v))
+                .collect(),
+            _ => match JsonPointerPath::step(node, token) {
+                Some(Step::Key(key)) => node
+                    .get(key)
+                    .map(|v| (format!("{path}/{}", escape(key)), v))
+                    .into_iter()
+                    .collect(),
+                Some(Step::Index(i)) => node.get(i).map(|v| (format!("{path}/{i}"), v)).into_iter().collect(),
+                Some(Step::End) | None => vec![],
+            },
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Decision<'a> {
+    type Error = SdjError;
+
+    fn try_from(decision: &'a str) -> SdjResult<Self> {
+        if decision.starts_with('$') {
+            return Ok(Self::JsonPath(decision.parse()?));
+        }
+        if decision.starts_with('[') {
+            return Ok(Self::ClaimsPath(decision.parse()?));
+        }
+        Ok(Self::from_pointer(decision.try_into()?))
+    }
+}
+
+impl TryFrom<String> for Decision<'static> {
+    type Error = SdjError;
+
+    fn try_from(decision: String) -> SdjResult<Self> {
+        if decision.starts_with('$') {
+            return Ok(Self::JsonPath(decision.parse()?));
+        }
+        if decision.starts_with('[') {
+            return Ok(Self::ClaimsPath(decision.parse()?));
+        }
+        Ok(Self::from_pointer(decision.try_into()?))
+    }
+}
+
+/// What [crate::prelude::IssuanceBuilder::disclose] takes: a Json pointer, possibly with `*`
+/// wildcards, a JSONPath expression or a claims path, either as a string or a [ClaimsPath]
+pub trait IntoDecision: sealed::Sealed {}
+
+pub(crate) mod sealed {
+    use super::*;
+
+    /// Converts to a single decision, wrapped in the public [Decisions]
+    pub trait Sealed {
+        fn try_into_decisions(self) -> SdjResult<Decisions<'static>>;
+    }
+
+    impl Sealed for &str {
+        fn try_into_decisions(self) -> SdjResult<Decisions<'static>> {
+            self.to_string().try_into_decisions()
+        }
+    }
+
+    impl Sealed for &String {
+        fn try_into_decisions(self) -> SdjResult<Decisions<'static>> {
+            self.clone().try_into_decisions()
+        }
+    }
+
+    impl Sealed for String {
+        fn try_into_decisions(self) -> SdjResult<Decisions<'static>> {
+            Ok(Decisions(vec![self.try_into()?]))
+        }
+    }
+
+    impl Sealed for ClaimsPath {
+        fn try_into_decisions(self) -> SdjResult<Decisions<'static>> {
+            Ok(Decisions(vec![Decision::ClaimsPath(self)]))
+        }
+    }
+}
+
+impl IntoDecision for &str {}
+impl IntoDecision for &String {}
+impl IntoDecision for String {}
+impl IntoDecision for ClaimsPath {}
+
+impl<'a> Decision<'a> {
+    fn from_pointer(pointer: JsonPointerPath<'a>) -> Self {
+        if pointer.tokens().iter().any(|t| t == Self::WILDCARD) {
+            Self::Wildcard(pointer)
+        } else {
+            Self::Pointer(pointer)
+        }
+    }
+}
+
+impl std::fmt::Display for Decision<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pointer(pointer) | Self::Wildcard(pointer) | Self::Subtree(pointer) => write!(f, "{}", &**pointer),
+            Self::JsonPath(path) => write!(f, "{path}"),
+            Self::ClaimsPath(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions, JwsAlgorithm, Verifier};
+    use jwt_simple::prelude::Ed25519KeyPair;
+    use serde_json::json;
+
+    fn expand(decisions: &[&'static str], input: &Value) -> SdjResult<Vec<String>> {
+        let decisions = decisions.iter().map(|&d| d.try_into()).collect::<SdjResult<_>>()?;
+        Decisions(decisions).try_expand(input, UnmatchedDecisions::Fail)
+    }
+
+    fn input() -> Value {
+        json!({
+            "email": "john@example.com",
+            "nationalities": ["US", "DE", "FR"],
+            "degrees": [{"type": "BSc", "grade": "A"}, {"type": "MSc"}],
+            "addresses": [{"city": "Paris", "contact": {"email": "a@b.c"}}, {"city": "Berlin"}]
+        })
+    }
+
+    #[test]
+    fn should_expand_wildcards() {
+        assert_eq!(
+            expand(&["/nationalities/*"], &input()).unwrap(),
+            ["/nationalities/0", "/nationalities/1", "/nationalities/2"]
+        );
+        assert_eq!(expand(&["/degrees/*/grade"], &input()).unwrap(), ["/degrees/0/grade"]);
+        assert_eq!(
+            expand(&["/degrees/*/grade", "/degrees/*"], &input()).unwrap(),
+            ["/degrees/0/grade", "/degrees/0", "/degrees/1"]
+        );
+    }
+
+    #[test]
+    fn should_expand_json_paths() {
+        assert_eq!(
+            expand(&["$.addresses[*].city"], &input()).unwrap(),
+            ["/addresses/0/city", "/addresses/1/city"]
+        );
+        // nested claims first
+        assert_eq!(
+            expand(&["$..email"], &input()).unwrap(),
+            ["/addresses/0/contact/email", "/email"]
+        );
+        // matched twice
+        assert_eq!(expand(&["/email", "$.email"], &input()).unwrap(), ["/email"]);
+    }
+
+    #[test]
+    fn should_expand_claims_paths() {
+        assert_eq!(
+            expand(&[r#"["degrees", null, "type"]"#, r#"["nationalities", 1]"#], &input()).unwrap(),
+            ["/degrees/0/type", "/degrees/1/type", "/nationalities/1"]
+        );
+        assert!(matches!(
+            expand(&[r#"["nationalities", "1"]"#], &input()),
+            Err(SdjError::UnmatchedDecision(d)) if d == r#"["nationalities","1"]"#
+        ));
+    }
+
+    #[test]
+    fn should_report_unmatched_decisions() {
+        for decision in ["/unknown", "/degrees/*/unknown", "$..unknown", "/nationalities/-"] {
+            assert!(
+                matches!(expand(&[decision], &input()), Err(SdjError::UnmatchedDecision(d)) if d == decision),
+                "{decision}"
+            );
+        }
+
+        static WARNINGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(vec![]);
+        let decisions = Decisions(vec!["/email".try_into().unwrap(), "$..unknown".try_into().unwrap()]);
+        let warn = UnmatchedDecisions::Warn(|d| WARNINGS.lock().unwrap().push(d.to_string()));
+        assert_eq!(decisions.try_expand(&input(), warn).unwrap(), ["/email"]);
+        assert_eq!(*WARNINGS.lock().unwrap(), ["$..unknown"]);
+    }
+
+    #[test]
+    fn should_issue_with_patterns() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(
+                input(),
+                &["$..email", "/nationalities/*", "/degrees/*/grade", "$.addresses[*]"],
+                IssuerOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 8);
+
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let claims = Verifier::verify(&sd_jwt.try_serialize().unwrap(), JwsAlgorithm::Ed25519, &issuer_key).unwrap();
+        assert_eq!(claims, input());
+
+        let options = IssuerOptions {
+            unmatched_decisions: UnmatchedDecisions::Warn(|_| {}),
+            ..Default::default()
+        };
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(input(), &["/email", "/phone_number"], options)
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 1);
+        assert!(matches!(
+            issuer.try_generate_sd_jwt(input(), &["/phone_number"], IssuerOptions::default()),
+            Err(SdjError::UnmatchedDecision(d)) if d == "/phone_number"
+        ));
+    }
+}
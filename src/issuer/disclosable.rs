@@ -0,0 +1,157 @@
+use crate::{error::SdjResult, issuer::input::InputClaimSet};
+use serde_json::Value;
+
+/// Types knowing which of their claims are selectively disclosable, so that they can be issued
+/// without maintaining JSON pointers by hand. Usually derived with `#[derive(SelectivelyDisclosable)]`
+/// (requires the `derive` feature) by annotating fields with `#[sd]`, `#[sd(each)]` or `#[sd(recursive)]`.
+pub trait SelectivelyDisclosable: serde::Serialize {
+    /// Appends the JSON pointers of the selectively disclosable claims of `value`, the
+    /// serialization of `Self` located at `pointer`. Nested claims come before the claims
+    /// containing them, so that they are disclosed recursively.
+    fn sd_decisions(value: &Value, pointer: &str, decisions: &mut Vec<String>);
+
+    fn try_to_input_claim_set(&self) -> SdjResult<InputClaimSet<'static>> {
+        let input = serde_json::to_value(self)?;
+        let mut decisions = vec![];
+        Self::sd_decisions(&input, "", &mut decisions);
+        InputClaimSet::try_from_decisions(input, decisions)
+    }
+}
+
+impl<T: SelectivelyDisclosable> SelectivelyDisclosable for Option<T> {
+    fn sd_decisions(value: &Value, pointer: &str, decisions: &mut Vec<String>) {
+        if !value.is_null() {
+            T::sd_decisions(value, pointer, decisions)
+        }
+    }
+}
+
+impl<T: SelectivelyDisclosable> SelectivelyDisclosable for Box<T> {
+    fn sd_decisions(value: &Value, pointer: &str, decisions: &mut Vec<String>) {
+        T::sd_decisions(value, pointer, decisions)
+    }
+}
+
+impl<T: SelectivelyDisclosable> SelectivelyDisclosable for Vec<T> {
+    fn sd_decisions(value: &Value, pointer: &str, decisions: &mut Vec<String>) {
+        for (i, item) in value.as_array().into_iter().flatten().enumerate() {
+            T::sd_decisions(item, &format!("{pointer}/{i}"), decisions)
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::{Issuer, IssuerOptions, JwsAlgorithm, SelectivelyDisclosable, Verifier};
+    use jwt_simple::prelude::Ed25519KeyPair;
+    use serde_json::json;
+
+    #[derive(serde::Serialize, SelectivelyDisclosable)]
+    struct IdToken {
+        iss: String,
+        #[sd]
+        given_name: String,
+        #[sd]
+        #[serde(rename = "family/name")]
+        family_name: String,
+        #[sd(recursive)]
+        address: Address,
+        #[sd(each)]
+        nationalities: Vec<String>,
+        #[sd(each, recursive)]
+        previous_addresses: Vec<Address>,
+        #[sd]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        email: Option<String>,
+        #[serde(flatten)]
+        extra: Extra,
+        #[serde(skip)]
+        #[allow(dead_code)]
+        internal: u32,
+    }
+
+    #[derive(serde::Serialize, SelectivelyDisclosable)]
+    #[serde(rename_all = "camelCase")]
+    struct Address {
+        #[sd]
+        street_address: String,
+        country: String,
+    }
+
+    #[derive(serde::Serialize, SelectivelyDisclosable)]
+    struct Extra {
+        #[sd]
+        birthdate: String,
+    }
+
+    fn address(street_address: &str) -> Address {
+        Address {
+            street_address: street_address.to_string(),
+            country: "US".to_string(),
+        }
+    }
+
+    fn id_token() -> IdToken {
+        IdToken {
+            iss: "https://example.com/issuer".to_string(),
+            given_name: "John".to_string(),
+            family_name: "Doe".to_string(),
+            address: address("123 Main St"),
+            nationalities: vec!["US".to_string(), "DE".to_string()],
+            previous_addresses: vec![address("1 Old St")],
+            email: None,
+            extra: Extra {
+                birthdate: "1940-01-01".to_string(),
+            },
+            internal: 42,
+        }
+    }
+
+    #[test]
+    fn should_derive_decisions() {
+        let input = id_token().try_to_input_claim_set().unwrap();
+        let decisions = input
+            .decisions
+            .as_ref()
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decisions,
+            [
+                "/given_name",
+                "/family~1name",
+                "/address/streetAddress",
+                "/address",
+                "/nationalities/0",
+                "/nationalities/1",
+                "/previous_addresses/0/streetAddress",
+                "/previous_addresses/0",
+                "/birthdate",
+            ]
+        );
+        assert!(input.input.get("internal").is_none());
+    }
+
+    #[test]
+    fn should_issue_from_struct() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let sd_jwt = issuer
+            .try_generate_sd_jwt_from(&id_token(), IssuerOptions::default())
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 9);
+
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let claims = Verifier::verify(&sd_jwt.try_serialize().unwrap(), JwsAlgorithm::Ed25519, &issuer_key).unwrap();
+        assert_eq!(claims["family/name"], "Doe");
+        assert_eq!(
+            claims["address"],
+            json!({"streetAddress": "123 Main St", "country": "US"})
+        );
+        assert_eq!(claims["previous_addresses"][0]["streetAddress"], "1 Old St");
+        assert_eq!(claims["nationalities"], json!(["US", "DE"]));
+    }
+}
@@ -6,21 +6,12 @@ impl Disclosure {
     /// Creates a new object disclosure.
     pub fn try_new_object(backend: &mut CryptoBackend, name: String, value: serde_json::Value) -> SdjResult<Self> {
         let salt = backend.new_salt()?;
-        Ok(Self::Object {
-            salt,
-            name,
-            value,
-            hasher: core::marker::PhantomData,
-        })
+        Self::try_new(salt, Some(name), value)
     }
 
     /// Creates a new array disclosure.
     pub fn try_new_array(backend: &mut CryptoBackend, value: serde_json::Value) -> SdjResult<Self> {
         let salt = backend.new_salt()?;
-        Ok(Self::Array {
-            salt,
-            value,
-            hasher: core::marker::PhantomData,
-        })
+        Self::try_new(salt, None, value)
     }
 }
@@ -0,0 +1,441 @@
+use crate::{
+    core::{
+        claims::{is_always_visible, is_reserved},
+        json_pointer::escape,
+    },
+    error::{SdjError, SdjResult},
+    issuer::decisions::{Decision, Decisions},
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Which claims of the input are selectively disclosable, described as data rather than code.
+///
+/// As JSON, each object of the frame stands for an object or array of the input claims:
+/// * `_sd` lists its selectively disclosable members by name, or items by index
+/// * `_sd_decoy` is the number of decoy digests to add to it
+/// * any other member is the frame of its nested object or array with this name, or index
+///
+/// e.g. `{"_sd": ["given_name", "address"], "address": {"_sd": ["street_address"]}, "nationalities": {"_sd": [0], "_sd_decoy": 2}}`
+/// where `street_address` is disclosed recursively, in the disclosure of `address`.
+///
+/// It can also be read from YAML claims annotated like the reference implementation does, see
+/// [Self::try_from_yaml].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisclosureFrame {
+    sd: Vec<FrameEntry>,
+    decoys: usize,
+    nested: BTreeMap<String, DisclosureFrame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FrameEntry {
+    Name(String),
+    Index(usize),
+}
+
+impl DisclosureFrame {
+    const SD: &'static str = "_sd";
+    const DECOY: &'static str = "_sd_decoy";
+
+    /// Checks that the frame matches the input claims: every selectively disclosable member or item
+    /// and every nested frame exists, reserved claims are left alone and so are always visible ones at
+    /// the root.
+    pub fn try_validate(&self, input: &Value) -> SdjResult<()> {
+        self.try_validate_at(input, "")
+    }
+
+    fn try_validate_at(&self, value: &Value, pointer: &str) -> SdjResult<()> {
+        let invalid = |token: &str, reason: &str| {
+            SdjError::InvalidDisclosureFrame(format!("'{pointer}/{}' {reason}", escape(token)))
+        };
+        for entry in &self.sd {
+            match (entry, value) {
+                (FrameEntry::Name(name), Value::Object(object)) => {
+                    if is_reserved(name) {
+                        return Err(invalid(name, "is reserved"));
+                    }
+                    if pointer.is_empty() && is_always_visible(name) {
+                        return Err(invalid(name, "must always be visible"));
+                    }
+                    if !object.contains_key(name) {
+                        return Err(invalid(name, "is not a claim of the input"));
+                    }
+                }
+                (FrameEntry::Index(index), Value::Array(items)) if *index < items.len() => {}
+                (FrameEntry::Name(name), _) => return Err(invalid(name, "is not an object member")),
+                (FrameEntry::Index(index), _) => return Err(invalid(&index.to_string(), "is not an array item")),
+            }
+        }
+        if self.decoys > 0 && !value.is_object() && !value.is_array() {
+            return Err(SdjError::InvalidDisclosureFrame(format!(
+                "'{pointer}' can't have decoys, neither an object nor an array"
+            )));
+        }
+        for (token, nested) in &self.nested {
+            let child = match value {
+                Value::Object(object) => object.get(token),
+                Value::Array(items) => token.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            };
+            let child = child.ok_or_else(|| invalid(token, "is not a claim of the input"))?;
+            nested.try_validate_at(child, &format!("{pointer}/{}", escape(token)))?;
+        }
+        Ok(())
+    }
+
+    /// Json pointers of the selectively disclosable claims, nested claims first so that they are
+    /// disclosed recursively
+    pub fn try_decisions(&self) -> SdjResult<Decisions<'static>> {
+        let mut pointers = vec![];
+        self.collect("", &mut pointers, &mut BTreeMap::new());
+        let decisions = pointers
+            .into_iter()
+            .map(|pointer| Ok(Decision::Pointer(pointer.try_into()?)))
+            .collect::<SdjResult<_>>()?;
+        Ok(Decisions(decisions))
+    }
+
+    /// Number of decoy digests by Json pointer of the object or array they are added to, see
+    /// [crate::prelude::IssuerOptions::decoys]
+    pub fn decoys(&self) -> BTreeMap<String, usize> {
+        let mut decoys = BTreeMap::new();
+        self.collect("", &mut vec![], &mut decoys);
+        decoys
+    }
+
+    fn collect(&self, pointer: &str, pointers: &mut Vec<String>, decoys: &mut BTreeMap<String, usize>) {
+        for (token, nested) in &self.nested {
+            nested.collect(&format!("{pointer}/{}", escape(token)), pointers, decoys);
+        }
+        pointers.extend(self.sd.iter().map(|entry| match entry {
+            FrameEntry::Name(name) => format!("{pointer}/{}", escape(name)),
+            FrameEntry::Index(index) => format!("{pointer}/{index}"),
+        }));
+        if self.decoys > 0 {
+            decoys.insert(pointer.to_string(), self.decoys);
+        }
+    }
+}
+
+impl TryFrom<&Value> for DisclosureFrame {
+    type Error = SdjError;
+
+    fn try_from(frame: &Value) -> SdjResult<Self> {
+        let invalid = |reason: String| SdjError::InvalidDisclosureFrame(reason);
+        let object = frame
+            .as_object()
+            .ok_or_else(|| invalid(format!("expected an object, got {frame}")))?;
+        let mut this = Self::default();
+        for (key, value) in object {
+            match key.as_str() {
+                Self::SD => {
+                    let entries = value
+                        .as_array()
+                        .ok_or_else(|| invalid(format!("'{}' must be an array", Self::SD)))?;
+                    this.sd = entries
+                        .iter()
+                        .map(|entry| match entry {
+                            Value::String(name) => Ok(FrameEntry::Name(name.clone())),
+                            Value::Number(n) if n.is_u64() => {
+                                Ok(FrameEntry::Index(n.as_u64().unwrap_or_default() as usize))
+                            }
+                            _ => Err(invalid(format!("'{entry}' is neither a claim name nor an array index"))),
+                        })
+                        .collect::<SdjResult<_>>()?;
+                }
+                Self::DECOY => {
+                    let decoys = value
+                        .as_u64()
+                        .ok_or_else(|| invalid(format!("'{}' must be a positive integer", Self::DECOY)))?;
+                    this.decoys = decoys as usize;
+                }
+                _ => {
+                    this.nested.insert(key.clone(), value.try_into()?);
+                }
+            }
+        }
+        Ok(this)
+    }
+}
+
+/// From a JSON disclosure frame
+impl std::str::FromStr for DisclosureFrame {
+    type Err = SdjError;
+
+    fn from_str(s: &str) -> SdjResult<Self> {
+        let frame = serde_json::from_str::<Value>(s).map_err(|e| SdjError::InvalidDisclosureFrame(e.to_string()))?;
+        Self::try_from(&frame)
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl DisclosureFrame {
+    const SD_TAG: &'static str = "sd";
+
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Reads YAML claims where the selectively disclosable ones are tagged with `!sd`, like the
+    /// test cases of the reference implementation, then returns the claims and their frame. The
+    /// tag goes on the name of an object member (`!sd given_name: John`) or on an array item
+    /// (`- !sd DE`). A `_sd_decoy` member sets the number of decoys of its object.
+    pub fn try_from_yaml(yaml: &str) -> SdjResult<(Value, Self)> {
+        let yaml = serde_yaml::from_str(yaml).map_err(|e| SdjError::InvalidDisclosureFrame(e.to_string()))?;
+        Self::from_yaml(yaml, "")
+    }
+
+    fn from_yaml(yaml: serde_yaml::Value, pointer: &str) -> SdjResult<(Value, Self)> {
+        use serde_yaml::Value as Yaml;
+        let invalid = |reason: &str| SdjError::InvalidDisclosureFrame(format!("'{pointer}' {reason}"));
+        let untag = |yaml: Yaml| match yaml {
+            Yaml::Tagged(tagged) if tagged.tag == Self::SD_TAG => Ok((tagged.value, true)),
+            Yaml::Tagged(tagged) => Err(invalid(&format!("has an unknown tag {}", tagged.tag))),
+            yaml => Ok((yaml, false)),
+        };
+
+        let mut frame = Self::default();
+        let claims = match yaml {
+            Yaml::Mapping(mapping) => {
+                let mut claims = serde_json::Map::new();
+                for (key, value) in mapping {
+                    let (key, sd) = untag(key)?;
+                    let Yaml::String(key) = key else {
+                        return Err(invalid("has a member whose name is not a string"));
+                    };
+                    if key == Self::DECOY && !sd {
+                        let decoys = value
+                            .as_u64()
+                            .ok_or_else(|| invalid("has an invalid number of decoys"))?;
+                        frame.decoys = decoys as usize;
+                        continue;
+                    }
+                    if matches!(value, Yaml::Tagged(_)) {
+                        return Err(invalid(&format!(
+                            "has a tagged value for '{key}', tag its name instead"
+                        )));
+                    }
+                    let (claim, nested) = Self::from_yaml(value, &format!("{pointer}/{}", escape(&key)))?;
+                    if !nested.is_empty() {
+                        frame.nested.insert(key.clone(), nested);
+                    }
+                    if sd {
+                        frame.sd.push(FrameEntry::Name(key.clone()));
+                    }
+                    claims.insert(key, claim);
+                }
+                Value::Object(claims)
+            }
+            Yaml::Sequence(items) => {
+                let mut claims = vec![];
+                for (i, item) in items.into_iter().enumerate() {
+                    let (item, sd) = untag(item)?;
+                    let (claim, nested) = Self::from_yaml(item, &format!("{pointer}/{i}"))?;
+                    if !nested.is_empty() {
+                        frame.nested.insert(i.to_string(), nested);
+                    }
+                    if sd {
+                        frame.sd.push(FrameEntry::Index(i));
+                    }
+                    claims.push(claim);
+                }
+                Value::Array(claims)
+            }
+            Yaml::Tagged(_) => return Err(invalid("has a misplaced tag")),
+            scalar => serde_json::to_value(scalar)?,
+        };
+        Ok((claims, frame))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions, JwsAlgorithm, Verifier};
+    use jwt_simple::prelude::Ed25519KeyPair;
+    use serde_json::json;
+
+    fn input() -> Value {
+        json!({
+            "iss": "https://example.com/issuer",
+            "given_name": "John",
+            "address": {"street_address": "123 Main St", "country": "US"},
+            "nationalities": ["US", "DE"]
+        })
+    }
+
+    fn frame() -> DisclosureFrame {
+        r#"{
+            "_sd": ["given_name", "address"],
+            "_sd_decoy": 2,
+            "address": {"_sd": ["street_address"]},
+            "nationalities": {"_sd": [1], "_sd_decoy": 1}
+        }"#
+        .parse()
+        .unwrap()
+    }
+
+    fn pointers(frame: &DisclosureFrame) -> Vec<String> {
+        let decisions = frame.try_decisions().unwrap();
+        decisions.0.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn should_read_json_frame() {
+        let frame = frame();
+        frame.try_validate(&input()).unwrap();
+        assert_eq!(
+            pointers(&frame),
+            ["/address/street_address", "/nationalities/1", "/given_name", "/address"]
+        );
+        assert_eq!(
+            frame.decoys(),
+            BTreeMap::from([("".to_string(), 2), ("/nationalities".to_string(), 1)])
+        );
+
+        for invalid in [
+            r#"[]"#,
+            r#"{"_sd": "a"}"#,
+            r#"{"_sd": [-1]}"#,
+            r#"{"_sd_decoy": "2"}"#,
+            r#"{"a": 1}"#,
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<DisclosureFrame>(),
+                    Err(SdjError::InvalidDisclosureFrame(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_fail_when_frame_does_not_match_input() {
+        for (frame, reason) in [
+            (r#"{"_sd": ["email"]}"#, "'/email' is not a claim of the input"),
+            (r#"{"_sd": [0]}"#, "'/0' is not an array item"),
+            (
+                r#"{"nationalities": {"_sd": [2]}}"#,
+                "'/nationalities/2' is not an array item",
+            ),
+            (
+                r#"{"nationalities": {"_sd": ["US"]}}"#,
+                "'/nationalities/US' is not an object member",
+            ),
+            (
+                r#"{"address": {"region": {"_sd": ["a"]}}}"#,
+                "'/address/region' is not a claim of the input",
+            ),
+            (
+                r#"{"given_name": {"_sd_decoy": 1}}"#,
+                "'/given_name' can't have decoys, neither an object nor an array",
+            ),
+            (r#"{"_sd": ["iss"]}"#, "'/iss' must always be visible"),
+            (r#"{"address": {"_sd": ["_sd"]}}"#, "'/address/_sd' is reserved"),
+        ] {
+            let frame = frame.parse::<DisclosureFrame>().unwrap();
+            assert!(
+                matches!(frame.try_validate(&input()), Err(SdjError::InvalidDisclosureFrame(r)) if r == reason),
+                "{reason}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn should_read_yaml_claims() {
+        let yaml = r#"
+iss: https://example.com/issuer
+!sd given_name: John
+!sd address:
+  !sd street_address: 123 Main St
+  country: US
+nationalities:
+  - US
+  - !sd DE
+  - !sd
+    country: FR
+    !sd since: 1990
+_sd_decoy: 2
+"#;
+        let (claims, frame) = DisclosureFrame::try_from_yaml(yaml).unwrap();
+        let mut expected = input();
+        expected["nationalities"] = json!(["US", "DE", {"country": "FR", "since": 1990}]);
+        assert_eq!(claims, expected);
+        frame.try_validate(&claims).unwrap();
+        assert_eq!(
+            pointers(&frame),
+            [
+                "/address/street_address",
+                "/nationalities/2/since",
+                "/nationalities/1",
+                "/nationalities/2",
+                "/given_name",
+                "/address"
+            ]
+        );
+        assert_eq!(frame.decoys(), BTreeMap::from([("".to_string(), 2)]));
+
+        for invalid in ["a: !sd b", "!other a: b", "!sd 1: a", "- !sd [a]\n- !foo b"] {
+            assert!(
+                matches!(
+                    DisclosureFrame::try_from_yaml(invalid),
+                    Err(SdjError::InvalidDisclosureFrame(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_only_keep_always_visible_claims_at_the_root() {
+        let input = json!({
+            "iss": "https://example.com/issuer",
+            "exp": 1_900_000_000,
+            "vehicle": {"exp": "2030-01-01", "status": "registered"}
+        });
+        let frame = r#"{"vehicle": {"_sd": ["exp", "status"]}}"#.parse::<DisclosureFrame>().unwrap();
+        frame.try_validate(&input).unwrap();
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt_framed(input.clone(), &frame, IssuerOptions::default())
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 2);
+
+        let frame = r#"{"_sd": ["exp"]}"#.parse::<DisclosureFrame>().unwrap();
+        assert!(matches!(
+            frame.try_validate(&input),
+            Err(SdjError::InvalidDisclosureFrame(r)) if r == "'/exp' must always be visible"
+        ));
+    }
+
+    #[test]
+    fn should_issue_framed() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let sd_jwt = issuer
+            .try_generate_sd_jwt_framed(input(), &frame(), IssuerOptions::default())
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 4);
+
+        let payload = sd_jwt.jws.try_read_unverified_payload().unwrap();
+        // 2 claims & 2 decoys
+        assert_eq!(payload["_sd"].as_array().unwrap().len(), 4);
+        // 1 decoy item
+        assert_eq!(payload["nationalities"].as_array().unwrap().len(), 3);
+
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let claims = Verifier::verify(&sd_jwt.try_serialize().unwrap(), JwsAlgorithm::Ed25519, &issuer_key).unwrap();
+        assert_eq!(claims, input());
+
+        let frame = r#"{"_sd": ["email"]}"#.parse().unwrap();
+        assert!(matches!(
+            issuer.try_generate_sd_jwt_framed(input(), &frame, IssuerOptions::default()),
+            Err(SdjError::InvalidDisclosureFrame(_))
+        ));
+    }
+}
@@ -1,7 +1,12 @@
 use crate::{
-    core::disclosure::Disclosure, core::json_pointer::JsonPointer, crypto::CryptoBackend, error::SdjResult,
-    issuer::decisions::Decisions,
+    core::disclosure::Disclosure,
+    core::json_pointer::{path::JsonPointerPath, JsonPointer},
+    crypto::CryptoBackend,
+    error::{SdjError, SdjResult},
+    issuer::{decisions::Decisions, options::IssuerOptions},
 };
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
 
 pub struct InputClaimSet<'a> {
     /// Json input claims to selectively disclose
@@ -20,20 +25,55 @@ impl<'a> InputClaimSet<'a> {
         Ok(input)
     }
 
+    /// Like [Self::try_new] with owned JSON pointers, e.g. computed from the input
+    pub fn try_from_decisions(input: serde_json::Value, decisions: Vec<String>) -> SdjResult<InputClaimSet<'static>> {
+        let input = InputClaimSet {
+            input,
+            decisions: Decisions(decisions.into_iter().map(TryInto::try_into).collect::<SdjResult<_>>()?),
+        };
+        input.validate()?;
+        Ok(input)
+    }
+
     fn validate(&self) -> SdjResult<()> {
         Ok(())
     }
 
-    /// Selects (using Json pointer) the claims to selectively disclose and:
+    /// Selects (using Json pointer, wildcards or JSONPath) the claims to selectively disclose and:
     /// * remove them from the input
     /// * return them hashed
-    pub fn try_select_disclosures(&mut self, backend: &mut CryptoBackend) -> SdjResult<Vec<Disclosure>> {
-        self.decisions
-            .as_ref()
-            .iter()
-            .map(|path| self.input.try_find_drop(backend, path))
+    pub fn try_select_disclosures(
+        &mut self,
+        backend: &mut CryptoBackend,
+        options: &IssuerOptions,
+    ) -> SdjResult<Vec<Disclosure>> {
+        let pointers = self.decisions.try_expand(&self.input, options.unmatched_decisions)?;
+        // before any claim they are nested in gets disclosed
+        self.try_add_decoys(backend, &options.decoys)?;
+        pointers
+            .into_iter()
+            .map(|pointer| self.input.try_find_drop(backend, &pointer.try_into()?))
             .collect::<SdjResult<Vec<_>>>()
     }
+
+    fn try_add_decoys(&mut self, backend: &mut CryptoBackend, decoys: &BTreeMap<String, usize>) -> SdjResult<()> {
+        for (pointer, &count) in decoys {
+            let path = JsonPointerPath::try_from(pointer.as_str())?;
+            let not_found = || SdjError::InvalidJsonPointerPath(pointer.clone());
+            let digests = (0..count)
+                .map(|_| Ok(json!(backend.new_decoy_digest()?)))
+                .collect::<SdjResult<Vec<_>>>()?;
+            match path.resolve_mut(&mut self.input).ok_or_else(not_found)? {
+                Value::Object(object) => {
+                    let sd = object.entry("_sd").or_insert_with(|| json!([]));
+                    sd.as_array_mut().ok_or_else(not_found)?.extend(digests);
+                }
+                Value::Array(items) => items.extend(digests.into_iter().map(|digest| json!({"...": digest}))),
+                _ => return Err(not_found()),
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -49,12 +89,12 @@ pub mod tests {
             "b": 2
         });
         let mut ics = InputClaimSet::try_new(input, &["/a"]).unwrap();
-        let disclosures = ics.try_select_disclosures(&mut CryptoBackend::new()).unwrap();
+        let disclosures = ics
+            .try_select_disclosures(&mut CryptoBackend::new(), &IssuerOptions::default())
+            .unwrap();
         assert_eq!(disclosures.len(), 1);
-        let Disclosure::Object { name, value, .. } = disclosures.get(0).unwrap() else {
-            unimplemented!()
-        };
-        assert_eq!(name, "a");
-        assert_eq!(value, &json!(1));
+        let disclosure = disclosures.first().unwrap();
+        assert_eq!(disclosure.name(), Some("a"));
+        assert_eq!(disclosure.value(), &json!(1));
     }
 }
@@ -0,0 +1,219 @@
+use crate::{
+    core::json_pointer::path::JsonPointerPath,
+    error::SdjResult,
+    issuer::{
+        decisions::{Decision, Decisions, IntoDecision},
+        input::InputClaimSet,
+        options::IssuerOptions,
+        Issuer,
+    },
+    prelude::SDJwt,
+};
+use serde_json::Value;
+
+/// Issuance of a SD-JWT whose decisions are only known at runtime e.g. loaded from a
+/// configuration or a database. See [Issuer::issue].
+pub struct IssuanceBuilder<'a> {
+    issuer: &'a mut Issuer,
+    input: Value,
+    decisions: Vec<PendingDecision>,
+    options: IssuerOptions,
+}
+
+/// Invalid decisions fail when signing, so that decisions can be chained
+enum PendingDecision {
+    Decision(SdjResult<Decisions<'static>>),
+    Subtree(String),
+}
+
+impl Issuer {
+    /// Starts issuing a SD-JWT of those claims, none of them selectively disclosable until
+    /// disclosed with the returned [IssuanceBuilder]
+    pub fn issue(&mut self, claims: Value) -> IssuanceBuilder<'_> {
+        IssuanceBuilder {
+            issuer: self,
+            input: claims,
+            decisions: vec![],
+            options: IssuerOptions::default(),
+        }
+    }
+}
+
+impl<'a> IssuanceBuilder<'a> {
+    /// Makes the claims matched by this decision selectively disclosable. Either a Json pointer,
+    /// possibly with `*` wildcards, a JSONPath expression or a claims path e.g. a
+    /// [crate::prelude::ClaimsPath]. Decisions can come in any order: nested claims are always
+    /// disclosed recursively, in the disclosure of the claims containing them.
+    pub fn disclose(mut self, decision: impl IntoDecision) -> Self {
+        self.decisions
+            .push(PendingDecision::Decision(decision.try_into_decisions()));
+        self
+    }
+
+    /// [Self::disclose] every decision
+    pub fn disclose_each<D: IntoDecision>(mut self, decisions: impl IntoIterator<Item = D>) -> Self {
+        let decisions = decisions
+            .into_iter()
+            .map(|d| PendingDecision::Decision(d.try_into_decisions()));
+        self.decisions.extend(decisions);
+        self
+    }
+
+    /// Makes every claim & array item nested under the one at this Json pointer (e.g. `/address`)
+    /// selectively disclosable, recursively. The claim itself is not, unless also disclosed.
+    pub fn disclose_all_under(mut self, pointer: impl Into<String>) -> Self {
+        self.decisions.push(PendingDecision::Subtree(pointer.into()));
+        self
+    }
+
+    pub fn with_options(mut self, options: IssuerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Signs the SD-JWT. Fails if any decision is invalid or, unless
+    /// [IssuerOptions::unmatched_decisions] says otherwise, matches no claim.
+    pub fn try_sign(self) -> SdjResult<SDJwt> {
+        let mut decisions = vec![];
+        for decision in self.decisions {
+            match decision {
+                PendingDecision::Decision(decision) => decisions.extend(decision?.0),
+                PendingDecision::Subtree(pointer) => {
+                    decisions.push(Decision::Subtree(JsonPointerPath::try_from(pointer)?))
+                }
+            }
+        }
+        let input = InputClaimSet {
+            input: self.input,
+            decisions: Decisions(decisions),
+        };
+        self.issuer.try_generate(input, self.options)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::{ClaimsPath, Issuer, IssuerOptions, JwsAlgorithm, SdjError, UnmatchedDecisions, Verifier};
+    use jwt_simple::prelude::Ed25519KeyPair;
+    use serde_json::{json, Value};
+
+    fn input() -> Value {
+        json!({
+            "iss": "https://example.com/issuer",
+            "email": "john@example.com",
+            "address": {"street_address": "123 Main St", "geo": {"lat": 1.5, "lon": 2.5}},
+            "degrees": [{"type": "BSc"}, {"type": "MSc"}]
+        })
+    }
+
+    fn verify(issuer: &Issuer, sd_jwt: &crate::prelude::SDJwt) -> Value {
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        Verifier::verify(&sd_jwt.try_serialize().unwrap(), JwsAlgorithm::Ed25519, &issuer_key).unwrap()
+    }
+
+    fn disclosed(sd_jwt: &crate::prelude::SDJwt) -> Vec<String> {
+        let mut disclosed = sd_jwt.disclosures.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        disclosed.sort();
+        disclosed
+    }
+
+    #[test]
+    fn should_issue_runtime_decisions() {
+        let mut issuer = Issuer::try_new().unwrap();
+        // e.g. loaded from a configuration
+        let configured = vec!["/email".to_string(), "$.degrees[*]".to_string()];
+        let sd_jwt = issuer
+            .issue(input())
+            .disclose_each(&configured)
+            .disclose("[\"address\", \"street_address\"]".parse::<ClaimsPath>().unwrap())
+            .disclose("/address")
+            .try_sign()
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 5);
+        assert_eq!(verify(&issuer, &sd_jwt), input());
+    }
+
+    #[test]
+    fn should_disclose_all_under() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let sd_jwt = issuer
+            .issue(input())
+            .disclose_all_under("/address")
+            .disclose("/address")
+            .try_sign()
+            .unwrap();
+        let disclosed = disclosed(&sd_jwt);
+        assert_eq!(disclosed.len(), 5);
+        assert!(disclosed.iter().any(|d| d.contains("\"lat\"")));
+        assert!(disclosed.iter().any(|d| d.contains("\"geo\"")));
+        assert_eq!(verify(&issuer, &sd_jwt), input());
+
+        let sd_jwt = issuer.issue(input()).disclose_all_under("/degrees").try_sign().unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 4);
+        assert_eq!(verify(&issuer, &sd_jwt), input());
+    }
+
+    #[test]
+    fn should_disclose_in_any_order() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let nested_first = issuer
+            .issue(input())
+            .disclose_all_under("/address")
+            .disclose("/address")
+            .try_sign()
+            .unwrap();
+        let nested_last = issuer
+            .issue(input())
+            .disclose("/address")
+            .disclose_all_under("/address")
+            .try_sign()
+            .unwrap();
+        for sd_jwt in [&nested_first, &nested_last] {
+            assert_eq!(sd_jwt.disclosures.len(), 5);
+            let payload = sd_jwt.jws.try_read_unverified_payload().unwrap();
+            // only the digest of the address is in the payload, the others are in its disclosure
+            assert_eq!(payload["_sd"].as_array().unwrap().len(), 1);
+            assert!(payload.get("address").is_none());
+            assert_eq!(verify(&issuer, sd_jwt), input());
+        }
+
+        let sd_jwt = issuer
+            .issue(input())
+            .disclose("/address")
+            .disclose("/address/geo")
+            .disclose("$..lat")
+            .try_sign()
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 3);
+        assert_eq!(verify(&issuer, &sd_jwt), input());
+    }
+
+    #[test]
+    fn should_fail_when_decision_invalid() {
+        let mut issuer = Issuer::try_new().unwrap();
+        assert!(matches!(
+            issuer.issue(input()).disclose("email").try_sign(),
+            Err(SdjError::InvalidJsonPointerPath(p)) if p == "email"
+        ));
+        assert!(matches!(
+            issuer.issue(input()).disclose_all_under("/email").try_sign(),
+            Err(SdjError::UnmatchedDecision(p)) if p == "/email"
+        ));
+
+        let options = IssuerOptions {
+            unmatched_decisions: UnmatchedDecisions::Warn(|_| {}),
+            ..Default::default()
+        };
+        let sd_jwt = issuer
+            .issue(input())
+            .disclose("/phone_number")
+            .disclose("/email")
+            .with_options(options)
+            .try_sign()
+            .unwrap();
+        assert_eq!(sd_jwt.disclosures.len(), 1);
+    }
+}
@@ -1,20 +1,25 @@
 use crate::{
     core::jws::Jws,
-    crypto::CryptoBackend,
+    crypto::{salt_source::SaltSource, CryptoBackend},
     error::SdjResult,
     issuer::{options::IssuerOptions, payload::JwtPayload},
     prelude::SDJwt,
 };
+use disclosable::SelectivelyDisclosable;
+use frame::DisclosureFrame;
 use input::InputClaimSet;
 use jwt_simple::prelude::Ed25519KeyPair;
 
-mod decisions;
+pub mod decisions;
+pub mod disclosable;
 mod disclosure;
+pub mod frame;
 pub mod input;
+pub mod issuance;
 mod jws;
+pub mod oid4vci;
 pub mod options;
 mod payload;
-mod sd_jwt;
 
 pub struct Issuer {
     pub(crate) backend: CryptoBackend,
@@ -29,13 +34,59 @@ impl Issuer {
         Ok(Self { backend, signature_key })
     }
 
+    /// Replaces where the salts of the disclosures come from, a ChaCha20 CSPRNG seeded from the OS
+    /// by default. See [SaltSource] for which ones are fit for production.
+    pub fn with_salt_source(mut self, salt_source: impl SaltSource + Send + Sync + 'static) -> Self {
+        self.set_salt_source(salt_source);
+        self
+    }
+
+    /// See [Self::with_salt_source], e.g. to derive the salts of each credential from its own id
+    pub fn set_salt_source(&mut self, salt_source: impl SaltSource + Send + Sync + 'static) {
+        self.backend.set_salt_source(salt_source);
+    }
+
+    /// Number of random bytes in the salts of the disclosures, 16 by default. Issuance fails when
+    /// below that.
+    pub fn with_salt_size(mut self, salt_size: usize) -> Self {
+        self.backend.set_salt_size(salt_size);
+        self
+    }
+
     pub fn try_generate_sd_jwt(
         &mut self,
         input: serde_json::Value,
-        decisions: &'static [&'static str],
+        decisions: &[&str],
+        options: IssuerOptions,
+    ) -> SdjResult<SDJwt> {
+        self.try_generate(InputClaimSet::try_new(input, decisions)?, options)
+    }
+
+    /// Issues a SD-JWT from a struct, usually deriving [SelectivelyDisclosable], whose claims
+    /// annotated with `#[sd]` are selectively disclosable
+    pub fn try_generate_sd_jwt_from(
+        &mut self,
+        claims: &impl SelectivelyDisclosable,
         options: IssuerOptions,
     ) -> SdjResult<SDJwt> {
-        let input = InputClaimSet::try_new(input, decisions)?;
+        self.try_generate(claims.try_to_input_claim_set()?, options)
+    }
+
+    /// Issues a SD-JWT whose selectively disclosable claims and decoys are described by a
+    /// [DisclosureFrame], after checking that it matches the input
+    pub fn try_generate_sd_jwt_framed(
+        &mut self,
+        input: serde_json::Value,
+        frame: &DisclosureFrame,
+        mut options: IssuerOptions,
+    ) -> SdjResult<SDJwt> {
+        frame.try_validate(&input)?;
+        options.decoys.extend(frame.decoys());
+        let decisions = frame.try_decisions()?;
+        self.try_generate(InputClaimSet { input, decisions }, options)
+    }
+
+    fn try_generate(&mut self, input: InputClaimSet, options: IssuerOptions) -> SdjResult<SDJwt> {
         let (payload, disclosures) = JwtPayload::try_new(&mut self.backend, input, &options)?;
         let jws = Jws::try_new(payload, options.sign_alg, &self.signature_key)?;
         Ok(SDJwt {
@@ -0,0 +1,416 @@
+use crate::{
+    core::{
+        jwt::try_verify,
+        oid4vci::{
+            error::Oid4vciError, CredentialConfigurationSupported, CredentialIssuerMetadata, CredentialRequest,
+            CredentialResponse, IssuedCredential, NonceResponse, ProofClaims, ProvenKey, PROOF_JWT_TYP,
+        },
+    },
+    crypto::random::try_new_random_string,
+    error::SdjResult,
+    issuer::{options::IssuerOptions, Issuer},
+};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// How to issue the credentials of a `credential_configuration_id`
+#[derive(Debug, Clone)]
+pub struct CredentialConfiguration {
+    /// Published in the Credential Issuer metadata
+    pub metadata: CredentialConfigurationSupported,
+    /// Claims to make selectively disclosable, see [crate::prelude::IssuanceBuilder::disclose]
+    pub decisions: Vec<String>,
+    pub options: IssuerOptions,
+}
+
+/// The `c_nonce`s handed out to Wallets, each usable once before it expires
+#[derive(Debug, Default)]
+pub struct CNonces {
+    expirations: HashMap<String, u64>,
+}
+
+impl CNonces {
+    pub fn try_issue(&mut self, now: u64, lifetime: u64) -> SdjResult<String> {
+        self.expirations.retain(|_, expiration| *expiration > now);
+        let c_nonce = try_new_random_string()?;
+        self.expirations.insert(c_nonce.clone(), now + lifetime);
+        Ok(c_nonce)
+    }
+
+    /// Whether the nonce had been issued and had not expired. It can't be used anymore afterwards.
+    pub fn consume(&mut self, c_nonce: &str, now: u64) -> bool {
+        self.expirations
+            .remove(c_nonce)
+            .map(|expiration| expiration > now)
+            .unwrap_or_default()
+    }
+}
+
+/// The credential & nonce endpoints of an [OpenID4VCI](https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html)
+/// Credential Issuer. Transport is up to the caller, this only handles the protocol objects. Every
+/// method takes the current time, in seconds since the epoch.
+pub struct CredentialIssuer {
+    issuer: Issuer,
+    credential_issuer: String,
+    credential_endpoint: String,
+    nonce_endpoint: Option<String>,
+    configurations: BTreeMap<String, CredentialConfiguration>,
+    c_nonces: CNonces,
+    /// How long a `c_nonce` can be used, in seconds
+    pub c_nonce_lifetime: u64,
+    /// How old a key proof can be, in seconds
+    pub proof_max_age: u64,
+    /// Tolerated clock skew, in seconds
+    pub leeway: u64,
+}
+
+impl CredentialIssuer {
+    pub fn new(issuer: Issuer, credential_issuer: impl Into<String>, credential_endpoint: impl Into<String>) -> Self {
+        Self {
+            issuer,
+            credential_issuer: credential_issuer.into(),
+            credential_endpoint: credential_endpoint.into(),
+            nonce_endpoint: None,
+            configurations: BTreeMap::new(),
+            c_nonces: CNonces::default(),
+            c_nonce_lifetime: 300,
+            proof_max_age: 300,
+            leeway: 60,
+        }
+    }
+
+    /// Once set, every key proof has to carry a `c_nonce` obtained from this endpoint
+    pub fn with_nonce_endpoint(mut self, nonce_endpoint: impl Into<String>) -> Self {
+        self.nonce_endpoint = Some(nonce_endpoint.into());
+        self
+    }
+
+    pub fn with_configuration(mut self, id: impl Into<String>, configuration: CredentialConfiguration) -> Self {
+        self.configurations.insert(id.into(), configuration);
+        self
+    }
+
+    pub fn issuer(&self) -> &Issuer {
+        &self.issuer
+    }
+
+    /// What to serve at `/.well-known/openid-credential-issuer`
+    pub fn metadata(&self) -> CredentialIssuerMetadata {
+        CredentialIssuerMetadata {
+            credential_issuer: self.credential_issuer.clone(),
+            credential_endpoint: self.credential_endpoint.clone(),
+            nonce_endpoint: self.nonce_endpoint.clone(),
+            credential_configurations_supported: self
+                .configurations
+                .iter()
+                .map(|(id, c)| (id.clone(), c.metadata.clone()))
+                .collect(),
+        }
+    }
+
+    /// Answers a request to the nonce endpoint
+    pub fn try_issue_nonce(&mut self, now: u64) -> SdjResult<NonceResponse> {
+        let c_nonce = self.c_nonces.try_issue(now, self.c_nonce_lifetime)?;
+        Ok(NonceResponse { c_nonce })
+    }
+
+    /// Answers a request to the credential endpoint with one SD-JWT per key proof, each bound to
+    /// the proven key. `claims` are the claims of the End-User, `iss`, `iat` & `vct` are added.
+    /// Errors to return to the Wallet are [crate::prelude::SdjError::Oid4vciError].
+    pub fn try_issue(&mut self, request: &CredentialRequest, claims: Value, now: u64) -> SdjResult<CredentialResponse> {
+        let id = request
+            .credential_configuration_id
+            .as_ref()
+            .ok_or_else(|| Oid4vciError::InvalidCredentialRequest("missing credential_configuration_id".to_string()))?;
+        let configuration = self
+            .configurations
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Oid4vciError::UnknownCredentialConfiguration(id.clone()))?;
+
+        let mut claims = claims;
+        let object = claims
+            .as_object_mut()
+            .ok_or_else(|| Oid4vciError::InvalidCredentialRequest("claims are not a Json object".to_string()))?;
+        object.insert("iss".to_string(), json!(self.credential_issuer));
+        object.insert("iat".to_string(), json!(now));
+        if let Some(vct) = &configuration.metadata.vct {
+            object.insert("vct".to_string(), json!(vct));
+        }
+
+        let proofs = request.proof_jwts();
+        let keys = if configuration.metadata.cryptographic_binding_methods_supported.is_some() {
+            let proven = proofs
+                .into_iter()
+                .map(|jwt| self.try_verify_proof_jwt(jwt, &configuration.metadata, now))
+                .collect::<Result<Vec<_>, _>>()?;
+            // in a batch, all the proofs carry the same c_nonce
+            let nonce = proven
+                .first()
+                .ok_or_else(|| Oid4vciError::InvalidProof("missing proof".to_string()))?
+                .claims
+                .nonce
+                .clone();
+            if proven.iter().any(|p| p.claims.nonce != nonce) {
+                return Err(Oid4vciError::InvalidProof("proofs have different nonces".to_string()).into());
+            }
+            self.try_consume_nonce(nonce.as_deref(), now)?;
+            proven.into_iter().map(|p| Some(p.jwk)).collect()
+        } else {
+            vec![None]
+        };
+
+        let credentials = keys
+            .into_iter()
+            .map(|cnf| {
+                let options = IssuerOptions {
+                    cnf,
+                    ..configuration.options.clone()
+                };
+                let sd_jwt = self
+                    .issuer
+                    .issue(claims.clone())
+                    .disclose_each(&configuration.decisions)
+                    .with_options(options)
+                    .try_sign()?;
+                Ok(IssuedCredential {
+                    credential: sd_jwt.try_serialize()?,
+                })
+            })
+            .collect::<SdjResult<Vec<_>>>()?;
+
+        Ok(CredentialResponse {
+            credentials: Some(credentials),
+            ..Default::default()
+        })
+    }
+
+    /// Verifies a `openid4vci-proof+jwt` key proof and consumes its `c_nonce`
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-jwt-proof-type
+    pub fn try_verify_proof(
+        &mut self,
+        jwt: &str,
+        configuration: &CredentialConfigurationSupported,
+        now: u64,
+    ) -> Result<ProvenKey, Oid4vciError> {
+        let proven = self.try_verify_proof_jwt(jwt, configuration, now)?;
+        self.try_consume_nonce(proven.claims.nonce.as_deref(), now)?;
+        Ok(proven)
+    }
+
+    fn try_verify_proof_jwt(
+        &self,
+        jwt: &str,
+        configuration: &CredentialConfigurationSupported,
+        now: u64,
+    ) -> Result<ProvenKey, Oid4vciError> {
+        let invalid = |reason: &str| Oid4vciError::InvalidProof(reason.to_string());
+
+        let (header, _) = crate::core::jwt::try_decode_unverified(jwt).map_err(|_| invalid("not a JWT"))?;
+        if header.typ.as_deref() != Some(PROOF_JWT_TYP) {
+            return Err(invalid("unexpected typ"));
+        }
+        let accepted_algs = configuration
+            .proof_types_supported
+            .as_ref()
+            .and_then(|p| p.get("jwt"))
+            .map(|p| &p.proof_signing_alg_values_supported);
+        if accepted_algs
+            .map(|algs| !algs.contains(&header.alg))
+            .unwrap_or_default()
+        {
+            return Err(invalid("unsupported alg"));
+        }
+        let jwk = header
+            .jwk
+            .ok_or_else(|| invalid("only keys in a jwk header are supported"))?;
+        if jwk.try_alg().map_err(|_| invalid("unsupported key"))?.to_jws_alg() != header.alg {
+            return Err(invalid("key does not match alg"));
+        }
+        let public_key = jwk.try_to_public_pem().map_err(|_| invalid("invalid key"))?;
+        let (_, claims) = try_verify(jwt, &public_key).map_err(|_| invalid("invalid signature"))?;
+
+        let claims = serde_json::from_value::<ProofClaims>(claims).map_err(|_| invalid("invalid claims"))?;
+        if claims.aud != self.credential_issuer {
+            return Err(invalid("unexpected aud"));
+        }
+        if claims.iat > now + self.leeway || claims.iat + self.proof_max_age + self.leeway < now {
+            return Err(invalid("iat is out of range"));
+        }
+        Ok(ProvenKey { jwk, claims })
+    }
+
+    fn try_consume_nonce(&mut self, nonce: Option<&str>, now: u64) -> Result<(), Oid4vciError> {
+        if self.nonce_endpoint.is_none() {
+            return Ok(());
+        }
+        let nonce = nonce.ok_or(Oid4vciError::InvalidNonce)?;
+        if !self.c_nonces.consume(nonce, now) {
+            return Err(Oid4vciError::InvalidNonce);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        core::{
+            jwk::Jwk,
+            jwt::JwtHeader,
+            oid4vci::{ProofTypeSupported, Proofs},
+        },
+        prelude::{Holder, JwsAlgorithm, SDJwt, SdjError},
+    };
+    use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
+
+    const NOW: u64 = 1_700_000_000;
+    const ISSUER: &str = "https://issuer.example.com";
+
+    fn credential_issuer() -> CredentialIssuer {
+        let metadata = CredentialConfigurationSupported {
+            format: "dc+sd-jwt".to_string(),
+            vct: Some("https://credentials.example.com/identity_credential".to_string()),
+            cryptographic_binding_methods_supported: Some(vec!["jwk".to_string()]),
+            proof_types_supported: Some(BTreeMap::from([(
+                "jwt".to_string(),
+                ProofTypeSupported {
+                    proof_signing_alg_values_supported: vec!["ES256".to_string()],
+                },
+            )])),
+            ..Default::default()
+        };
+        let configuration = CredentialConfiguration {
+            metadata,
+            decisions: vec!["/given_name".to_string()],
+            options: IssuerOptions::default(),
+        };
+        CredentialIssuer::new(Issuer::try_new().unwrap(), ISSUER, format!("{ISSUER}/credential"))
+            .with_nonce_endpoint(format!("{ISSUER}/nonce"))
+            .with_configuration("pid", configuration)
+    }
+
+    fn proof(key_pair: &str, aud: &str, iat: u64, nonce: Option<String>) -> String {
+        let jwk = Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, key_pair).unwrap();
+        let header = JwtHeader::new(JwsAlgorithm::P256, PROOF_JWT_TYP).with_jwk(jwk);
+        let claims = ProofClaims {
+            iss: None,
+            aud: aud.to_string(),
+            iat,
+            nonce,
+        };
+        header.try_sign(&claims, key_pair).unwrap()
+    }
+
+    fn request(proofs: Vec<String>) -> CredentialRequest {
+        CredentialRequest {
+            credential_configuration_id: Some("pid".to_string()),
+            proofs: Some(Proofs { jwt: proofs }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_issue_key_bound_credentials() {
+        let mut credential_issuer = credential_issuer();
+        let metadata = credential_issuer.metadata();
+        assert_eq!(metadata.credential_issuer, ISSUER);
+        assert!(metadata.credential_configurations_supported.contains_key("pid"));
+
+        let key_pairs = [ES256KeyPair::generate(), ES256KeyPair::generate()].map(|k| k.to_pem().unwrap());
+        let c_nonce = credential_issuer.try_issue_nonce(NOW).unwrap().c_nonce;
+        let proofs = key_pairs
+            .iter()
+            .map(|kp| proof(kp, ISSUER, NOW, Some(c_nonce.clone())))
+            .collect();
+
+        let response = credential_issuer
+            .try_issue(&request(proofs), json!({"given_name": "John"}), NOW + 1)
+            .unwrap();
+        let credentials = response.credentials.unwrap();
+        assert_eq!(credentials.len(), 2);
+
+        let issuer_key = Ed25519KeyPair::from_pem(&credential_issuer.issuer().get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        for (credential, key_pair) in credentials.iter().zip(&key_pairs) {
+            let sd_jwt = credential.credential.parse::<SDJwt>().unwrap();
+            let verified = Holder::verify(sd_jwt, JwsAlgorithm::Ed25519, &issuer_key).unwrap();
+            let claims = verified.claims();
+            assert_eq!(claims["iss"], json!(ISSUER));
+            assert_eq!(claims["iat"], json!(NOW + 1));
+            assert_eq!(
+                claims["vct"],
+                json!("https://credentials.example.com/identity_credential")
+            );
+            assert_eq!(claims["given_name"], json!("John"));
+            let jwk = Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, key_pair).unwrap();
+            assert_eq!(claims["cnf"], json!({ "jwk": jwk }));
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_proofs() {
+        let mut credential_issuer = credential_issuer();
+        let key_pair = ES256KeyPair::generate().to_pem().unwrap();
+        let mut issue = |proof: String| {
+            let claims = json!({"given_name": "John"});
+            match credential_issuer.try_issue(&request(vec![proof]), claims, NOW) {
+                Err(SdjError::Oid4vciError(e)) => e.error_code().to_string(),
+                other => panic!("unexpected {other:?}"),
+            }
+        };
+
+        assert_eq!(issue(proof(&key_pair, ISSUER, NOW, None)), "invalid_nonce");
+        assert_eq!(
+            issue(proof(&key_pair, ISSUER, NOW, Some("unknown".to_string()))),
+            "invalid_nonce"
+        );
+        assert_eq!(
+            issue(proof(&key_pair, "https://other.example.com", NOW, None)),
+            "invalid_proof"
+        );
+        assert_eq!(issue(proof(&key_pair, ISSUER, NOW - 3600, None)), "invalid_proof");
+        assert_eq!(issue(proof(&key_pair, ISSUER, NOW + 3600, None)), "invalid_proof");
+        assert_eq!(issue("a.b.c".to_string()), "invalid_proof");
+    }
+
+    #[test]
+    fn should_not_accept_c_nonce_twice() {
+        let mut credential_issuer = credential_issuer();
+        let key_pair = ES256KeyPair::generate().to_pem().unwrap();
+        let c_nonce = credential_issuer.try_issue_nonce(NOW).unwrap().c_nonce;
+        let proof = proof(&key_pair, ISSUER, NOW, Some(c_nonce.clone()));
+        let claims = json!({"given_name": "John"});
+
+        assert!(credential_issuer
+            .try_issue(&request(vec![proof.clone()]), claims.clone(), NOW)
+            .is_ok());
+        assert!(matches!(
+            credential_issuer.try_issue(&request(vec![proof]), claims, NOW),
+            Err(SdjError::Oid4vciError(Oid4vciError::InvalidNonce))
+        ));
+
+        let expired = credential_issuer.try_issue_nonce(NOW).unwrap().c_nonce;
+        assert!(!credential_issuer
+            .c_nonces
+            .consume(&expired, NOW + credential_issuer.c_nonce_lifetime));
+    }
+
+    #[test]
+    fn should_reject_unknown_configuration() {
+        let mut credential_issuer = credential_issuer();
+        let request = CredentialRequest {
+            credential_configuration_id: Some("mdl".to_string()),
+            ..Default::default()
+        };
+        let err = credential_issuer.try_issue(&request, json!({}), NOW).unwrap_err();
+        let SdjError::Oid4vciError(err) = err else {
+            panic!("unexpected {err:?}")
+        };
+        assert_eq!(err.to_error_response().error, "unknown_credential_configuration");
+    }
+}
@@ -1,5 +1,8 @@
+use crate::core::jwk::Jwk;
 use crate::crypto::hash::HashAlgorithm;
+use crate::issuer::decisions::UnmatchedDecisions;
 use crate::prelude::JwsAlgorithm;
+use std::collections::BTreeMap;
 
 /// Configuration of the issued SD-JWT
 #[derive(Debug, Clone)]
@@ -8,6 +11,13 @@ pub struct IssuerOptions {
     pub hash_alg: HashAlgorithm,
     /// Signature algorithm of the JWS
     pub sign_alg: JwsAlgorithm,
+    /// Holder public key the SD-JWT is bound to, in the `cnf` claim
+    pub cnf: Option<Jwk>,
+    /// Whether issuance fails when a decision matches no claim of the input, the default
+    pub unmatched_decisions: UnmatchedDecisions,
+    /// Number of decoy digests to add to the object or array at each Json pointer of the input,
+    /// hiding how many claims it actually has. Decoy items are appended to arrays.
+    pub decoys: BTreeMap<String, usize>,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -16,6 +26,9 @@ impl Default for IssuerOptions {
         Self {
             hash_alg: Default::default(),
             sign_alg: Default::default(),
+            cnf: None,
+            unmatched_decisions: Default::default(),
+            decoys: Default::default(),
         }
     }
 }
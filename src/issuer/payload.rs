@@ -14,15 +14,15 @@ impl JwtPayload {
         mut input: InputClaimSet,
         options: &IssuerOptions,
     ) -> SdjResult<(JwtPayload, Vec<Disclosure>)> {
-        let disclosures = input.try_select_disclosures(backend)?;
+        let disclosures = input.try_select_disclosures(backend, options)?;
 
         let sd_alg = options.hash_alg.to_jwt_claim();
 
-        input
-            .input
-            .as_object_mut()
-            .ok_or(SdjError::ImplementationError)?
-            .insert("_sd_alg".to_string(), json!(sd_alg));
+        let claims = input.input.as_object_mut().ok_or(SdjError::ImplementationError)?;
+        claims.insert("_sd_alg".to_string(), json!(sd_alg));
+        if let Some(jwk) = &options.cnf {
+            claims.insert("cnf".to_string(), json!({ "jwk": jwk }));
+        }
 
         Ok((JwtPayload(input.input), disclosures))
     }
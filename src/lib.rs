@@ -1,10 +1,10 @@
 //! Selective Disclosure JWTs
 
-#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
+#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier", feature = "jwe"))]
 mod core;
-#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
+#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier", feature = "jwe"))]
 mod crypto;
-#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
+#[cfg(any(feature = "issuer", feature = "holder", feature = "verifier", feature = "jwe"))]
 mod error;
 
 #[cfg(feature = "holder")]
@@ -14,23 +14,104 @@ mod issuer;
 #[cfg(feature = "verifier")]
 mod verifier;
 
+// so that derived code referring to `::selective_disclosure_jwt` also compiles in this crate
+#[cfg(feature = "derive")]
+extern crate self as selective_disclosure_jwt;
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::core::claims::{is_always_visible, is_reserved};
+    pub use serde_json;
+}
+
 /// Marker trait for all instances involved in the flow
+#[allow(dead_code)]
 pub(crate) trait ThirdParty {}
 
 pub mod prelude {
-    #[cfg(any(feature = "issuer", feature = "holder", feature = "verifier"))]
+    #[cfg(any(feature = "issuer", feature = "holder", feature = "verifier", feature = "jwe"))]
     pub use crate::{
-        core::sd_jwt::SDJwt,
+        core::{
+            dcql::{
+                claims_path::ClaimsPath, error::DcqlError, ClaimsQuery, CredentialQuery, CredentialQueryMeta,
+                CredentialSetQuery, DcqlQuery,
+            },
+            jwk::{Jwk, JwkSet},
+            jwt::JwtHeader,
+            key_binding::KeyBindingClaims,
+            oid4vci::{
+                error::Oid4vciError, AuthorizationCodeGrant, CredentialConfigurationSupported, CredentialErrorResponse,
+                CredentialIssuerMetadata, CredentialOffer, CredentialRequest, CredentialResponse, Grants,
+                IssuedCredential, NonceResponse, PreAuthorizedCodeGrant, Proof, ProofClaims, ProofTypeSupported,
+                Proofs, ProvenKey,
+            },
+            oid4vp::{
+                error::Oid4vpError, AuthorizationRequest, AuthorizationResponse, ClientIdPrefix, ClientMetadata,
+                Confirmation, ResponseMode, TransactionData, VerifierAttestationClaims,
+            },
+            presentation_exchange::{
+                error::PresentationExchangeError, DescriptorMap, PresentationDefinition, PresentationSubmission,
+            },
+            sd_jwt::{SDJwt, SDJwtJson, SDJwtJsonHeader},
+        },
         crypto::{hash::HashAlgorithm, sign::JwsAlgorithm},
         error::{SdjError, SdjResult},
     };
 
+    #[cfg(feature = "jwe")]
+    pub use crate::core::jwe::{ContentEncryption, SDJwtSerialization};
+
+    #[cfg(feature = "issuer")]
+    pub use crate::crypto::salt_source::{
+        ChaCha20SaltSource, FixedSaltSource, HkdfSaltSource, OsSaltSource, SaltSource,
+    };
+
     #[cfg(feature = "issuer")]
-    pub use crate::issuer::{input::InputClaimSet, options::IssuerOptions, Issuer};
+    pub use crate::issuer::{
+        decisions::{IntoDecision, UnmatchedDecisions},
+        disclosable::SelectivelyDisclosable,
+        frame::DisclosureFrame,
+        input::InputClaimSet,
+        issuance::IssuanceBuilder,
+        oid4vci::{CNonces, CredentialConfiguration, CredentialIssuer},
+        options::IssuerOptions,
+        Issuer,
+    };
+
+    #[cfg(feature = "derive")]
+    pub use selective_disclosure_jwt_derive::SelectivelyDisclosable;
 
     #[cfg(feature = "holder")]
-    pub use crate::holder::Holder;
+    pub use crate::holder::{
+        dcql::{CredentialQueryEvaluation, DcqlEvaluation, DcqlMatch},
+        inspection::{ClaimNode, ClaimVisibility, Inspection, IssuerInconsistency},
+        key_binding::KeyBindingBuilder,
+        oid4vci::WalletClient,
+        oid4vp::{PresentationClient, VerifierTrust},
+        presentation::Presentation,
+        presentation_exchange::{DescriptorMatch, Evaluation},
+        preview::PresentationPreview,
+        selection::Selection,
+        store::{
+            memory::InMemoryCredentialStore, CredentialBackup, CredentialFilter, CredentialId, CredentialMetadata,
+            CredentialStore, StoredCredential,
+        },
+        transport::{HttpMethod, HttpRequest, HttpResponse, Transport},
+        verified::VerifiedSDJwt,
+        Holder,
+    };
+
+    #[cfg(feature = "file-store")]
+    pub use crate::holder::store::file::FileCredentialStore;
 
     #[cfg(feature = "verifier")]
-    pub use crate::verifier::Verifier;
+    pub use crate::verifier::{
+        key_binding::KeyBindingCheck,
+        nonce::{InMemoryNonceStore, NonceStore},
+        oid4vp::{CreatedRequest, PresentationQuery, PresentationVerifier, TrustedIssuers, VerifiedResponse},
+        provenance::{ClaimProvenance, Provenance, VerifiedClaims},
+        session::{InMemorySessionStore, Session, SessionStore},
+        Verifier,
+    };
 }
@@ -0,0 +1,208 @@
+use crate::{
+    core::{
+        jwk::Jwk,
+        jwt::try_verify,
+        key_binding::{try_sd_hash, try_transaction_data_hashes, KeyBindingClaims, KB_JWT_TYP},
+        oid4vp::TransactionData,
+    },
+    error::{SdjError, SdjResult},
+    prelude::SDJwt,
+};
+use serde_json::Value;
+
+/// What the Key Binding JWT of a presentation is expected to be bound to
+#[derive(Debug, Clone)]
+pub struct KeyBindingCheck<'a> {
+    /// The Verifier, in OpenID4VP its `client_id`
+    pub aud: &'a str,
+    pub nonce: &'a str,
+    pub now: u64,
+    /// How long after its `iat` a KB-JWT is accepted, in seconds
+    pub max_age: u64,
+    /// Tolerated clock skew, in seconds
+    pub leeway: u64,
+    /// The base64url encoded transaction data the presentation has to be bound to, as requested
+    pub transaction_data: &'a [String],
+}
+
+/// Verifies the KB-JWT of a presentation with the holder key of the (verified) payload
+///
+/// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-08.html#name-verification-by-the-verifier
+pub(crate) fn try_verify_key_binding(
+    presentation: &str,
+    sd_jwt: &SDJwt,
+    payload: &Value,
+    check: &KeyBindingCheck,
+) -> SdjResult<KeyBindingClaims> {
+    let invalid = |reason: &str| SdjError::InvalidKeyBinding(reason.to_string());
+
+    let kb_jwt = sd_jwt.key_binding.as_deref().ok_or_else(|| invalid("missing"))?;
+    let cnf = payload
+        .pointer("/cnf/jwk")
+        .ok_or_else(|| invalid("credential not bound to a key"))?;
+    let holder_key = serde_json::from_value::<Jwk>(cnf.clone()).map_err(|_| SdjError::InvalidJwk)?;
+    let (header, claims) = try_verify(kb_jwt, &holder_key.try_to_public_pem()?).map_err(|_| invalid("signature"))?;
+    if header.typ.as_deref() != Some(KB_JWT_TYP) {
+        return Err(invalid("unexpected typ"));
+    }
+    let claims: KeyBindingClaims = serde_json::from_value(claims).map_err(|_| invalid("claims"))?;
+
+    if claims.aud != check.aud {
+        return Err(invalid("unexpected aud"));
+    }
+    if claims.nonce != check.nonce {
+        return Err(invalid("unexpected nonce"));
+    }
+    match (&claims.transaction_data_hashes, check.transaction_data) {
+        (None, []) => {}
+        (Some(hashes), transaction_data) if !transaction_data.is_empty() => {
+            let alg = claims.transaction_data_hashes_alg.as_deref();
+            for encoded in transaction_data {
+                if !TransactionData::try_decode(encoded)?.accepts_hash_alg(alg.unwrap_or("sha-256")) {
+                    return Err(invalid("transaction_data_hashes_alg not accepted"));
+                }
+            }
+            let mut expected = try_transaction_data_hashes(transaction_data, alg)?;
+            let mut hashes = hashes.clone();
+            expected.sort();
+            hashes.sort();
+            if hashes != expected {
+                return Err(invalid("transaction_data_hashes do not match the request"));
+            }
+        }
+        _ => return Err(invalid("transaction_data_hashes do not match the request")),
+    }
+    if claims.iat > check.now + check.leeway || claims.iat + check.max_age + check.leeway < check.now {
+        return Err(invalid("expired or issued in the future"));
+    }
+    let unbound = presentation
+        .rsplit_once(SDJwt::DELIMITER)
+        .map(|(p, _)| p)
+        .unwrap_or_default();
+    if claims.sd_hash != try_sd_hash(&format!("{unbound}{}", SDJwt::DELIMITER), payload)? {
+        return Err(invalid("sd_hash does not match the presentation"));
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        holder::verified::VerifiedSDJwt,
+        prelude::{Issuer, IssuerOptions, JwsAlgorithm},
+    };
+    use jwt_simple::prelude::ES256KeyPair;
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn check() -> KeyBindingCheck<'static> {
+        KeyBindingCheck {
+            aud: "x509_san_dns:verifier.example.com",
+            nonce: "n-0S6_WzA2Mj",
+            now: NOW,
+            max_age: 300,
+            leeway: 60,
+            transaction_data: &[],
+        }
+    }
+
+    #[test]
+    fn should_verify_key_binding() {
+        let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+        let options = IssuerOptions {
+            cnf: Some(Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key).unwrap()),
+            ..Default::default()
+        };
+        let input = json!({"given_name": "John", "family_name": "Doe"});
+        let sd_jwt = Issuer::try_new()
+            .unwrap()
+            .try_generate_sd_jwt(input, &["/given_name", "/family_name"], options)
+            .unwrap();
+        let credential = VerifiedSDJwt::try_from_trusted(sd_jwt).unwrap();
+        let present = |aud: &str, nonce: &str, iat: u64| {
+            credential
+                .select(["/given_name"])
+                .unwrap()
+                .try_bind(JwsAlgorithm::P256, &holder_key, aud, nonce, iat)
+                .unwrap()
+                .try_serialize()
+                .unwrap()
+        };
+        let verify = |presentation: &str| {
+            let sd_jwt = presentation.parse::<SDJwt>().unwrap();
+            try_verify_key_binding(presentation, &sd_jwt, credential.payload(), &check())
+        };
+
+        let claims = verify(&present(check().aud, check().nonce, NOW - 10)).unwrap();
+        assert_eq!(claims.nonce, "n-0S6_WzA2Mj");
+
+        for invalid in [
+            present("x509_san_dns:other.example.com", check().nonce, NOW),
+            present(check().aud, "replayed", NOW),
+            present(check().aud, check().nonce, NOW - 3600),
+            present(check().aud, check().nonce, NOW + 3600),
+        ] {
+            assert!(matches!(verify(&invalid), Err(SdjError::InvalidKeyBinding(_))));
+        }
+
+        // a disclosure added after binding
+        let presentation = present(check().aud, check().nonce, NOW);
+        let (unbound, kb_jwt) = presentation.rsplit_once('~').unwrap();
+        let family_name = credential.select(["/family_name"]).unwrap().disclosures[0]
+            .build()
+            .unwrap();
+        let tampered = format!("{unbound}~{family_name}~{kb_jwt}");
+        assert!(matches!(verify(&tampered), Err(SdjError::InvalidKeyBinding(_))));
+
+        // transaction data
+        let transaction_data = |amount: &str| {
+            TransactionData {
+                r#type: "payment".to_string(),
+                credential_ids: vec!["pid".to_string()],
+                transaction_data_hashes_alg: None,
+                details: serde_json::Map::from_iter([("amount".to_string(), json!(amount))]),
+            }
+            .try_encode()
+            .unwrap()
+        };
+        let requested = [transaction_data("42.00 EUR")];
+        let bind = |transaction_data: &[String]| {
+            credential
+                .select(["/given_name"])
+                .unwrap()
+                .key_binding(check().aud, check().nonce, NOW)
+                .try_with_transaction_data(transaction_data)
+                .unwrap()
+                .try_sign(JwsAlgorithm::P256, &holder_key)
+                .unwrap()
+                .try_serialize()
+                .unwrap()
+        };
+        let verify_transaction = |presentation: &str, transaction_data: &[String]| {
+            let sd_jwt = presentation.parse::<SDJwt>().unwrap();
+            let check = KeyBindingCheck {
+                transaction_data,
+                ..check()
+            };
+            try_verify_key_binding(presentation, &sd_jwt, credential.payload(), &check)
+        };
+        let claims = verify_transaction(&bind(&requested), &requested).unwrap();
+        assert_eq!(claims.transaction_data_hashes.unwrap().len(), 1);
+        for (presented, requested) in [
+            (present(check().aud, check().nonce, NOW), &requested[..]),
+            (bind(&requested), &[][..]),
+            (bind(&[transaction_data("4200.00 EUR")]), &requested[..]),
+        ] {
+            assert!(matches!(
+                verify_transaction(&presented, requested),
+                Err(SdjError::InvalidKeyBinding(_))
+            ));
+        }
+
+        // no KB-JWT
+        let unbound = credential.select(["/given_name"]).unwrap().try_serialize().unwrap();
+        assert!(matches!(verify(&unbound), Err(SdjError::InvalidKeyBinding(_))));
+    }
+}
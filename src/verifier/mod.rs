@@ -1,3 +1,211 @@
+use crate::{
+    core::{
+        dcql::{claims_path::ClaimsPath, error::DcqlError, DcqlQuery},
+        processing::ProcessedClaims,
+    },
+    error::{SdjError, SdjResult},
+    prelude::{JwsAlgorithm, SDJwt},
+};
+use key_binding::{try_verify_key_binding, KeyBindingCheck};
+use nonce::NonceStore;
+use provenance::VerifiedClaims;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+pub mod key_binding;
+pub mod nonce;
+pub mod oid4vp;
+pub mod provenance;
+pub mod session;
+
 pub struct Verifier;
 
 impl crate::ThirdParty for Verifier {}
+
+impl Verifier {
+    /// Verifies the Issuer signature then returns the claims disclosed by the Holder
+    ///
+    /// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-05.html#section-6.1
+    pub fn verify(sd_jwt: &str, alg: JwsAlgorithm, issuer_verifying_key: &str) -> SdjResult<Value> {
+        let sd_jwt = sd_jwt.parse::<SDJwt>()?;
+        let payload = json!(sd_jwt.jws.try_read_payload(alg, issuer_verifying_key)?);
+        let processed = ProcessedClaims::try_verify(&payload, &sd_jwt.disclosures)?;
+        Ok(processed.claims)
+    }
+
+    /// Like [Self::verify], deserializing the disclosed claims into `T` and telling for each of
+    /// them whether it was disclosed, always visible, withheld or never issued
+    pub fn verify_typed<T: DeserializeOwned>(
+        sd_jwt: &str,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+    ) -> SdjResult<VerifiedClaims<T>> {
+        let sd_jwt = sd_jwt.parse::<SDJwt>()?;
+        let payload = json!(sd_jwt.jws.try_read_payload(alg, issuer_verifying_key)?);
+        VerifiedClaims::try_from_processed(ProcessedClaims::try_verify(&payload, &sd_jwt.disclosures)?)
+    }
+
+    /// Like [Self::verify], also checking that every required claims path (e.g. `["address", "country"]`)
+    /// matches a disclosed or always visible claim. Fails with [SdjError::MissingClaim] otherwise.
+    pub fn verify_required(
+        sd_jwt: &str,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+        required: &[ClaimsPath],
+    ) -> SdjResult<Value> {
+        let claims = Self::verify(sd_jwt, alg, issuer_verifying_key)?;
+        Self::try_require(&claims, required)?;
+        Ok(claims)
+    }
+
+    /// Checks that every required claims path matches one of the verified claims
+    pub fn try_require(claims: &Value, required: &[ClaimsPath]) -> SdjResult<()> {
+        match required.iter().find(|path| path.find(claims).is_empty()) {
+            Some(missing) => Err(SdjError::MissingClaim(missing.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Like [Self::verify], also verifying that the presentation ends with a Key Binding JWT
+    /// signed by the holder key in `cnf` for this Verifier & transaction
+    ///
+    /// See also: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-08.html#name-verification-by-the-verifier
+    pub fn verify_bound(
+        presentation: &str,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+        check: &KeyBindingCheck,
+    ) -> SdjResult<Value> {
+        Ok(Self::try_verify_bound(presentation, alg, issuer_verifying_key, check)?.claims)
+    }
+
+    /// Like [Self::verify_bound], deserializing the disclosed claims into `T` along with their
+    /// provenance, like [Self::verify_typed] does
+    pub fn verify_bound_typed<T: DeserializeOwned>(
+        presentation: &str,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+        check: &KeyBindingCheck,
+    ) -> SdjResult<VerifiedClaims<T>> {
+        VerifiedClaims::try_from_processed(Self::try_verify_bound(presentation, alg, issuer_verifying_key, check)?)
+    }
+
+    fn try_verify_bound(
+        presentation: &str,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+        check: &KeyBindingCheck,
+    ) -> SdjResult<ProcessedClaims> {
+        let sd_jwt = presentation.parse::<SDJwt>()?;
+        let payload = json!(sd_jwt.jws.try_read_payload(alg, issuer_verifying_key)?);
+        let processed = ProcessedClaims::try_verify(&payload, &sd_jwt.disclosures)?;
+        try_verify_key_binding(presentation, &sd_jwt, &payload, check)?;
+        Ok(processed)
+    }
+
+    /// Like [Self::verify_bound], also consuming the nonce of the KB-JWT so that the presentation
+    /// can't be replayed. Fails with [SdjError::ReplayedNonce] when the nonce was not issued by
+    /// `nonces`, has expired or was already used.
+    pub fn verify_bound_once(
+        presentation: &str,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+        check: &KeyBindingCheck,
+        nonces: &mut impl NonceStore,
+    ) -> SdjResult<Value> {
+        let claims = Self::verify_bound(presentation, alg, issuer_verifying_key, check)?;
+        if !nonces.consume(check.nonce, check.now)? {
+            return Err(SdjError::ReplayedNonce(check.nonce.to_string()));
+        }
+        Ok(claims)
+    }
+
+    /// Verifies every presentation of a `vp_token` answering a DCQL query, then checks that their
+    /// disclosed claims satisfy the query. Presentations of a credential query requiring
+    /// cryptographic holder binding must end with a KB-JWT passing the `check`. Returns the
+    /// disclosed claims by credential query id. Consuming the nonce is up to the caller, unlike
+    /// with a [oid4vp::PresentationVerifier].
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-response-parameters
+    pub fn verify_dcql(
+        vp_token: &Value,
+        query: &DcqlQuery,
+        alg: JwsAlgorithm,
+        issuer_verifying_key: &str,
+        check: &KeyBindingCheck,
+    ) -> SdjResult<BTreeMap<String, Vec<Value>>> {
+        let vp_token = vp_token.as_object().ok_or(SdjError::InvalidVpToken)?;
+        let mut presented = BTreeMap::new();
+        for (query_id, presentations) in vp_token {
+            let credential_query = query
+                .credential(query_id)
+                .ok_or_else(|| DcqlError::UnknownCredentialQuery(query_id.clone()))?;
+            let claims = presentations
+                .as_array()
+                .ok_or(SdjError::InvalidVpToken)?
+                .iter()
+                .map(|p| {
+                    let p = p.as_str().ok_or(SdjError::InvalidVpToken)?;
+                    if credential_query.require_cryptographic_holder_binding {
+                        Self::verify_bound(p, alg, issuer_verifying_key, check)
+                    } else {
+                        Self::verify(p, alg, issuer_verifying_key)
+                    }
+                })
+                .collect::<SdjResult<Vec<_>>>()?;
+            presented.insert(query_id.clone(), claims);
+        }
+        query.try_check(&presented)?;
+        Ok(presented)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions, VerifiedSDJwt};
+    use jwt_simple::prelude::Ed25519KeyPair;
+
+    #[test]
+    fn should_require_claims_paths() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let input = json!({
+            "iss": "https://example.com/issuer",
+            "address": {"street_address": "123 Main St", "country": "US"},
+            "degrees": [{"type": "BSc"}, {"type": "MSc"}]
+        });
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(
+                input,
+                &["/address/street_address", "/address", "/degrees/0", "/degrees/1"],
+                IssuerOptions::default(),
+            )
+            .unwrap();
+        let presentation = VerifiedSDJwt::try_from_trusted(sd_jwt)
+            .unwrap()
+            .select(["/address", "/degrees/1"])
+            .unwrap()
+            .try_serialize()
+            .unwrap();
+
+        let path = |p: &str| p.parse::<ClaimsPath>().unwrap();
+        let required = [
+            path(r#"["iss"]"#),
+            path(r#"["address", "country"]"#),
+            path(r#"["degrees", null, "type"]"#),
+        ];
+        let claims = Verifier::verify_required(&presentation, JwsAlgorithm::Ed25519, &issuer_key, &required).unwrap();
+        assert_eq!(claims["degrees"], json!([{"type": "MSc"}]));
+
+        let withheld = path(r#"["address", "street_address"]"#);
+        assert!(matches!(
+            Verifier::verify_required(&presentation, JwsAlgorithm::Ed25519, &issuer_key, std::slice::from_ref(&withheld)),
+            Err(SdjError::MissingClaim(p)) if p == withheld
+        ));
+    }
+}
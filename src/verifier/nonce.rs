@@ -0,0 +1,155 @@
+use crate::{crypto::random::try_new_random_string, error::SdjResult};
+use std::collections::HashMap;
+
+/// Single-use nonces the Verifier hands out for Key Binding JWTs, so that a presentation can't be
+/// replayed within its freshness window. Implementations backed by a shared store (e.g. Redis with
+/// `SET EX` & `GETDEL`) only have to provide [NonceStore::insert] & [NonceStore::consume].
+pub trait NonceStore {
+    /// Remembers a nonce until `expires_at`, in seconds since the epoch
+    fn insert(&mut self, nonce: String, expires_at: u64) -> SdjResult<()>;
+
+    /// Atomically removes a nonce. Returns whether it had been issued and had not expired, it can't
+    /// be consumed anymore afterwards.
+    fn consume(&mut self, nonce: &str, now: u64) -> SdjResult<bool>;
+
+    /// Issues a fresh nonce usable for `lifetime` seconds
+    fn try_issue(&mut self, now: u64, lifetime: u64) -> SdjResult<String> {
+        let nonce = try_new_random_string()?;
+        self.insert(nonce.clone(), now + lifetime)?;
+        Ok(nonce)
+    }
+}
+
+/// A non-persistent [NonceStore], evicting expired nonces whenever one is issued or consumed
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryNonceStore {
+    expirations: HashMap<String, u64>,
+}
+
+impl InMemoryNonceStore {
+    /// Number of nonces which have been issued but neither consumed nor evicted yet
+    pub fn len(&self) -> usize {
+        self.expirations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expirations.is_empty()
+    }
+
+    fn evict_expired(&mut self, now: u64) {
+        self.expirations.retain(|_, expires_at| *expires_at > now);
+    }
+}
+
+impl NonceStore for InMemoryNonceStore {
+    fn insert(&mut self, nonce: String, expires_at: u64) -> SdjResult<()> {
+        self.expirations.insert(nonce, expires_at);
+        Ok(())
+    }
+
+    fn consume(&mut self, nonce: &str, now: u64) -> SdjResult<bool> {
+        let consumed = self
+            .expirations
+            .remove(nonce)
+            .map(|expires_at| expires_at > now)
+            .unwrap_or_default();
+        self.evict_expired(now);
+        Ok(consumed)
+    }
+
+    fn try_issue(&mut self, now: u64, lifetime: u64) -> SdjResult<String> {
+        self.evict_expired(now);
+        let nonce = try_new_random_string()?;
+        self.insert(nonce.clone(), now + lifetime)?;
+        Ok(nonce)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        core::jwk::Jwk,
+        error::SdjError,
+        prelude::{Issuer, IssuerOptions, JwsAlgorithm, KeyBindingCheck, VerifiedSDJwt, Verifier},
+    };
+    use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn should_consume_nonce_once() {
+        let mut nonces = InMemoryNonceStore::default();
+        let nonce = nonces.try_issue(NOW, 60).unwrap();
+        assert!(!nonces.consume("unknown", NOW).unwrap());
+        assert!(nonces.consume(&nonce, NOW + 10).unwrap());
+        assert!(!nonces.consume(&nonce, NOW + 10).unwrap());
+
+        let expired = nonces.try_issue(NOW, 60).unwrap();
+        assert!(!nonces.consume(&expired, NOW + 60).unwrap());
+    }
+
+    #[test]
+    fn should_evict_expired_nonces() {
+        let mut nonces = InMemoryNonceStore::default();
+        for _ in 0..3 {
+            nonces.try_issue(NOW, 60).unwrap();
+        }
+        let fresh = nonces.try_issue(NOW + 30, 60).unwrap();
+        assert_eq!(nonces.len(), 4);
+        nonces.try_issue(NOW + 61, 60).unwrap();
+        assert_eq!(nonces.len(), 2);
+        assert!(nonces.consume(&fresh, NOW + 61).unwrap());
+        assert!(!nonces.consume("unknown", NOW + 200).unwrap());
+        assert!(nonces.is_empty());
+    }
+
+    #[test]
+    fn should_reject_replayed_presentation() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+        let options = IssuerOptions {
+            cnf: Some(Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key).unwrap()),
+            ..Default::default()
+        };
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(json!({"given_name": "John"}), &["/given_name"], options)
+            .unwrap();
+        let credential = VerifiedSDJwt::try_from_trusted(sd_jwt).unwrap();
+
+        let mut nonces = InMemoryNonceStore::default();
+        let nonce = nonces.try_issue(NOW, 300).unwrap();
+        let presentation = credential
+            .select(["/given_name"])
+            .unwrap()
+            .try_bind(
+                JwsAlgorithm::P256,
+                &holder_key,
+                "https://verifier.example.com",
+                &nonce,
+                NOW,
+            )
+            .unwrap()
+            .try_serialize()
+            .unwrap();
+        let check = KeyBindingCheck {
+            aud: "https://verifier.example.com",
+            nonce: &nonce,
+            now: NOW + 5,
+            max_age: 300,
+            leeway: 60,
+            transaction_data: &[],
+        };
+        let verify = |nonces: &mut InMemoryNonceStore| {
+            Verifier::verify_bound_once(&presentation, JwsAlgorithm::Ed25519, &issuer_key, &check, nonces)
+        };
+
+        assert_eq!(verify(&mut nonces).unwrap()["given_name"], "John");
+        assert!(matches!(verify(&mut nonces), Err(SdjError::ReplayedNonce(n)) if n == nonce));
+    }
+}
@@ -0,0 +1,910 @@
+#[cfg(feature = "jwe")]
+use crate::core::{
+    jwe::{try_decrypt, ContentEncryption},
+    jwk::{Jwk, JwkSet},
+};
+use crate::{
+    core::{
+        dcql::{error::DcqlError, DcqlQuery},
+        jwt::JwtHeader,
+        oid4vp::{
+            error::Oid4vpError, AuthorizationRequest, AuthorizationResponse, ClientIdPrefix, ClientMetadata,
+            ResponseMode, TransactionData, REQUEST_OBJECT_TYP,
+        },
+        presentation_exchange::PresentationDefinition,
+        processing::{is_ancestor_or_self, ProcessedClaims},
+        url::{encode_form, try_parse_query},
+    },
+    crypto::random::try_new_random_string,
+    error::{SdjError, SdjResult},
+    prelude::{JwsAlgorithm, SDJwt},
+    verifier::{
+        key_binding::{try_verify_key_binding, KeyBindingCheck},
+        provenance::{Provenance, VerifiedClaims},
+        session::{Session, SessionStore},
+    },
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// The Issuers a Verifier accepts credentials from
+pub trait TrustedIssuers {
+    /// The algorithm & PEM encoded verifying key of a trusted Issuer, by its `iss`
+    fn verifying_key(&self, iss: &str) -> Option<(JwsAlgorithm, String)>;
+}
+
+impl TrustedIssuers for BTreeMap<String, (JwsAlgorithm, String)> {
+    fn verifying_key(&self, iss: &str) -> Option<(JwsAlgorithm, String)> {
+        self.get(iss).cloned()
+    }
+}
+
+/// What a Verifier requests, in either query language
+#[derive(Debug, Clone)]
+pub enum PresentationQuery {
+    Dcql(DcqlQuery),
+    PresentationExchange(PresentationDefinition),
+}
+
+/// An authorization request to pass to the Wallet e.g. as a QR code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedRequest {
+    /// Identifies the session the response will be verified against
+    pub state: String,
+    pub uri: String,
+}
+
+/// The disclosed claims of a response, verified against its request
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedResponse {
+    pub state: String,
+    /// Disclosed claims of each presented credential, by credential query id with DCQL or by
+    /// input descriptor id with Presentation Exchange
+    pub claims: BTreeMap<String, Vec<Value>>,
+    /// Where the disclosed claims of each presented credential come from, in the same order
+    pub provenance: BTreeMap<String, Vec<Provenance>>,
+}
+
+impl VerifiedResponse {
+    /// Deserializes the disclosed claims of the credentials presented for this credential query or
+    /// input descriptor id into `T`, along with their provenance
+    pub fn try_typed<T: DeserializeOwned>(&self, id: &str) -> SdjResult<Vec<VerifiedClaims<T>>> {
+        let claims = self.claims.get(id).into_iter().flatten();
+        let provenance = self.provenance.get(id).into_iter().flatten();
+        claims
+            .zip(provenance)
+            .map(|(claims, provenance)| VerifiedClaims::try_new(claims.clone(), provenance.clone()))
+            .collect()
+    }
+}
+
+/// Disclosed claims of the presented credentials and their provenance, by credential query or
+/// input descriptor id
+type Presented = (BTreeMap<String, Vec<Value>>, BTreeMap<String, Vec<Provenance>>);
+
+struct RequestSigner {
+    alg: JwsAlgorithm,
+    key_pair: String,
+    /// e.g. `x5c` or a Verifier Attestation in `jwt`
+    header: Map<String, Value>,
+}
+
+/// The Verifier side of [OpenID4VP](https://openid.net/specs/openid-4-verifiable-presentations-1_0.html):
+/// creates authorization requests, remembers them in a [SessionStore], then verifies the responses
+/// posted with `direct_post` or, with the `jwe` feature, `direct_post.jwt`. Transport is up to the
+/// caller. Every method takes the current time, in seconds since the epoch.
+pub struct PresentationVerifier<S: SessionStore> {
+    client_id: String,
+    response_uri: String,
+    sessions: S,
+    signer: Option<RequestSigner>,
+    request_uri: Option<String>,
+    /// PEM encoded P-256 key pair the responses are encrypted to, along with its public JWK
+    #[cfg(feature = "jwe")]
+    encryption_key: Option<(String, Jwk)>,
+    /// Where the Wallet is invoked
+    pub authorization_endpoint: String,
+    /// How long a request can be answered, in seconds
+    pub session_lifetime: u64,
+    /// How old a KB-JWT can be, in seconds
+    pub kb_max_age: u64,
+    /// Tolerated clock skew, in seconds
+    pub leeway: u64,
+}
+
+impl<S: SessionStore> PresentationVerifier<S> {
+    /// `client_id` is prefixed by how the Wallet authenticates the Verifier e.g.
+    /// `x509_san_dns:verifier.example.com`. Requests are unsigned until a signer is set, which is
+    /// only allowed with the `redirect_uri` prefix.
+    pub fn new(client_id: impl Into<String>, response_uri: impl Into<String>, sessions: S) -> Self {
+        Self {
+            client_id: client_id.into(),
+            response_uri: response_uri.into(),
+            sessions,
+            signer: None,
+            request_uri: None,
+            #[cfg(feature = "jwe")]
+            encryption_key: None,
+            authorization_endpoint: "openid4vp://".to_string(),
+            session_lifetime: 600,
+            kb_max_age: 300,
+            leeway: 60,
+        }
+    }
+
+    /// Signs requests with a key the Wallet can tie to the `client_id`, conveyed by `header`
+    /// parameters e.g. its certificate chain in `x5c`
+    pub fn with_signer(mut self, alg: JwsAlgorithm, key_pair: impl Into<String>, header: Map<String, Value>) -> Self {
+        self.signer = Some(RequestSigner {
+            alg,
+            key_pair: key_pair.into(),
+            header,
+        });
+        self
+    }
+
+    /// Passes signed requests by reference, at `<request_uri>/<state>`. The request objects are
+    /// then served with [Self::request_object].
+    pub fn with_request_uri(mut self, request_uri: impl Into<String>) -> Self {
+        self.request_uri = Some(request_uri.into());
+        self
+    }
+
+    /// Has the Wallet encrypt its responses to this PEM encoded P-256 key pair, with `direct_post.jwt`
+    #[cfg(feature = "jwe")]
+    pub fn try_with_encryption_key(mut self, key_pair: impl Into<String>) -> SdjResult<Self> {
+        let key_pair = key_pair.into();
+        let jwk = Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &key_pair)?;
+        self.encryption_key = Some((key_pair, jwk));
+        Ok(self)
+    }
+
+    pub fn sessions(&self) -> &S {
+        &self.sessions
+    }
+
+    /// `direct_post.jwt` once an encryption key is set, along with the metadata for encrypting to it
+    #[cfg(feature = "jwe")]
+    fn response_mode(&self) -> (ResponseMode, Option<ClientMetadata>) {
+        let Some((_, jwk)) = &self.encryption_key else {
+            return (ResponseMode::DirectPost, None);
+        };
+        let client_metadata = ClientMetadata {
+            jwks: Some(JwkSet {
+                keys: vec![jwk.clone()],
+            }),
+            encrypted_response_enc_values_supported: Some(
+                [ContentEncryption::A128GCM, ContentEncryption::A256GCM]
+                    .map(|enc| enc.as_str().to_string())
+                    .to_vec(),
+            ),
+            vp_formats_supported: None,
+        };
+        (ResponseMode::DirectPostJwt, Some(client_metadata))
+    }
+
+    /// Responses are never encrypted without the `jwe` feature
+    #[cfg(not(feature = "jwe"))]
+    fn response_mode(&self) -> (ResponseMode, Option<ClientMetadata>) {
+        (ResponseMode::DirectPost, None)
+    }
+
+    #[cfg(feature = "jwe")]
+    fn try_decrypt_response(&self, jwe: &str) -> SdjResult<AuthorizationResponse> {
+        let (key_pair, _) = self.encryption_key.as_ref().ok_or(SdjError::ImplementationError)?;
+        let response = try_decrypt(jwe, JwsAlgorithm::P256, key_pair)?;
+        Ok(serde_json::from_slice(&response).map_err(|e| invalid_response(&e.to_string()))?)
+    }
+
+    #[cfg(not(feature = "jwe"))]
+    fn try_decrypt_response(&self, _: &str) -> SdjResult<AuthorizationResponse> {
+        Err(SdjError::FeatureRequired("direct_post.jwt", "jwe"))
+    }
+
+    /// Creates a request with a fresh `nonce` & `state` then saves it until it is answered or expires
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-authorization-request
+    pub fn try_create_request(&mut self, query: PresentationQuery, now: u64) -> SdjResult<CreatedRequest> {
+        self.try_create_transaction_request(query, &[], now)
+    }
+
+    /// Like [Self::try_create_request], also asking the Wallet to bind the presentations of the
+    /// credentials each transaction refers to, e.g. for authorizing a payment
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-transaction-data
+    pub fn try_create_transaction_request(
+        &mut self,
+        query: PresentationQuery,
+        transaction_data: &[TransactionData],
+        now: u64,
+    ) -> SdjResult<CreatedRequest> {
+        let prefix = ClientIdPrefix::try_split(&self.client_id)?.0;
+        match (prefix, &self.signer) {
+            (ClientIdPrefix::RedirectUri, Some(_)) => {
+                return Err(invalid_request("requests cannot be signed with redirect_uri").into())
+            }
+            (ClientIdPrefix::RedirectUri, None) | (_, Some(_)) => {}
+            (prefix, None) => {
+                let reason = format!("requests have to be signed with {}", prefix.as_str());
+                return Err(invalid_request(&reason).into());
+            }
+        }
+
+        let state = try_new_random_string()?;
+        let (dcql_query, presentation_definition) = match query {
+            PresentationQuery::Dcql(query) => (Some(query), None),
+            PresentationQuery::PresentationExchange(definition) => (None, Some(definition)),
+        };
+        let (response_mode, client_metadata) = self.response_mode();
+        let request = AuthorizationRequest {
+            client_id: self.client_id.clone(),
+            response_type: AuthorizationRequest::VP_TOKEN.to_string(),
+            response_mode: Some(response_mode),
+            response_uri: Some(self.response_uri.clone()),
+            redirect_uri: None,
+            nonce: try_new_random_string()?,
+            state: Some(state.clone()),
+            dcql_query,
+            presentation_definition,
+            client_metadata,
+            transaction_data: match transaction_data {
+                [] => None,
+                transaction_data => Some(
+                    transaction_data
+                        .iter()
+                        .map(TransactionData::try_encode)
+                        .collect::<Result<_, _>>()?,
+                ),
+            },
+        };
+        request.try_validate()?;
+
+        let request_object = self
+            .signer
+            .as_ref()
+            .map(|signer| {
+                let mut header = JwtHeader::new(signer.alg, REQUEST_OBJECT_TYP);
+                header.other = signer.header.clone();
+                header.try_sign(&request, &signer.key_pair)
+            })
+            .transpose()?;
+        let parameters = match (&request_object, &self.request_uri) {
+            (Some(_), Some(request_uri)) => vec![
+                ("client_id".to_string(), self.client_id.clone()),
+                ("request_uri".to_string(), format!("{request_uri}/{state}")),
+            ],
+            (Some(request_object), None) => vec![
+                ("client_id".to_string(), self.client_id.clone()),
+                ("request".to_string(), request_object.clone()),
+            ],
+            (None, _) => {
+                let Value::Object(parameters) = serde_json::to_value(&request)? else {
+                    return Err(SdjError::ImplementationError);
+                };
+                parameters
+                    .into_iter()
+                    .map(|(name, value)| match value {
+                        Value::String(value) => Ok((name, value)),
+                        value => Ok((name, serde_json::to_string(&value)?)),
+                    })
+                    .collect::<SdjResult<Vec<_>>>()?
+            }
+        };
+        let uri = format!(
+            "{}?{}",
+            self.authorization_endpoint,
+            encode_form(parameters.iter().map(|(n, v)| (n.as_str(), v.as_str())))
+        );
+
+        self.sessions.save(Session {
+            state: state.clone(),
+            request,
+            request_object,
+            expires_at: now + self.session_lifetime,
+        })?;
+        Ok(CreatedRequest { state, uri })
+    }
+
+    /// The request object to serve at the `request_uri` of a pending session
+    pub fn request_object(&self, state: &str, now: u64) -> SdjResult<Option<String>> {
+        let session = self.sessions.get(state)?.filter(|s| !s.is_expired(now));
+        Ok(session.and_then(|s| s.request_object))
+    }
+
+    /// Verifies the `application/x-www-form-urlencoded` body posted to the `response_uri` against
+    /// the session it answers, then closes the session whatever the outcome, so that a response
+    /// can't be replayed. Every presentation has to be signed by a trusted Issuer, bound to this
+    /// `client_id` & the session `nonce`, satisfy the query and disclose nothing beyond it.
+    ///
+    /// See also: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-response-mode-direct_post
+    pub fn try_verify_response(
+        &mut self,
+        body: &str,
+        issuers: &impl TrustedIssuers,
+        now: u64,
+    ) -> SdjResult<VerifiedResponse> {
+        let parameters = try_parse_query(&format!("?{body}"))?;
+        let parameter = |name: &str| parameters.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        let response = match (parameter("response"), self.response_mode().0) {
+            (Some(jwe), ResponseMode::DirectPostJwt) => self.try_decrypt_response(jwe)?,
+            (None, ResponseMode::DirectPost) => {
+                let vp_token = parameter("vp_token").ok_or_else(|| invalid_response("missing vp_token"))?;
+                let submission = parameter("presentation_submission")
+                    .map(serde_json::from_str)
+                    .transpose()
+                    .map_err(|_| invalid_response("presentation_submission"))?;
+                AuthorizationResponse {
+                    // a single presentation may be passed as is rather than as a Json string
+                    vp_token: serde_json::from_str(vp_token).unwrap_or_else(|_| vp_token.into()),
+                    presentation_submission: submission,
+                    state: parameter("state").map(str::to_string),
+                }
+            }
+            (Some(_), _) => return Err(invalid_response("unexpected encrypted response").into()),
+            (None, _) => return Err(invalid_response("expected an encrypted response").into()),
+        };
+
+        let state = response
+            .state
+            .as_deref()
+            .ok_or_else(|| invalid_response("missing state"))?;
+        let session = self
+            .sessions
+            .remove(state)?
+            .ok_or_else(|| Oid4vpError::UnknownState(state.to_string()))?;
+        if session.is_expired(now) {
+            return Err(Oid4vpError::ExpiredSession(session.state).into());
+        }
+        let check = KeyBindingCheck {
+            aud: &session.request.client_id,
+            nonce: &session.request.nonce,
+            now,
+            max_age: self.kb_max_age,
+            leeway: self.leeway,
+            transaction_data: &[],
+        };
+        let request = &session.request;
+        let (claims, provenance) = match (&request.dcql_query, &request.presentation_definition) {
+            (Some(query), _) => try_verify_dcql(&response, request, query, issuers, &check)?,
+            (None, Some(definition)) => {
+                try_verify_presentation_exchange(&response, request, definition, issuers, &check)?
+            }
+            (None, None) => return Err(SdjError::ImplementationError),
+        };
+        for encoded in request.transaction_data.iter().flatten() {
+            let transaction_data = TransactionData::try_decode(encoded)?;
+            if !transaction_data.credential_ids.iter().any(|id| claims.contains_key(id)) {
+                return Err(invalid_response("transaction_data not bound to any presentation").into());
+            }
+        }
+        Ok(VerifiedResponse {
+            state: session.state,
+            claims,
+            provenance,
+        })
+    }
+}
+
+fn invalid_request(reason: &str) -> Oid4vpError {
+    Oid4vpError::InvalidRequest(reason.to_string())
+}
+
+fn invalid_response(reason: &str) -> Oid4vpError {
+    Oid4vpError::InvalidResponse(reason.to_string())
+}
+
+fn try_verify_dcql(
+    response: &AuthorizationResponse,
+    request: &AuthorizationRequest,
+    query: &DcqlQuery,
+    issuers: &impl TrustedIssuers,
+    check: &KeyBindingCheck,
+) -> SdjResult<Presented> {
+    let vp_token = response.vp_token.as_object().ok_or(SdjError::InvalidVpToken)?;
+    let (mut presented, mut provenance) = (BTreeMap::new(), BTreeMap::new());
+    for (query_id, presentations) in vp_token {
+        let credential_query = query
+            .credential(query_id)
+            .ok_or_else(|| DcqlError::UnknownCredentialQuery(query_id.clone()))?;
+        let (mut claims, mut provenances) = (vec![], vec![]);
+        for presentation in presentations.as_array().ok_or(SdjError::InvalidVpToken)? {
+            let presentation = presentation.as_str().ok_or(SdjError::InvalidVpToken)?;
+            let transaction_data = request.try_transaction_data(query_id)?;
+            let binding = credential_query.require_cryptographic_holder_binding || !transaction_data.is_empty();
+            let check = KeyBindingCheck {
+                transaction_data: &transaction_data,
+                ..check.clone()
+            };
+            let processed = try_verify_presentation(presentation, issuers, &check, binding)?;
+            let requested = credential_query.try_match(&processed.claims)?;
+            try_check_disclosed(query_id, &processed, &requested, true)?;
+            provenances.push(Provenance::new(&processed));
+            claims.push(processed.claims);
+        }
+        presented.insert(query_id.clone(), claims);
+        provenance.insert(query_id.clone(), provenances);
+    }
+    query.try_check(&presented)?;
+    Ok((presented, provenance))
+}
+
+fn try_verify_presentation_exchange(
+    response: &AuthorizationResponse,
+    request: &AuthorizationRequest,
+    definition: &PresentationDefinition,
+    issuers: &impl TrustedIssuers,
+    check: &KeyBindingCheck,
+) -> SdjResult<Presented> {
+    let submission = response
+        .presentation_submission
+        .as_ref()
+        .ok_or_else(|| invalid_response("missing presentation_submission"))?;
+    if submission.definition_id != definition.id {
+        return Err(invalid_response("presentation_submission of another presentation definition").into());
+    }
+
+    let mut presented = BTreeMap::<String, Vec<Value>>::new();
+    let mut provenance = BTreeMap::<String, Vec<Provenance>>::new();
+    for descriptor_map in &submission.descriptor_map {
+        let descriptor = definition
+            .input_descriptor(&descriptor_map.id)
+            .ok_or_else(|| invalid_response(&format!("unknown input descriptor {}", descriptor_map.id)))?;
+        if !PresentationDefinition::SD_JWT_FORMATS.contains(&descriptor_map.format.as_str()) {
+            return Err(invalid_response(&format!("unsupported format {}", descriptor_map.format)).into());
+        }
+        let presentation = match (descriptor_map.path.as_str(), &response.vp_token) {
+            ("$", Value::String(presentation)) => Some(presentation.as_str()),
+            (path, Value::Array(presentations)) => path
+                .strip_prefix("$[")
+                .and_then(|p| p.strip_suffix(']'))
+                .and_then(|i| i.parse::<usize>().ok())
+                .and_then(|i| presentations.get(i)?.as_str()),
+            _ => None,
+        }
+        .ok_or(SdjError::InvalidVpToken)?;
+
+        let transaction_data = request.try_transaction_data(&descriptor.id)?;
+        let check = KeyBindingCheck {
+            transaction_data: &transaction_data,
+            ..check.clone()
+        };
+        let processed = try_verify_presentation(presentation, issuers, &check, true)?;
+        let requested = descriptor.try_match_claims(&processed.claims)?;
+        try_check_disclosed(&descriptor.id, &processed, &requested, !descriptor.limits_disclosure())?;
+        provenance
+            .entry(descriptor.id.clone())
+            .or_default()
+            .push(Provenance::new(&processed));
+        presented
+            .entry(descriptor.id.clone())
+            .or_default()
+            .push(processed.claims);
+    }
+
+    if let Some(missing) = definition
+        .input_descriptors
+        .iter()
+        .find(|d| !presented.contains_key(&d.id))
+    {
+        return Err(invalid_response(&format!("no presentation for input descriptor {}", missing.id)).into());
+    }
+    Ok((presented, provenance))
+}
+
+/// Verifies the Issuer signature, the disclosures and, when present or `binding` is required, the KB-JWT
+fn try_verify_presentation(
+    presentation: &str,
+    issuers: &impl TrustedIssuers,
+    check: &KeyBindingCheck,
+    binding: bool,
+) -> SdjResult<ProcessedClaims> {
+    let sd_jwt = presentation.parse::<SDJwt>()?;
+    let unverified = sd_jwt.jws.try_read_unverified_payload()?;
+    let iss = unverified.get("iss").and_then(Value::as_str).unwrap_or_default();
+    let (alg, issuer_verifying_key) = issuers
+        .verifying_key(iss)
+        .ok_or_else(|| Oid4vpError::UntrustedIssuer(iss.to_string()))?;
+    let payload = json!(sd_jwt.jws.try_read_payload(alg, &issuer_verifying_key)?);
+    let processed = ProcessedClaims::try_verify(&payload, &sd_jwt.disclosures)?;
+    if binding || sd_jwt.key_binding.is_some() {
+        try_verify_key_binding(presentation, &sd_jwt, &payload, check)?;
+    }
+    Ok(processed)
+}
+
+/// Checks that every disclosed claim is either one of the requested claims, one of their
+/// ancestors or, when `nested` is allowed, nested in one of them. Hence nothing may be disclosed
+/// when no claim is requested.
+fn try_check_disclosed(
+    id: &str,
+    processed: &ProcessedClaims,
+    requested: &[String],
+    nested: bool,
+) -> Result<(), Oid4vpError> {
+    let excessive = processed.disclosures.iter().find(|disclosure| {
+        !requested
+            .iter()
+            .any(|r| is_ancestor_or_self(&disclosure.path, r) || (nested && is_ancestor_or_self(r, &disclosure.path)))
+    });
+    match excessive {
+        Some(disclosure) => Err(Oid4vpError::ExcessiveDisclosure {
+            id: id.to_string(),
+            path: disclosure.path.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        core::x509::tests::{CERTIFICATE, KEY_PAIR},
+        holder::transport::{HttpMethod, HttpRequest, HttpResponse, Transport},
+        prelude::{InMemorySessionStore, Issuer, IssuerOptions, PresentationClient, VerifiedSDJwt, VerifierTrust},
+        verifier::provenance::ClaimProvenance,
+    };
+    use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
+    use std::cell::RefCell;
+
+    const NOW: u64 = 1_700_000_000;
+    const ISSUER: &str = "https://issuer.example.com";
+    const RESPONSE_URI: &str = "https://verifier.example.com/post";
+    const REQUEST_URI: &str = "https://verifier.example.com/request";
+
+    struct Trust;
+
+    impl VerifierTrust for Trust {
+        fn is_trusted_chain(&self, chain: &[Vec<u8>]) -> bool {
+            chain.first() == base64_simd::STANDARD.decode_to_vec(CERTIFICATE).ok().as_ref()
+        }
+
+        fn attestation_issuer_key(&self, _: &str) -> Option<(JwsAlgorithm, String)> {
+            None
+        }
+    }
+
+    /// Serves the request objects of the Verifier then records the posted responses
+    #[derive(Default)]
+    struct MockEndpoints {
+        request_objects: BTreeMap<String, String>,
+        posted: RefCell<Vec<String>>,
+    }
+
+    impl Transport for &MockEndpoints {
+        fn send(&self, request: HttpRequest) -> SdjResult<HttpResponse> {
+            let (status, body) = match (request.method, request.url.as_str()) {
+                (HttpMethod::Get, url) if self.request_objects.contains_key(url) => {
+                    (200, self.request_objects[url].clone().into_bytes())
+                }
+                (HttpMethod::Post, RESPONSE_URI) => {
+                    let body = String::from_utf8(request.body.unwrap_or_default()).unwrap();
+                    self.posted.borrow_mut().push(body);
+                    (200, b"{}".to_vec())
+                }
+                _ => (404, vec![]),
+            };
+            Ok(HttpResponse {
+                status,
+                headers: vec![],
+                body,
+            })
+        }
+    }
+
+    /// A credential bound to the returned holder key, along with the trusted Issuer
+    fn credential() -> (VerifiedSDJwt, String, BTreeMap<String, (JwsAlgorithm, String)>) {
+        let input = json!({"iss": ISSUER, "given_name": "John", "family_name": "Doe"});
+        credential_of(input, &["/given_name", "/family_name"])
+    }
+
+    fn credential_of(
+        input: Value,
+        decisions: &[&str],
+    ) -> (VerifiedSDJwt, String, BTreeMap<String, (JwsAlgorithm, String)>) {
+        let mut issuer = Issuer::try_new().unwrap();
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+        let options = IssuerOptions {
+            cnf: Some(Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key).unwrap()),
+            ..Default::default()
+        };
+        let sd_jwt = issuer.try_generate_sd_jwt(input, decisions, options).unwrap();
+        let issuers = BTreeMap::from([(ISSUER.to_string(), (JwsAlgorithm::Ed25519, issuer_key))]);
+        (VerifiedSDJwt::try_from_trusted(sd_jwt).unwrap(), holder_key, issuers)
+    }
+
+    fn dcql_query() -> PresentationQuery {
+        PresentationQuery::Dcql(
+            serde_json::from_value(json!({"credentials": [{
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "claims": [{"path": ["given_name"]}]
+            }]}))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn should_verify_signed_encrypted_response() {
+        let header = Map::from_iter([("x5c".to_string(), json!([CERTIFICATE]))]);
+        let mut verifier = PresentationVerifier::new(
+            "x509_san_dns:verifier.example.com",
+            RESPONSE_URI,
+            InMemorySessionStore::default(),
+        )
+        .with_signer(JwsAlgorithm::P256, KEY_PAIR, header)
+        .with_request_uri(REQUEST_URI)
+        .try_with_encryption_key(ES256KeyPair::generate().to_pem().unwrap())
+        .unwrap();
+        let created = verifier.try_create_request(dcql_query(), NOW).unwrap();
+
+        let request_uri = format!("{REQUEST_URI}/{}", created.state);
+        let endpoints = MockEndpoints {
+            request_objects: BTreeMap::from([(
+                request_uri,
+                verifier.request_object(&created.state, NOW).unwrap().unwrap(),
+            )]),
+            ..Default::default()
+        };
+        let client = PresentationClient::new(&endpoints);
+        let request = client.try_resolve_request(&created.uri, &Trust, NOW).unwrap();
+        assert_eq!(request.response_mode, Some(ResponseMode::DirectPostJwt));
+
+        let (credential, holder_key, issuers) = credential();
+        let presentations = request
+            .dcql_query
+            .as_ref()
+            .unwrap()
+            .try_present(&[("pid", &credential)])
+            .unwrap();
+        let response = request
+            .try_respond_dcql(presentations, &[(JwsAlgorithm::P256, &holder_key)], NOW)
+            .unwrap();
+        client.try_send_response(&request, &response).unwrap();
+
+        let body = endpoints.posted.borrow()[0].clone();
+        let verified = verifier.try_verify_response(&body, &issuers, NOW + 5).unwrap();
+        assert_eq!(verified.state, created.state);
+        assert_eq!(verified.claims["pid"][0]["given_name"], "John");
+        assert!(verified.claims["pid"][0].get("family_name").is_none());
+
+        #[derive(serde::Deserialize)]
+        struct Pid {
+            given_name: String,
+            family_name: Option<String>,
+        }
+        let pid = verified.try_typed::<Pid>("pid").unwrap();
+        assert_eq!(pid[0].claims.given_name, "John");
+        assert_eq!(pid[0].claims.family_name, None);
+        assert_eq!(pid[0].provenance.of("/iss"), ClaimProvenance::AlwaysVisible);
+        assert_eq!(pid[0].provenance.of("/given_name"), ClaimProvenance::Disclosed);
+        assert_eq!(pid[0].provenance.of("/family_name"), ClaimProvenance::Withheld);
+        assert!(verified.try_typed::<Pid>("other").unwrap().is_empty());
+
+        // replayed
+        assert!(matches!(
+            verifier.try_verify_response(&body, &issuers, NOW + 5),
+            Err(SdjError::Oid4vpError(Oid4vpError::UnknownState(_)))
+        ));
+    }
+
+    #[test]
+    fn should_reject_unexpected_presentations() {
+        let client_id = format!("redirect_uri:{RESPONSE_URI}");
+        let mut verifier = PresentationVerifier::new(&client_id, RESPONSE_URI, InMemorySessionStore::default());
+        let definition: PresentationDefinition = serde_json::from_value(json!({
+            "id": "pd",
+            "input_descriptors": [{"id": "pid", "constraints": {"fields": [{"path": ["$.given_name"]}]}}]
+        }))
+        .unwrap();
+        let endpoints = MockEndpoints::default();
+        let client = PresentationClient::new(&endpoints);
+        let (credential, holder_key, issuers) = credential();
+
+        let mut respond = |disclosed: &[&str], nonce: Option<&str>, now: u64| {
+            let created = verifier
+                .try_create_request(PresentationQuery::PresentationExchange(definition.clone()), NOW)
+                .unwrap();
+            let request = client.try_resolve_request(&created.uri, &Trust, NOW).unwrap();
+            let presentation = credential
+                .select(disclosed)
+                .unwrap()
+                .try_bind(
+                    JwsAlgorithm::P256,
+                    &holder_key,
+                    &client_id,
+                    nonce.unwrap_or(&request.nonce),
+                    NOW,
+                )
+                .unwrap()
+                .try_serialize()
+                .unwrap();
+            let (_, submission) = definition.try_present(&[("pid", &credential)]).unwrap();
+            let body = encode_form([
+                ("vp_token", presentation.as_str()),
+                ("presentation_submission", &serde_json::to_string(&submission).unwrap()),
+                ("state", &created.state),
+            ]);
+            verifier.try_verify_response(&body, &issuers, now)
+        };
+
+        let verified = respond(&["/given_name"], None, NOW).unwrap();
+        assert_eq!(verified.claims["pid"][0]["given_name"], "John");
+        assert!(matches!(
+            respond(&["/given_name", "/family_name"], None, NOW),
+            Err(SdjError::Oid4vpError(Oid4vpError::ExcessiveDisclosure { path, .. })) if path == "/family_name"
+        ));
+        assert!(matches!(
+            respond(&["/given_name"], Some("another nonce"), NOW),
+            Err(SdjError::InvalidKeyBinding(_))
+        ));
+        assert!(matches!(
+            respond(&["/given_name"], None, NOW + 3600),
+            Err(SdjError::Oid4vpError(Oid4vpError::ExpiredSession(_)))
+        ));
+    }
+
+    #[test]
+    fn should_limit_disclosure_when_required() {
+        let client_id = format!("redirect_uri:{RESPONSE_URI}");
+        let mut verifier = PresentationVerifier::new(&client_id, RESPONSE_URI, InMemorySessionStore::default());
+        let endpoints = MockEndpoints::default();
+        let client = PresentationClient::new(&endpoints);
+        let input = json!({"iss": ISSUER, "address": {"country": "DE", "locality": "Berlin"}});
+        let (credential, holder_key, issuers) = credential_of(input, &["/address/country", "/address"]);
+
+        let mut respond = |limit_disclosure: Option<&str>, disclosed: &[&str]| {
+            let definition: PresentationDefinition = serde_json::from_value(json!({
+                "id": "pd",
+                "input_descriptors": [{"id": "pid", "constraints": {
+                    "limit_disclosure": limit_disclosure,
+                    "fields": [{"path": ["$.address"]}]
+                }}]
+            }))
+            .unwrap();
+            let created = verifier
+                .try_create_request(PresentationQuery::PresentationExchange(definition.clone()), NOW)
+                .unwrap();
+            let request = client.try_resolve_request(&created.uri, &Trust, NOW).unwrap();
+            let disclosed = credential
+                .processed()
+                .disclosures
+                .iter()
+                .filter(|d| disclosed.contains(&d.path.as_str()))
+                .map(|d| d.index)
+                .collect::<Vec<_>>();
+            let presentation = credential
+                .select_disclosures(&disclosed)
+                .try_bind(JwsAlgorithm::P256, &holder_key, &client_id, &request.nonce, NOW)
+                .unwrap()
+                .try_serialize()
+                .unwrap();
+            let (_, submission) = definition.try_present(&[("pid", &credential)]).unwrap();
+            let body = encode_form([
+                ("vp_token", presentation.as_str()),
+                ("presentation_submission", &serde_json::to_string(&submission).unwrap()),
+                ("state", &created.state),
+            ]);
+            verifier.try_verify_response(&body, &issuers, NOW)
+        };
+
+        let verified = respond(None, &["/address", "/address/country"]).unwrap();
+        assert_eq!(verified.claims["pid"][0]["address"]["country"], "DE");
+        let verified = respond(Some("required"), &["/address"]).unwrap();
+        assert!(verified.claims["pid"][0]["address"].get("country").is_none());
+        assert!(matches!(
+            respond(Some("required"), &["/address", "/address/country"]),
+            Err(SdjError::Oid4vpError(Oid4vpError::ExcessiveDisclosure { path, .. })) if path == "/address/country"
+        ));
+    }
+
+    #[test]
+    fn should_reject_disclosures_when_no_claim_requested() {
+        let client_id = format!("redirect_uri:{RESPONSE_URI}");
+        let mut verifier = PresentationVerifier::new(&client_id, RESPONSE_URI, InMemorySessionStore::default());
+        let endpoints = MockEndpoints::default();
+        let client = PresentationClient::new(&endpoints);
+        let (credential, holder_key, issuers) = credential();
+        let dcql = PresentationQuery::Dcql(
+            serde_json::from_value(json!({"credentials": [{"id": "pid", "format": "dc+sd-jwt"}]})).unwrap(),
+        );
+        let definition: PresentationDefinition =
+            serde_json::from_value(json!({"id": "pd", "input_descriptors": [{"id": "pid"}]})).unwrap();
+
+        let mut respond = |query: PresentationQuery, disclosed: &[&str]| {
+            let created = verifier.try_create_request(query.clone(), NOW).unwrap();
+            let request = client.try_resolve_request(&created.uri, &Trust, NOW).unwrap();
+            let presentation = credential
+                .select(disclosed)
+                .unwrap()
+                .try_bind(JwsAlgorithm::P256, &holder_key, &client_id, &request.nonce, NOW)
+                .unwrap()
+                .try_serialize()
+                .unwrap();
+            let body = match &query {
+                PresentationQuery::Dcql(_) => encode_form([
+                    ("vp_token", json!({"pid": [presentation]}).to_string().as_str()),
+                    ("state", &created.state),
+                ]),
+                PresentationQuery::PresentationExchange(definition) => {
+                    let (_, submission) = definition.try_present(&[("pid", &credential)]).unwrap();
+                    encode_form([
+                        ("vp_token", presentation.as_str()),
+                        ("presentation_submission", &serde_json::to_string(&submission).unwrap()),
+                        ("state", &created.state),
+                    ])
+                }
+            };
+            verifier.try_verify_response(&body, &issuers, NOW)
+        };
+
+        for query in [dcql, PresentationQuery::PresentationExchange(definition)] {
+            let verified = respond(query.clone(), &[]).unwrap();
+            assert_eq!(verified.claims["pid"][0]["iss"], ISSUER);
+            assert!(matches!(
+                respond(query, &["/given_name"]),
+                Err(SdjError::Oid4vpError(Oid4vpError::ExcessiveDisclosure { path, .. })) if path == "/given_name"
+            ));
+        }
+    }
+
+    #[test]
+    fn should_verify_transaction_data() {
+        let client_id = format!("redirect_uri:{RESPONSE_URI}");
+        let mut verifier = PresentationVerifier::new(&client_id, RESPONSE_URI, InMemorySessionStore::default());
+        let transaction_data: TransactionData = serde_json::from_value(json!({
+            "type": "payment",
+            "credential_ids": ["pid"],
+            "amount": "42.00 EUR"
+        }))
+        .unwrap();
+        let endpoints = MockEndpoints::default();
+        let client = PresentationClient::new(&endpoints);
+        let (credential, holder_key, issuers) = credential();
+
+        let mut respond = |bind_transaction_data: bool| {
+            let created = verifier
+                .try_create_transaction_request(dcql_query(), std::slice::from_ref(&transaction_data), NOW)
+                .unwrap();
+            let mut request = client.try_resolve_request(&created.uri, &Trust, NOW).unwrap();
+            assert_eq!(request.transaction_data.as_ref().unwrap().len(), 1);
+            if !bind_transaction_data {
+                request.transaction_data = None;
+            }
+            let presentations = request
+                .dcql_query
+                .as_ref()
+                .unwrap()
+                .try_present(&[("pid", &credential)])
+                .unwrap();
+            let response = request
+                .try_respond_dcql(presentations, &[(JwsAlgorithm::P256, &holder_key)], NOW)
+                .unwrap();
+            client.try_send_response(&request, &response).unwrap();
+            let body = endpoints.posted.borrow_mut().pop().unwrap();
+            verifier.try_verify_response(&body, &issuers, NOW)
+        };
+
+        assert_eq!(respond(true).unwrap().claims["pid"][0]["given_name"], "John");
+        assert!(matches!(respond(false), Err(SdjError::InvalidKeyBinding(_))));
+    }
+
+    #[test]
+    fn should_require_signed_requests() {
+        let mut unsigned = PresentationVerifier::new(
+            "x509_san_dns:verifier.example.com",
+            RESPONSE_URI,
+            InMemorySessionStore::default(),
+        );
+        assert!(matches!(
+            unsigned.try_create_request(dcql_query(), NOW),
+            Err(SdjError::Oid4vpError(Oid4vpError::InvalidRequest(_)))
+        ));
+        assert!(unsigned.sessions().get("any").unwrap().is_none());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    core::{
+        json_pointer::visit,
+        processing::{is_ancestor_or_self, ProcessedClaims},
+    },
+    error::{SdjError, SdjResult},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Claims of a verified SD-JWT deserialized into a user type, along with where each of them
+/// comes from
+#[derive(Debug, Clone)]
+pub struct VerifiedClaims<T> {
+    pub claims: T,
+    pub provenance: Provenance,
+}
+
+impl<T: DeserializeOwned> VerifiedClaims<T> {
+    pub(crate) fn try_from_processed(processed: ProcessedClaims) -> SdjResult<Self> {
+        let provenance = Provenance::new(&processed);
+        Self::try_new(processed.claims, provenance)
+    }
+
+    pub(crate) fn try_new(claims: Value, provenance: Provenance) -> SdjResult<Self> {
+        let claims = serde_json::from_value(claims).map_err(|e| SdjError::UnexpectedClaims(e.to_string()))?;
+        Ok(Self { claims, provenance })
+    }
+}
+
+/// Where a claim of a verified SD-JWT comes from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClaimProvenance {
+    /// In clear in the Issuer-signed JWT
+    AlwaysVisible,
+    /// Revealed by its own disclosure or by the one of a claim it is nested in
+    Disclosed,
+    /// Absent while the object or array which would contain it has digests the Holder did not
+    /// disclose. Since these digests may be decoys, the claim might as well have never been issued.
+    Withheld,
+    /// Absent and nothing in the Issuer-signed JWT stands for it
+    NotIssued,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    /// Provenance of every claim & array item which is present, by Json pointer
+    pub claims: BTreeMap<String, ClaimProvenance>,
+    /// Json pointers of the objects & arrays where claims might have been withheld
+    pub withheld_in: Vec<String>,
+}
+
+impl Provenance {
+    pub(crate) fn new(processed: &ProcessedClaims) -> Self {
+        let mut claims = BTreeMap::new();
+        visit(&processed.claims, "", &mut |path, _| {
+            let disclosed = processed.disclosures.iter().any(|d| is_ancestor_or_self(&d.path, path));
+            let provenance = if disclosed {
+                ClaimProvenance::Disclosed
+            } else {
+                ClaimProvenance::AlwaysVisible
+            };
+            claims.insert(path.to_string(), provenance);
+        });
+        Self {
+            claims,
+            withheld_in: processed.withheld_in.clone(),
+        }
+    }
+
+    /// Provenance of the claim at this Json pointer, present or not. An absent claim is only
+    /// [ClaimProvenance::Withheld] when the closest object or array containing it is present and has
+    /// undisclosed digests.
+    pub fn of(&self, pointer: &str) -> ClaimProvenance {
+        if let Some(provenance) = self.claims.get(pointer) {
+            return *provenance;
+        }
+        let mut path = pointer;
+        while let Some((parent, _)) = path.rsplit_once('/') {
+            if parent.is_empty() || self.claims.contains_key(parent) {
+                return if self.withheld_in.iter().any(|w| w == parent) {
+                    ClaimProvenance::Withheld
+                } else {
+                    ClaimProvenance::NotIssued
+                };
+            }
+            path = parent;
+        }
+        ClaimProvenance::NotIssued
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::prelude::{Issuer, IssuerOptions, Jwk, JwsAlgorithm, KeyBindingCheck, VerifiedSDJwt, Verifier};
+    use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
+    use serde_json::json;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct IdToken {
+        iss: String,
+        given_name: String,
+        family_name: Option<String>,
+        email: Option<String>,
+        address: Address,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Address {
+        street_address: Option<String>,
+        country: String,
+    }
+
+    #[test]
+    fn should_verify_typed_claims() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let input = json!({
+            "iss": "https://example.com/issuer",
+            "given_name": "John",
+            "family_name": "Doe",
+            "address": {"street_address": "123 Main St", "country": "US"},
+        });
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(
+                input,
+                &["/given_name", "/family_name", "/address/street_address", "/address"],
+                IssuerOptions::default(),
+            )
+            .unwrap();
+        let presentation = VerifiedSDJwt::try_from_trusted(sd_jwt)
+            .unwrap()
+            .select(["/given_name", "/address"])
+            .unwrap()
+            .try_serialize()
+            .unwrap();
+
+        let verified = Verifier::verify_typed::<IdToken>(&presentation, JwsAlgorithm::Ed25519, &issuer_key).unwrap();
+        assert_eq!(verified.claims.iss, "https://example.com/issuer");
+        assert_eq!(verified.claims.given_name, "John");
+        assert_eq!(verified.claims.family_name, None);
+        assert_eq!(verified.claims.email, None);
+        assert_eq!(verified.claims.address.street_address, None);
+        assert_eq!(verified.claims.address.country, "US");
+
+        let provenance = &verified.provenance;
+        assert_eq!(provenance.of("/iss"), ClaimProvenance::AlwaysVisible);
+        assert_eq!(provenance.of("/given_name"), ClaimProvenance::Disclosed);
+        assert_eq!(provenance.of("/address/country"), ClaimProvenance::Disclosed);
+        assert_eq!(provenance.of("/family_name"), ClaimProvenance::Withheld);
+        assert_eq!(provenance.of("/address/street_address"), ClaimProvenance::Withheld);
+        assert_eq!(provenance.of("/address/country/code"), ClaimProvenance::NotIssued);
+    }
+
+    #[test]
+    fn should_verify_bound_typed_claims() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+        let options = IssuerOptions {
+            cnf: Some(Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key).unwrap()),
+            ..Default::default()
+        };
+        let input = json!({
+            "iss": "https://example.com/issuer",
+            "given_name": "John",
+            "email": "johndoe@example.com",
+            "address": {"country": "US"},
+        });
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(input, &["/given_name", "/email"], options)
+            .unwrap();
+        let check = KeyBindingCheck {
+            aud: "https://verifier.example.com",
+            nonce: "n-0S6_WzA2Mj",
+            now: 1_700_000_000,
+            max_age: 300,
+            leeway: 60,
+            transaction_data: &[],
+        };
+        let presentation = VerifiedSDJwt::try_from_trusted(sd_jwt)
+            .unwrap()
+            .select(["/given_name"])
+            .unwrap()
+            .try_bind(JwsAlgorithm::P256, &holder_key, check.aud, check.nonce, check.now)
+            .unwrap()
+            .try_serialize()
+            .unwrap();
+
+        let verified =
+            Verifier::verify_bound_typed::<IdToken>(&presentation, JwsAlgorithm::Ed25519, &issuer_key, &check).unwrap();
+        assert_eq!(verified.claims.given_name, "John");
+        assert_eq!(verified.claims.email, None);
+        assert_eq!(verified.claims.address.country, "US");
+        assert_eq!(verified.provenance.of("/given_name"), ClaimProvenance::Disclosed);
+        assert_eq!(verified.provenance.of("/email"), ClaimProvenance::Withheld);
+        assert_eq!(
+            verified.provenance.of("/address/country"),
+            ClaimProvenance::AlwaysVisible
+        );
+
+        // not bound to this Verifier
+        let check = KeyBindingCheck {
+            aud: "https://other.example.com",
+            ..check
+        };
+        assert!(matches!(
+            Verifier::verify_bound_typed::<IdToken>(&presentation, JwsAlgorithm::Ed25519, &issuer_key, &check),
+            Err(SdjError::InvalidKeyBinding(_))
+        ));
+    }
+
+    #[test]
+    fn should_tell_never_issued_claims() {
+        let mut issuer = Issuer::try_new().unwrap();
+        let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+            .unwrap()
+            .public_key()
+            .to_pem();
+        let input = json!({"given_name": "John", "address": {"country": "US"}});
+        let sd_jwt = issuer
+            .try_generate_sd_jwt(input, &["/given_name"], IssuerOptions::default())
+            .unwrap();
+
+        let verified = Verifier::verify_typed::<serde_json::Value>(
+            &sd_jwt.try_serialize().unwrap(),
+            JwsAlgorithm::Ed25519,
+            &issuer_key,
+        )
+        .unwrap();
+        assert_eq!(verified.provenance.of("/given_name"), ClaimProvenance::Disclosed);
+        assert_eq!(verified.provenance.of("/address/region"), ClaimProvenance::NotIssued);
+        assert!(matches!(
+            Verifier::verify_typed::<IdToken>(&sd_jwt.try_serialize().unwrap(), JwsAlgorithm::Ed25519, &issuer_key),
+            Err(SdjError::UnexpectedClaims(_))
+        ));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{core::oid4vp::AuthorizationRequest, error::SdjResult};
+use std::collections::BTreeMap;
+
+/// An authorization request waiting for its response, identified by its `state`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    pub state: String,
+    pub request: AuthorizationRequest,
+    /// The signed request, served at the `request_uri` when passed by reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_object: Option<String>,
+    /// In seconds since the epoch
+    pub expires_at: u64,
+}
+
+impl Session {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Persists the pending sessions of a [crate::prelude::PresentationVerifier], e.g. so that they
+/// are shared by the endpoints serving requests & receiving responses
+pub trait SessionStore {
+    /// Saves a new session. Saving a session with the same `state` overwrites it.
+    fn save(&mut self, session: Session) -> SdjResult<()>;
+
+    /// Fetches a session by its `state`
+    fn get(&self, state: &str) -> SdjResult<Option<Session>>;
+
+    /// Removes a session and returns it, if it was in the store
+    fn remove(&mut self, state: &str) -> SdjResult<Option<Session>>;
+}
+
+/// A non-persistent [SessionStore]
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySessionStore {
+    sessions: BTreeMap<String, Session>,
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn save(&mut self, session: Session) -> SdjResult<()> {
+        self.sessions.insert(session.state.clone(), session);
+        Ok(())
+    }
+
+    fn get(&self, state: &str) -> SdjResult<Option<Session>> {
+        Ok(self.sessions.get(state).cloned())
+    }
+
+    fn remove(&mut self, state: &str) -> SdjResult<Option<Session>> {
+        Ok(self.sessions.remove(state))
+    }
+}
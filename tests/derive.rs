@@ -0,0 +1,7 @@
+//! Checks that `#[derive(SelectivelyDisclosable)]` rejects, at compile time, claims which can't be
+//! selectively disclosable.
+
+#[test]
+fn should_reject_invalid_claims() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
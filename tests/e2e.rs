@@ -1,7 +1,9 @@
-use jwt_simple::prelude::Ed25519KeyPair;
+use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
 use serde_json::json;
 
-use selective_disclosure_jwt::prelude::{Holder, Issuer, IssuerOptions, JwsAlgorithm};
+use selective_disclosure_jwt::prelude::{
+    DcqlQuery, Holder, Issuer, IssuerOptions, Jwk, JwsAlgorithm, KeyBindingCheck, SdjError, Selection, Verifier,
+};
 
 #[test]
 fn e2e_test() {
@@ -87,5 +89,172 @@ fn e2e() -> Result<(), Box<dyn std::error::Error>> {
     let serialized_sd_jwt = holder_sd_jwt.try_serialize()?;
     println!("== Holder == SD-JWT: {serialized_sd_jwt}");
 
+    // === Verifier ===
+    let claims = Verifier::verify(&serialized_sd_jwt, JwsAlgorithm::Ed25519, &issuer_pk)?;
+    println!("== Verifier == Claims: {claims}");
+    assert_eq!(
+        claims,
+        json!({
+            "sub": "user_42",
+            "iss": "https://example.com/issuer",
+            "iat": 1683000000,
+            "exp": 1883000000,
+            "given_name": "John",
+            "family_name": "Doe",
+            "email": "johndoe@example.com",
+            "nationalities": ["US", "DE"]
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn e2e_select_claims_test() {
+    e2e_select_claims().unwrap();
+}
+
+fn e2e_select_claims() -> Result<(), Box<dyn std::error::Error>> {
+    let input = json!({
+        "iss": "https://example.com/issuer",
+        "given_name": "John",
+        "family_name": "Doe",
+        "address": {
+          "street_address": "123 Main St",
+          "country": "US"
+        },
+        "nationalities": ["US", "DE"]
+    });
+    let decisions = &[
+        "/given_name",
+        "/family_name",
+        "/address/street_address",
+        "/address",
+        "/nationalities/0",
+        "/nationalities/1",
+    ];
+    let mut issuer = Issuer::try_new()?;
+    let sd_jwt = issuer.try_generate_sd_jwt(input, decisions, IssuerOptions::default())?;
+    let serialized_sd_jwt = sd_jwt.try_serialize()?;
+
+    let issuer_kp = Ed25519KeyPair::from_pem(&issuer.get_signature_key())?;
+    let issuer_pk = issuer_kp.public_key().to_pem();
+
+    let selection = Selection::none()
+        .claim("given_name")
+        .subtree("/address")
+        .matching(|_, value| value == "DE");
+    let holder_sd_jwt = Holder::select_claims(&serialized_sd_jwt, &selection, JwsAlgorithm::Ed25519, &issuer_pk)?;
+    assert_eq!(holder_sd_jwt.disclosures.len(), 4);
+
+    let all = Holder::select_claims(&serialized_sd_jwt, &Selection::all(), JwsAlgorithm::Ed25519, &issuer_pk)?;
+    assert_eq!(all.disclosures.len(), decisions.len());
+
+    Ok(())
+}
+
+#[test]
+fn e2e_dcql_test() {
+    e2e_dcql().unwrap();
+}
+
+fn e2e_dcql() -> Result<(), Box<dyn std::error::Error>> {
+    let input = json!({
+        "iss": "https://example.com/issuer",
+        "vct": "https://credentials.example.com/identity_credential",
+        "given_name": "John",
+        "family_name": "Doe",
+        "address": {
+          "street_address": "123 Main St",
+          "country": "US"
+        }
+    });
+    let decisions = &["/given_name", "/family_name", "/address/country", "/address"];
+    let holder_key = ES256KeyPair::generate().to_pem()?;
+    let options = IssuerOptions {
+        cnf: Some(Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key)?),
+        ..Default::default()
+    };
+    let mut issuer = Issuer::try_new()?;
+    let sd_jwt = issuer.try_generate_sd_jwt(input, decisions, options)?;
+    let issuer_kp = Ed25519KeyPair::from_pem(&issuer.get_signature_key())?;
+    let issuer_pk = issuer_kp.public_key().to_pem();
+
+    // === Verifier ===
+    let check = KeyBindingCheck {
+        aud: "x509_san_dns:verifier.example.com",
+        nonce: "n-0S6_WzA2Mj",
+        now: 1_700_000_000,
+        max_age: 300,
+        leeway: 60,
+        transaction_data: &[],
+    };
+    let query: DcqlQuery = serde_json::from_value(json!({
+        "credentials": [{
+            "id": "pid",
+            "format": "dc+sd-jwt",
+            "meta": {"vct_values": ["https://credentials.example.com/identity_credential"]},
+            "claims": [{"path": ["given_name"]}, {"path": ["address", "country"]}]
+        }]
+    }))?;
+
+    // === Holder ===
+    let verified = Holder::verify(sd_jwt, JwsAlgorithm::Ed25519, &issuer_pk)?;
+    let evaluation = query.evaluate([&verified]);
+    assert!(evaluation.is_satisfied());
+    let presentations = query.try_present(&[("pid", &verified)])?;
+    let mut vp_token = serde_json::Map::new();
+    for (id, presentations) in presentations {
+        let presentations = presentations
+            .into_iter()
+            .map(|p| {
+                p.try_bind(JwsAlgorithm::P256, &holder_key, check.aud, check.nonce, check.now)?
+                    .try_serialize()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        vp_token.insert(id, json!(presentations));
+    }
+
+    // === Verifier ===
+    let vp_token = json!(vp_token);
+    let claims = Verifier::verify_dcql(&vp_token, &query, JwsAlgorithm::Ed25519, &issuer_pk, &check)?;
+    let mut expected = json!({
+        "iss": "https://example.com/issuer",
+        "vct": "https://credentials.example.com/identity_credential",
+        "given_name": "John",
+        "address": {"street_address": "123 Main St", "country": "US"}
+    });
+    expected["cnf"] = verified.payload()["cnf"].clone();
+    assert_eq!(claims["pid"], vec![expected]);
+
+    // bound to another Verifier
+    let other = KeyBindingCheck {
+        aud: "x509_san_dns:other.example.com",
+        ..check.clone()
+    };
+    assert!(matches!(
+        Verifier::verify_dcql(&vp_token, &query, JwsAlgorithm::Ed25519, &issuer_pk, &other),
+        Err(SdjError::InvalidKeyBinding(_))
+    ));
+
+    // not bound, which is only accepted when the query doesn't require it
+    let unbound = query.try_present(&[("pid", &verified)])?["pid"][0].try_serialize()?;
+    let vp_token = json!({ "pid": [unbound] });
+    assert!(matches!(
+        Verifier::verify_dcql(&vp_token, &query, JwsAlgorithm::Ed25519, &issuer_pk, &check),
+        Err(SdjError::InvalidKeyBinding(_))
+    ));
+    let mut unbound_query = query.clone();
+    unbound_query.credentials[0].require_cryptographic_holder_binding = false;
+    Verifier::verify_dcql(&vp_token, &unbound_query, JwsAlgorithm::Ed25519, &issuer_pk, &check)?;
+
+    // a presentation which does not disclose what was requested
+    let undisclosed = verified.select_claims(&Selection::none()).try_serialize()?;
+    let vp_token = json!({ "pid": [undisclosed] });
+    assert!(matches!(
+        Verifier::verify_dcql(&vp_token, &unbound_query, JwsAlgorithm::Ed25519, &issuer_pk, &check),
+        Err(SdjError::DcqlError(_))
+    ));
+
     Ok(())
 }
@@ -0,0 +1,179 @@
+//! Runs the Issuer, Holder & Verifier against the test cases of the SD-JWT reference
+//! implementation, expected unchanged in `tests/testcases` i.e. one `<name>/specification.yml`
+//! per test case, as in the `tests/testcases` directory of
+//! https://github.com/openwallet-foundation-labs/sd-jwt-python along with its license & the
+//! upstream commit they were taken from.
+//!
+//! The user claims are read with [DisclosureFrame::try_from_yaml] then issued with the resulting
+//! frame. The Holder discloses the claims of `holder_disclosed_claims` the way the reference
+//! implementation does, and the Verifier has to end up with `expect_verified_user_claims`. The
+//! reference implementation derives its salts & decoys from a seeded Python PRNG, hence the issued
+//! SD-JWTs are not compared byte for byte: `tests/vectors.rs` does that with fixed salts.
+
+use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair};
+use serde_json::{json, Value};
+
+use selective_disclosure_jwt::prelude::{
+    ClaimVisibility, DisclosureFrame, Holder, Inspection, Issuer, IssuerOptions, Jwk, JwsAlgorithm, KeyBindingCheck,
+    Selection, VerifiedSDJwt, Verifier,
+};
+
+const AUD: &str = "https://verifier.example.org";
+const NONCE: &str = "1234567890";
+const NOW: u64 = 1_700_000_000;
+
+#[derive(Debug, serde::Deserialize)]
+struct Specification {
+    user_claims: serde_yaml::Value,
+    holder_disclosed_claims: Value,
+    expect_verified_user_claims: Value,
+    #[serde(default)]
+    key_binding: bool,
+}
+
+#[test]
+#[ignore = "needs the test cases of the reference implementation in tests/testcases"]
+fn should_pass_reference_test_cases() {
+    let mut cases = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testcases"))
+        .expect("the test cases of the reference implementation")
+        .map(|entry| entry.unwrap().path().join("specification.yml"))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    cases.sort();
+    assert!(!cases.is_empty());
+
+    for path in cases {
+        let name = path
+            .parent()
+            .unwrap()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let spec: Specification = serde_yaml::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        check(&name, &spec);
+    }
+}
+
+fn check(name: &str, spec: &Specification) {
+    let (mut claims, frame) = DisclosureFrame::try_from_yaml(&serde_yaml::to_string(&spec.user_claims).unwrap())
+        .unwrap_or_else(|e| panic!("{name}: {e}"));
+    let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+    let mut expected = spec.expect_verified_user_claims.clone();
+    if spec.key_binding {
+        let cnf = json!({ "jwk": Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key).unwrap() });
+        claims["cnf"] = cnf.clone();
+        expected["cnf"] = cnf;
+    }
+
+    let mut issuer = Issuer::try_new().unwrap();
+    let sd_jwt = issuer
+        .try_generate_sd_jwt_framed(claims.clone(), &frame, IssuerOptions::default())
+        .unwrap_or_else(|e| panic!("{name}: {e}"));
+    let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+        .unwrap()
+        .public_key()
+        .to_pem();
+
+    let sd_jwt = sd_jwt.try_serialize().unwrap().parse().unwrap();
+    let credential =
+        VerifiedSDJwt::try_new(sd_jwt, JwsAlgorithm::Ed25519, &issuer_key).unwrap_or_else(|e| panic!("{name}: {e}"));
+    assert_eq!(credential.claims(), &claims, "{name}");
+
+    let mut disclosed = vec![];
+    select(
+        &Holder::inspect(&credential),
+        &claims,
+        &spec.holder_disclosed_claims,
+        "",
+        &mut disclosed,
+    );
+    let mut sd_jwt = credential.into_sd_jwt();
+    sd_jwt.disclosures.retain(|d| disclosed.contains(&*d.hash().unwrap()));
+    let presented = VerifiedSDJwt::try_from_trusted(sd_jwt).unwrap_or_else(|e| panic!("{name}: {e}"));
+    let selection = presented.select_claims(&Selection::all());
+    let presented = if spec.key_binding {
+        let presentation = selection
+            .try_bind(JwsAlgorithm::P256, &holder_key, AUD, NONCE, NOW)
+            .unwrap()
+            .try_serialize()
+            .unwrap();
+        let check = KeyBindingCheck {
+            aud: AUD,
+            nonce: NONCE,
+            now: NOW,
+            max_age: 300,
+            leeway: 60,
+            transaction_data: &[],
+        };
+        Verifier::verify_bound(&presentation, JwsAlgorithm::Ed25519, &issuer_key, &check)
+    } else {
+        Verifier::verify(&selection.try_serialize().unwrap(), JwsAlgorithm::Ed25519, &issuer_key)
+    };
+    assert_eq!(presented.unwrap_or_else(|e| panic!("{name}: {e}")), expected, "{name}");
+}
+
+/// Digests of the selectively disclosable claims picked by `holder_disclosed_claims`, which
+/// mirrors the claims, the way the reference implementation's Holder reads it: an object member is
+/// disclosed when its mirror is truthy, an array item unless its mirror is `false` or missing, and
+/// `true` stands for an empty mirror except for an array whose items are then all disclosed
+fn select(inspection: &Inspection, claims: &Value, mirror: &Value, pointer: &str, disclosed: &mut Vec<String>) {
+    let digest = |pointer: &str| match &inspection.find(pointer)?.visibility {
+        ClaimVisibility::SelectivelyDisclosable { digest } => Some(digest.clone()),
+        _ => None,
+    };
+    match claims {
+        Value::Object(members) => {
+            let mirror = match mirror {
+                Value::Bool(true) => json!({}),
+                Value::Object(_) => mirror.clone(),
+                _ => return,
+            };
+            for (key, value) in members {
+                let pointer = format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+                let mirror = mirror.get(key).unwrap_or(&Value::Null);
+                if let Some(digest) = digest(&pointer).filter(|_| is_truthy(mirror)) {
+                    disclosed.push(digest);
+                }
+                select(inspection, value, mirror, &pointer, disclosed);
+            }
+        }
+        Value::Array(items) => {
+            let mirror = match mirror {
+                Value::Bool(true) => vec![Value::Bool(true); items.len()],
+                Value::Array(mirror) => mirror.clone(),
+                _ => return,
+            };
+            for (i, item) in items.iter().enumerate() {
+                let pointer = format!("{pointer}/{i}");
+                let mirror = mirror.get(i).unwrap_or(&Value::Null);
+                let Some(digest) = digest(&pointer) else {
+                    select(inspection, item, mirror, &pointer, disclosed);
+                    continue;
+                };
+                if matches!(mirror, Value::Null | Value::Bool(false)) {
+                    continue;
+                }
+                disclosed.push(digest);
+                let mirror = match (mirror, item) {
+                    (Value::Bool(true), Value::Array(_)) => json!([]),
+                    _ => mirror.clone(),
+                };
+                select(inspection, item, &mirror, &pointer, disclosed);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Like Python's truthiness
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(members) => !members.is_empty(),
+    }
+}
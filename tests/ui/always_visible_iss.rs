@@ -0,0 +1,11 @@
+use selective_disclosure_jwt::prelude::SelectivelyDisclosable;
+
+#[derive(serde::Serialize, SelectivelyDisclosable)]
+struct IdToken {
+    #[sd]
+    iss: String,
+    #[sd]
+    given_name: String,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use selective_disclosure_jwt::prelude::SelectivelyDisclosable;
+
+#[derive(serde::Serialize, SelectivelyDisclosable)]
+struct Address {
+    #[sd]
+    country: String,
+}
+
+#[derive(serde::Serialize, SelectivelyDisclosable)]
+struct IdToken {
+    #[sd]
+    #[serde(flatten)]
+    address: Address,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use selective_disclosure_jwt::prelude::SelectivelyDisclosable;
+
+#[derive(serde::Serialize, SelectivelyDisclosable)]
+struct Address {
+    #[sd]
+    #[serde(rename = "...")]
+    digest: String,
+}
+
+#[derive(serde::Serialize, SelectivelyDisclosable)]
+struct IdToken {
+    #[sd(recursive)]
+    address: Address,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use selective_disclosure_jwt::prelude::SelectivelyDisclosable;
+
+#[derive(serde::Serialize, SelectivelyDisclosable)]
+struct IdToken {
+    given_name: String,
+    #[serde(rename = "_sd")]
+    digests: Vec<String>,
+}
+
+fn main() {}
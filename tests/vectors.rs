@@ -0,0 +1,224 @@
+//! Runs the Issuer, Holder & Verifier against every test vector in `tests/vectors`.
+//!
+//! The vectors are written for this crate: input claims, selectively disclosable claims, fixed
+//! salts, expected disclosures & payload, claims disclosed by the Holder and claims expected by the
+//! Verifier. They are not the test cases of the SD-JWT reference implementation, which
+//! `tests/testcases.rs` runs. Disclosures are encoded like the reference implementation does i.e.
+//! `json.dumps` with `", "` separators, sorted keys & UTF-8 preserved.
+//!
+//! The Issuer takes its salts from the test vector so that its output can be compared byte for byte,
+//! except for the order of `_sd` digests which is up to the Issuer.
+
+use jwt_simple::prelude::{ES256KeyPair, Ed25519KeyPair, EdDSAKeyPairLike, JWTClaims};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use selective_disclosure_jwt::prelude::{
+    FixedSaltSource, Issuer, Jwk, JwsAlgorithm, KeyBindingCheck, SDJwt, VerifiedSDJwt, Verifier,
+};
+
+const AUD: &str = "https://verifier.example.org";
+const NONCE: &str = "1234567890";
+const NOW: u64 = 1_700_000_000;
+
+#[derive(Debug, serde::Deserialize)]
+struct TestCase {
+    user_claims: Value,
+    sd_claims: Vec<String>,
+    salts: Vec<String>,
+    disclosures: Vec<String>,
+    payload: Value,
+    key_binding: bool,
+    /// Issued by another implementation in a way this Issuer doesn't e.g. with salts of various sizes
+    #[serde(default)]
+    verify_only: bool,
+    holder_disclosed_claims: Vec<String>,
+    presented_claims: Value,
+}
+
+#[test]
+fn should_pass_test_vectors() {
+    let mut cases = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/vectors"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    cases.sort();
+    assert!(!cases.is_empty());
+
+    for path in cases {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let case: TestCase = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(case.sd_claims.len(), case.salts.len(), "{name}");
+        assert_eq!(case.sd_claims.len(), case.disclosures.len(), "{name}");
+
+        if !case.verify_only {
+            check_issuer(&name, &case);
+        }
+        check_presentation(&name, &case);
+    }
+}
+
+/// Issues the input claims with the salts of the test vector then checks that the disclosures &
+/// payload are the expected ones, and that every claim is disclosed back
+fn check_issuer(name: &str, case: &TestCase) {
+    let salt_size = base64_simd::URL_SAFE_NO_PAD
+        .decode_to_vec(&case.salts[0])
+        .unwrap()
+        .len();
+    let salts = FixedSaltSource::insecure_try_new(&case.salts).unwrap();
+    let mut issuer = Issuer::try_new()
+        .unwrap()
+        .with_salt_source(salts)
+        .with_salt_size(salt_size);
+    let sd_jwt = issuer
+        .issue(case.user_claims.clone())
+        .disclose_each(&case.sd_claims)
+        .try_sign()
+        .unwrap();
+    let disclosures = sd_jwt
+        .disclosures
+        .iter()
+        .map(|d| d.build().unwrap())
+        .collect::<Vec<_>>();
+    // disclosures nesting digests differ by the order of these, and so do the digests of them
+    let (issued, expected) = (digest_map(&disclosures), digest_map(&case.disclosures));
+    for (disclosure, expected_disclosure) in disclosures.iter().zip(&case.disclosures) {
+        if disclosure != expected_disclosure {
+            assert_eq!(
+                resolve_digests(&decode(disclosure), &issued),
+                resolve_digests(&decode(expected_disclosure), &expected),
+                "{name}"
+            );
+        }
+    }
+
+    let payload = read_payload(sd_jwt.jws.as_ref());
+    assert_eq!(
+        resolve_digests(&payload, &issued),
+        resolve_digests(&case.payload, &expected),
+        "{name}"
+    );
+
+    let issuer_key = Ed25519KeyPair::from_pem(&issuer.get_signature_key())
+        .unwrap()
+        .public_key()
+        .to_pem();
+    let claims = Verifier::verify(&sd_jwt.try_serialize().unwrap(), JwsAlgorithm::Ed25519, &issuer_key).unwrap();
+    assert_eq!(claims, case.user_claims, "{name}");
+}
+
+/// Signs the expected payload, has the Holder select the claims to disclose then verifies them
+fn check_presentation(name: &str, case: &TestCase) {
+    let holder_key = ES256KeyPair::generate().to_pem().unwrap();
+    let mut payload = case.payload.clone();
+    if case.key_binding {
+        let cnf = Jwk::try_from_key_pair_pem(JwsAlgorithm::P256, &holder_key).unwrap();
+        payload["cnf"] = json!({ "jwk": cnf });
+    }
+    let issuer_key = Ed25519KeyPair::generate();
+    let jws = issuer_key
+        .sign(JWTClaims {
+            issued_at: None,
+            expires_at: None,
+            invalid_before: None,
+            issuer: None,
+            subject: None,
+            audiences: None,
+            jwt_id: None,
+            nonce: None,
+            custom: payload,
+        })
+        .unwrap();
+    let issuer_key = issuer_key.public_key().to_pem();
+
+    let sd_jwt = format!("{jws}~{}~", case.disclosures.join("~"))
+        .parse::<SDJwt>()
+        .unwrap();
+    // disclosures are encoded the way the Issuer would
+    for ((disclosure, expected), salt) in sd_jwt.disclosures.iter().zip(&case.disclosures).zip(&case.salts) {
+        assert_eq!(&disclosure.build().unwrap(), expected, "{name}");
+        assert!(disclosure.to_string().starts_with(&format!("[\"{salt}\"")), "{name}");
+    }
+
+    let credential =
+        VerifiedSDJwt::try_new(sd_jwt, JwsAlgorithm::Ed25519, &issuer_key).unwrap_or_else(|e| panic!("{name}: {e}"));
+    assert_eq!(credential.claims(), &with_cnf(&case.user_claims, &credential), "{name}");
+
+    let selection = credential.select(&case.holder_disclosed_claims).unwrap();
+    let presented = if case.key_binding {
+        let presentation = selection
+            .try_bind(JwsAlgorithm::P256, &holder_key, AUD, NONCE, NOW)
+            .unwrap()
+            .try_serialize()
+            .unwrap();
+        let check = KeyBindingCheck {
+            aud: AUD,
+            nonce: NONCE,
+            now: NOW,
+            max_age: 300,
+            leeway: 60,
+            transaction_data: &[],
+        };
+        Verifier::verify_bound(&presentation, JwsAlgorithm::Ed25519, &issuer_key, &check).unwrap()
+    } else {
+        let presentation = selection.try_serialize().unwrap();
+        Verifier::verify(&presentation, JwsAlgorithm::Ed25519, &issuer_key).unwrap()
+    };
+    assert_eq!(presented, with_cnf(&case.presented_claims, &credential), "{name}");
+}
+
+fn read_payload(jws: &str) -> Value {
+    let payload = jws.split('.').nth(1).unwrap();
+    serde_json::from_slice(&base64_simd::URL_SAFE_NO_PAD.decode_to_vec(payload).unwrap()).unwrap()
+}
+
+/// Expected claims along with the `cnf` the test added to the payload, if any
+fn with_cnf(claims: &Value, credential: &VerifiedSDJwt) -> Value {
+    let mut claims = claims.clone();
+    if let Some(cnf) = credential.payload().get("cnf") {
+        claims["cnf"] = cnf.clone();
+    }
+    claims
+}
+
+fn decode(disclosure: &str) -> Value {
+    serde_json::from_slice(&base64_simd::URL_SAFE_NO_PAD.decode_to_vec(disclosure).unwrap()).unwrap()
+}
+
+/// Decoded disclosures by digest
+fn digest_map(disclosures: &[String]) -> HashMap<String, Value> {
+    disclosures
+        .iter()
+        .map(|d| {
+            let digest = base64_simd::URL_SAFE_NO_PAD.encode_to_string(Sha256::digest(d.as_bytes()));
+            (digest, decode(d))
+        })
+        .collect()
+}
+
+/// Replaces every digest by the disclosure it stands for, then sorts `_sd` since the order of the
+/// digests is up to the Issuer. Decoys are left as they are.
+fn resolve_digests(value: &Value, disclosures: &HashMap<String, Value>) -> Value {
+    let resolve = |digest: &Value| match digest.as_str().and_then(|d| disclosures.get(d)) {
+        Some(disclosure) => resolve_digests(disclosure, disclosures),
+        None => digest.clone(),
+    };
+    match value {
+        Value::Object(object) => object
+            .iter()
+            .map(|(k, v)| match (k.as_str(), v) {
+                ("_sd", Value::Array(digests)) => {
+                    let mut digests = digests.iter().map(resolve).collect::<Vec<_>>();
+                    digests.sort_by_key(|d| d.to_string());
+                    (k.clone(), Value::Array(digests))
+                }
+                ("...", digest) => (k.clone(), resolve(digest)),
+                _ => (k.clone(), resolve_digests(v, disclosures)),
+            })
+            .collect(),
+        Value::Array(items) => items.iter().map(|i| resolve_digests(i, disclosures)).collect(),
+        v => v.clone(),
+    }
+}